@@ -0,0 +1,37 @@
+use std::{panic, sync::OnceLock};
+use teloxide::prelude::*;
+use tokio::sync::mpsc;
+
+static ALERTS: OnceLock<mpsc::UnboundedSender<String>> = OnceLock::new();
+
+/// Sends `text` to `admin_chat_id`. A no-op when no admin chat is configured.
+pub fn report(text: impl Into<String>) {
+  if let Some(alerts) = ALERTS.get() {
+    let _ = alerts.send(text.into());
+  }
+}
+
+/// Forwards [`report`]s and panics to the admin chat, so problems show up without tailing logs.
+pub fn spawn(bot: Bot, chat_id: Option<i64>) {
+  let Some(chat_id) = chat_id.map(ChatId) else {
+    return;
+  };
+  let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+  if ALERTS.set(tx).is_err() {
+    return;
+  }
+
+  let default_hook = panic::take_hook();
+  panic::set_hook(Box::new(move |info| {
+    default_hook(info);
+    report(format!("💥 {info}"));
+  }));
+
+  tokio::spawn(async move {
+    while let Some(text) = rx.recv().await {
+      if let Err(err) = bot.send_message(chat_id, text).await {
+        log::error!("Could not send an alert to {chat_id}: {err}");
+      }
+    }
+  });
+}
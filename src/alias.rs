@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+/// Rewrites `/alias@bot args` into `/target@bot args` when `alias` is configured.
+pub fn resolve(text: &str, aliases: &HashMap<String, String>) -> String {
+  let Some(command) = text.strip_prefix('/') else {
+    return text.to_string();
+  };
+  let (head, rest) = command
+    .split_once(char::is_whitespace)
+    .unwrap_or((command, ""));
+  let (name, mention) = head
+    .split_once('@')
+    .map_or((head, None), |(name, bot)| (name, Some(bot)));
+
+  let Some(target) = aliases.get(&name.to_lowercase()) else {
+    return text.to_string();
+  };
+  // the target may carry its own arguments, e.g. "w": "watch abc"
+  let target = target.trim_start_matches('/');
+  let (target, target_args) = target.split_once(' ').unwrap_or((target, ""));
+
+  let mut resolved = format!("/{target}");
+  if let Some(bot) = mention {
+    resolved.push('@');
+    resolved.push_str(bot);
+  }
+  for args in [target_args, rest] {
+    if !args.is_empty() {
+      resolved.push(' ');
+      resolved.push_str(args);
+    }
+  }
+  resolved
+}
+
+/// Lines for /help, sorted by alias.
+pub fn describe(aliases: &HashMap<String, String>) -> String {
+  let mut aliases: Vec<_> = aliases.iter().collect();
+  aliases.sort();
+  aliases
+    .into_iter()
+    .map(|(alias, target)| format!("/{alias} → /{}", target.trim_start_matches('/')))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn aliases() -> HashMap<String, String> {
+    HashMap::from([
+      ("s".to_string(), "status".to_string()),
+      ("dl".to_string(), "/list downloading".to_string()),
+    ])
+  }
+
+  #[test]
+  fn resolves_aliases() {
+    let aliases = aliases();
+    assert_eq!(resolve("/s", &aliases), "/status");
+    assert_eq!(resolve("/S@qbit_bot", &aliases), "/status@qbit_bot");
+    assert_eq!(resolve("/dl", &aliases), "/list downloading");
+    assert_eq!(resolve("/dl@bot 2", &aliases), "/list@bot downloading 2");
+  }
+
+  #[test]
+  fn leaves_everything_else() {
+    let aliases = aliases();
+    assert_eq!(resolve("/status", &aliases), "/status");
+    assert_eq!(resolve("s", &aliases), "s");
+    assert_eq!(resolve("/sx arg", &aliases), "/sx arg");
+  }
+}
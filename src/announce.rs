@@ -0,0 +1,77 @@
+use teloxide::{prelude::*, types::ParseMode};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+  config::AnnounceConfig,
+  features::Features,
+  format::format_bytes,
+  parental::Parental,
+  render,
+  torrent::events::{TorrentEvent, TorrentEvents, TorrentSnapshot},
+};
+
+fn announcement(torrent: &TorrentSnapshot, config: &AnnounceConfig) -> String {
+  let mut text = format!(
+    "✅ {}\n📦 {}",
+    render::bold(&torrent.name),
+    format_bytes(torrent.size)
+  );
+  if !torrent.category.is_empty() {
+    text.push_str(&format!(" · 🏷 {}", render::escape(&torrent.category)));
+  }
+  if let Some(template) = &config.link {
+    let link = template
+      .replace("{hash}", &torrent.hash)
+      .replace("{name}", &torrent.name);
+    text.push_str(&format!(
+      "\n<a href=\"{}\">▶️ Stream</a>",
+      render::escape(&link)
+    ));
+  }
+  text
+}
+
+/// Posts every finished torrent to the announcement channel, apart from the per-user
+/// notifications. The channel is treated like any other chat by the parental filter.
+pub fn spawn(
+  bot: Bot,
+  config: Option<AnnounceConfig>,
+  features: Features,
+  parental: Parental,
+  events: TorrentEvents,
+) {
+  let Some(config) = config else {
+    return;
+  };
+  let channel = ChatId(config.chat_id);
+  let mut events = events.subscribe();
+
+  tokio::spawn(async move {
+    loop {
+      let torrent = match events.recv().await {
+        Ok(TorrentEvent::Completed(torrent)) => torrent,
+        Ok(_) => continue,
+        Err(RecvError::Lagged(skipped)) => {
+          log::warn!("Announcements skipped {skipped} events");
+          continue;
+        }
+        Err(RecvError::Closed) => break,
+      };
+      if features.in_maintenance() || parental.hides(channel, &torrent) {
+        continue;
+      }
+      let wanted = config.categories.is_empty() || config.categories.contains(&torrent.category);
+      if !wanted {
+        continue;
+      }
+
+      let res = bot
+        .send_message(channel, announcement(&torrent, &config))
+        .parse_mode(ParseMode::Html)
+        .await;
+      if let Err(err) = res {
+        log::warn!("Could not announce {} in {channel}: {err}", torrent.name);
+      }
+    }
+  });
+}
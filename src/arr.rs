@@ -0,0 +1,221 @@
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use teloxide::prelude::*;
+
+use crate::{
+  audit,
+  config::{ArrConfig, Config},
+  db::Db,
+  duplicates, i18n,
+  parental::{self, Parental},
+  quota::Quotas,
+  search::SearchProviders,
+  torrent::{self, ApiResult},
+  HandlerResult,
+};
+
+/// Which library manager a release is handed to.
+#[derive(Debug, Clone, Copy)]
+enum App {
+  Sonarr,
+  Radarr,
+}
+
+impl App {
+  fn name(self) -> &'static str {
+    match self {
+      App::Sonarr => "Sonarr",
+      App::Radarr => "Radarr",
+    }
+  }
+
+  fn config(self, config: &Config) -> Option<&ArrConfig> {
+    match self {
+      App::Sonarr => config.sonarr.as_ref(),
+      App::Radarr => config.radarr.as_ref(),
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct Decision {
+  approved: bool,
+  #[serde(default)]
+  rejections: Vec<String>,
+}
+
+/// Pushes a release like an indexer would, so the app grabs it through its own download client
+/// and imports it into the library. Returns why it was rejected, if it was.
+async fn push(config: &ArrConfig, title: &str, link: &str) -> ApiResult<Option<Vec<String>>> {
+  let (download_url, magnet_url) = if link.starts_with("magnet:") {
+    ("", link)
+  } else {
+    (link, "")
+  };
+  let decisions: Vec<Decision> = reqwest::Client::new()
+    .post(format!(
+      "{}/api/v3/release/push",
+      config.url.trim_end_matches('/')
+    ))
+    .header("X-Api-Key", &config.api_key)
+    .json(&json!({
+      "title": title,
+      "downloadUrl": download_url,
+      "magnetUrl": magnet_url,
+      "protocol": "torrent",
+      "publishDate": Utc::now().to_rfc3339(),
+    }))
+    .send()
+    .await?
+    .error_for_status()?
+    .json()
+    .await?;
+  Ok(
+    decisions
+      .into_iter()
+      .find(|decision| !decision.approved)
+      .map(|decision| decision.rejections),
+  )
+}
+
+/// Title and link of what `text` points at: a magnet or info hash, or the number of a /search result when
+/// replying to the results.
+fn target(msg: &Message, text: &str, indexers: &SearchProviders) -> Option<(String, String)> {
+  if let Some(magnet) = torrent::hash_magnet(text) {
+    return target(msg, &magnet, indexers);
+  }
+  if text.starts_with("magnet:") {
+    let title = parental::magnet_name(text)
+      .or_else(|| torrent::extract_info_hash(text))
+      .unwrap_or_else(|| text.to_string());
+    return Some((title, text.to_string()));
+  }
+  let number: usize = text.parse().ok()?;
+  let result = indexers.replied_result(msg.reply_to_message()?, number)?;
+  Some((result.title, result.link))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn handoff(
+  app: App,
+  bot: Bot,
+  msg: Message,
+  text: String,
+  config: Arc<Config>,
+  indexers: SearchProviders,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(arr) = app.config(&config) else {
+    bot
+      .send_message(
+        msg.chat.id,
+        lang.tf("arr.disabled", &[("app", &app.name())]),
+      )
+      .await?;
+    return Ok(());
+  };
+  let Some((title, link)) = target(&msg, text.trim(), &indexers) else {
+    bot
+      .send_message(
+        msg.chat.id,
+        lang.tf("arr.usage", &[("command", &app.name().to_lowercase())]),
+      )
+      .await?;
+    return Ok(());
+  };
+  if parental.blocks(msg.chat.id, &title) {
+    bot
+      .send_message(msg.chat.id, lang.t("parental.blocked"))
+      .await?;
+    return Ok(());
+  }
+  let hash = torrent::extract_info_hash(&link);
+  let size = duplicates::magnet_size(&link);
+  if let Some(user) = msg.from() {
+    if let Err(reason) = quotas.check(user.id.0, size, lang) {
+      bot.send_message(msg.chat.id, reason).await?;
+      return Ok(());
+    }
+  }
+
+  let reply = match push(arr, &title, &link).await {
+    Ok(None) => {
+      if let Some(user) = msg.from() {
+        if let Some(hash) = &hash {
+          quotas.record(user.id.0, hash, size);
+        }
+        audit::record(
+          &db,
+          user,
+          format!("sent a release to {}", app.name()),
+          hash.as_deref(),
+        );
+      }
+      lang.tf("arr.sent", &[("app", &app.name()), ("title", &title)])
+    }
+    Ok(Some(rejections)) => lang.tf(
+      "arr.rejected",
+      &[("app", &app.name()), ("reasons", &rejections.join("; "))],
+    ),
+    Err(err) => lang.tf("arr.failed", &[("app", &app.name()), ("error", &err)]),
+  };
+  bot.send_message(msg.chat.id, reply).await?;
+  Ok(())
+}
+
+/// `/sonarr <magnet>`, or `/sonarr <n>` in reply to /search results.
+#[allow(clippy::too_many_arguments)]
+pub async fn sonarr(
+  bot: Bot,
+  msg: Message,
+  text: String,
+  config: Arc<Config>,
+  indexers: SearchProviders,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  handoff(
+    App::Sonarr,
+    bot,
+    msg,
+    text,
+    config,
+    indexers,
+    quotas,
+    parental,
+    db,
+  )
+  .await
+}
+
+/// `/radarr <magnet>`, or `/radarr <n>` in reply to /search results.
+#[allow(clippy::too_many_arguments)]
+pub async fn radarr(
+  bot: Bot,
+  msg: Message,
+  text: String,
+  config: Arc<Config>,
+  indexers: SearchProviders,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  handoff(
+    App::Radarr,
+    bot,
+    msg,
+    text,
+    config,
+    indexers,
+    quotas,
+    parental,
+    db,
+  )
+  .await
+}
@@ -0,0 +1,127 @@
+use chrono::Utc;
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardMarkup, User},
+};
+
+use crate::{
+  callback_data,
+  db::{Db, UserSettings},
+  format::format_timestamp,
+  i18n::{self, Lang},
+  settings, HandlerResult,
+};
+
+/// One state-changing action taken through the bot.
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+  pub user_id: u64,
+  pub user_name: String,
+  pub action: String,
+  pub hash: Option<String>,
+  pub at: i64,
+}
+
+/// Writes `action` to the audit log. Failures are only logged, the action already happened.
+pub fn record(db: &Db, user: &User, action: impl Into<String>, hash: Option<&str>) {
+  let entry = AuditEntry {
+    user_id: user.id.0,
+    user_name: user
+      .username
+      .as_ref()
+      .map(|name| format!("@{name}"))
+      .unwrap_or_else(|| user.full_name()),
+    action: action.into(),
+    hash: hash.map(str::to_string),
+    at: Utc::now().timestamp(),
+  };
+  if let Err(err) = db.log_action(&entry) {
+    log::error!(
+      "Could not record {:?} in the audit log: {err}",
+      entry.action
+    );
+  }
+}
+
+fn render(
+  entries: &[AuditEntry],
+  page: u32,
+  settings: &UserSettings,
+  total: u32,
+  lang: Lang,
+) -> String {
+  let page_size = settings.page_size;
+  if entries.is_empty() {
+    return lang.t("history.empty").to_string();
+  }
+  let mut text = lang.tf(
+    "history.title",
+    &[("page", &(page + 1)), ("pages", &total.div_ceil(page_size))],
+  );
+  text.push('\n');
+  for entry in entries {
+    let at = format_timestamp(entry.at, settings.tz());
+    text.push_str(&format!("\n{at} {} {}", entry.user_name, entry.action));
+    if let Some(hash) = &entry.hash {
+      text.push_str(&format!(" ({})", &hash[..hash.len().min(8)]));
+    }
+  }
+  text
+}
+
+fn keyboard(page: u32, page_size: u32, total: u32, lang: Lang) -> InlineKeyboardMarkup {
+  let mut row = Vec::new();
+  if page > 0 {
+    row.push(callback_data::button(
+      lang.t("history.newer"),
+      format!("history:{}", page - 1),
+    ));
+  }
+  if (page + 1) * page_size < total {
+    row.push(callback_data::button(
+      lang.t("history.older"),
+      format!("history:{}", page + 1),
+    ));
+  }
+  InlineKeyboardMarkup::new([row])
+}
+
+pub async fn history(bot: Bot, msg: Message, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let settings = settings::of(&db, msg.from());
+  let page_size = settings.page_size;
+  let total = db.history_len()?;
+  let entries = db.history(page_size, 0)?;
+  bot
+    .send_message(msg.chat.id, render(&entries, 0, &settings, total, lang))
+    .reply_markup(keyboard(0, page_size, total, lang))
+    .await?;
+  Ok(())
+}
+
+pub async fn callback(bot: Bot, q: CallbackQuery, db: Db) -> HandlerResult {
+  let page = q
+    .data
+    .as_deref()
+    .and_then(|data| data.strip_prefix("history:"))
+    .and_then(|page| page.parse::<u32>().ok());
+  let lang = i18n::lang(&db, Some(&q.from));
+  bot.answer_callback_query(q.id).await?;
+  let (Some(page), Some(msg)) = (page, q.message) else {
+    return Ok(());
+  };
+
+  let settings = settings::of(&db, Some(&q.from));
+  let page_size = settings.page_size;
+  let total = db.history_len()?;
+  let entries = db.history(page_size, page * page_size)?;
+  bot
+    .edit_message_text(
+      msg.chat.id,
+      msg.id,
+      render(&entries, page, &settings, total, lang),
+    )
+    .reply_markup(keyboard(page, page_size, total, lang))
+    .await?;
+  Ok(())
+}
@@ -0,0 +1,148 @@
+use chrono::Utc;
+use std::{
+  collections::{HashMap, HashSet},
+  env,
+  sync::{Arc, RwLock},
+};
+use teloxide::{
+  prelude::*,
+  types::{InlineQueryResult, UpdateKind},
+};
+
+use crate::{db::Db, i18n, HandlerResult};
+
+/// Telegram accounts and chats allowed to use the bot, from `ALLOWED_USER_IDS` and
+/// `ALLOWED_CHAT_IDS` (comma separated). With both empty the bot is open to everyone.
+///
+/// Admins come from `ADMIN_USER_IDS`, falling back to the allowed users, so an open bot has no
+/// admins unless they are listed. Guests let in by a /guest link get read-only access until
+/// their time runs out.
+#[derive(Debug, Clone, Default)]
+pub struct Access {
+  users: HashSet<u64>,
+  chats: HashSet<i64>,
+  admins: HashSet<u64>,
+  // user id to the Unix time their guest access ends
+  guests: Arc<RwLock<HashMap<u64, i64>>>,
+}
+
+fn parse_ids<T: std::str::FromStr + Eq + std::hash::Hash>(var: &str) -> HashSet<T> {
+  env::var(var)
+    .unwrap_or_default()
+    .split(',')
+    .filter_map(|id| id.trim().parse().ok())
+    .collect()
+}
+
+impl Access {
+  pub fn from_env(db: &Db) -> Self {
+    let guests = db
+      .guests()
+      .unwrap_or_else(|err| {
+        log::error!("Could not load guests: {err}");
+        Vec::new()
+      })
+      .into_iter()
+      .map(|(user_id, _, until)| (user_id, until))
+      .collect();
+    let access = Access {
+      users: parse_ids("ALLOWED_USER_IDS"),
+      chats: parse_ids("ALLOWED_CHAT_IDS"),
+      admins: parse_ids("ADMIN_USER_IDS"),
+      guests: Arc::new(RwLock::new(guests)),
+    };
+    if access.is_open() {
+      log::warn!("ALLOWED_USER_IDS and ALLOWED_CHAT_IDS are empty, anyone can control the bot");
+    }
+    if access.admins.is_empty() && access.users.is_empty() {
+      log::warn!("ADMIN_USER_IDS and ALLOWED_USER_IDS are empty, nobody can run admin commands");
+    }
+    access
+  }
+
+  pub fn is_open(&self) -> bool {
+    self.users.is_empty() && self.chats.is_empty()
+  }
+
+  pub fn is_admin(&self, user: UserId) -> bool {
+    if !self.admins.is_empty() {
+      self.admins.contains(&user.0)
+    } else {
+      self.users.contains(&user.0)
+    }
+  }
+
+  /// Whether `user` is listed in `ADMIN_USER_IDS`, rather than an admin by falling back to the
+  /// allowed users.
+  pub fn is_listed_admin(&self, user: UserId) -> bool {
+    self.admins.contains(&user.0)
+  }
+
+  pub fn allows(&self, upd: &Update) -> bool {
+    self.admits(upd) || upd.user().is_some_and(|user| self.is_guest(user.id))
+  }
+
+  /// Whether the update only gets in on a guest link, so it may look but not touch.
+  pub fn is_read_only(&self, upd: &Update) -> bool {
+    !self.admits(upd) && upd.user().is_some_and(|user| self.is_guest(user.id))
+  }
+
+  /// Whether the update gets full access, guests aside.
+  pub fn admits(&self, upd: &Update) -> bool {
+    self.is_open()
+      || upd
+        .user()
+        .is_some_and(|user| self.users.contains(&user.id.0))
+      || upd
+        .chat()
+        .is_some_and(|chat| self.chats.contains(&chat.id.0))
+  }
+
+  fn is_guest(&self, user: UserId) -> bool {
+    let guests = self
+      .guests
+      .read()
+      .unwrap_or_else(|poisoned| poisoned.into_inner());
+    guests
+      .get(&user.0)
+      .is_some_and(|until| *until > Utc::now().timestamp())
+  }
+
+  pub fn admit_guest(&self, user: UserId, until: i64) {
+    self
+      .guests
+      .write()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+      .insert(user.0, until);
+  }
+
+  pub fn revoke_guest(&self, user: UserId) {
+    self
+      .guests
+      .write()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+      .remove(&user.0);
+  }
+}
+
+pub async fn deny(bot: Bot, upd: Update, db: Db) -> HandlerResult {
+  let user = upd.user().map(|user| (user.id, user.username.clone()));
+  log::warn!("Denied access to {user:?}");
+  let denied = i18n::lang(&db, upd.user()).t("access.denied");
+
+  match upd.kind {
+    UpdateKind::Message(msg) => {
+      bot.send_message(msg.chat.id, denied).await?;
+    }
+    UpdateKind::CallbackQuery(q) => {
+      bot.answer_callback_query(q.id).text(denied).await?;
+    }
+    UpdateKind::InlineQuery(q) => {
+      bot
+        .answer_inline_query(q.id, Vec::<InlineQueryResult>::new())
+        .await?;
+    }
+    _ => {}
+  }
+  Ok(())
+}
@@ -0,0 +1,106 @@
+use chrono::Utc;
+use std::{
+  collections::hash_map::DefaultHasher,
+  hash::{Hash, Hasher},
+  sync::OnceLock,
+};
+use teloxide::{prelude::*, types::InlineKeyboardButton};
+
+use crate::{db::Db, i18n, HandlerResult};
+
+/// Callback data starting with this is a token standing in for a longer payload.
+pub const TOKEN_PREFIX: &str = "~";
+
+/// Tokens that haven't been on a keyboard for this long stop working and get pruned.
+pub const TTL_DAYS: i64 = 30;
+
+const TOKEN_LEN: usize = 8;
+const ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+// payloads this short go out as they are, anything longer gets a token
+const MAX_INLINE: usize = 24;
+
+static DB: OnceLock<Db> = OnceLock::new();
+
+/// Keyboards built before this keep their raw payloads.
+pub fn init(db: Db) {
+  let _ = DB.set(db);
+}
+
+/// The same payload always gets the same token, so redrawn keyboards don't pile up rows.
+fn token(payload: &str, salt: u32) -> String {
+  let mut hasher = DefaultHasher::new();
+  (payload, salt).hash(&mut hasher);
+  let mut hash = hasher.finish();
+  (0..TOKEN_LEN)
+    .map(|_| {
+      let c = ALPHABET[(hash % ALPHABET.len() as u64) as usize] as char;
+      hash /= ALPHABET.len() as u64;
+      c
+    })
+    .collect()
+}
+
+/// Telegram caps callback data at 64 bytes, so long payloads are sent as a short token instead.
+pub fn shorten(payload: String) -> String {
+  let Some(db) = DB.get() else {
+    return payload;
+  };
+  if payload.len() <= MAX_INLINE {
+    return payload;
+  }
+  let now = Utc::now().timestamp();
+  let mut salt = 0;
+  loop {
+    let token = token(&payload, salt);
+    let stored = match db.callback_token(&token) {
+      Ok(stored) => stored,
+      Err(err) => {
+        log::error!("Could not look up callback token {token}: {err}");
+        return payload;
+      }
+    };
+    if stored.is_some_and(|(stored, _)| stored != payload) {
+      salt += 1;
+      continue;
+    }
+    if let Err(err) = db.set_callback_token(&token, &payload, now) {
+      log::error!("Could not save callback token {token}: {err}");
+      return payload;
+    }
+    return format!("{TOKEN_PREFIX}{token}");
+  }
+}
+
+/// A callback button whose data goes through [`shorten`].
+pub fn button(text: impl Into<String>, payload: impl Into<String>) -> InlineKeyboardButton {
+  InlineKeyboardButton::callback(text, shorten(payload.into()))
+}
+
+/// The payload behind callback `data`, `None` when it's a token that expired.
+pub fn resolve(data: &str) -> Option<String> {
+  let Some(token) = data.strip_prefix(TOKEN_PREFIX) else {
+    return Some(data.to_string());
+  };
+  let (payload, used_at) = DB.get()?.callback_token(token).ok()??;
+  let expires = used_at + TTL_DAYS * 24 * 60 * 60;
+  (Utc::now().timestamp() < expires).then_some(payload)
+}
+
+/// Swaps a token for its payload before routing, expired tokens are left for [`expired`].
+pub fn expand(mut q: CallbackQuery) -> CallbackQuery {
+  if let Some(payload) = q.data.as_deref().and_then(resolve) {
+    q.data = Some(payload);
+  }
+  q
+}
+
+pub async fn expired(bot: Bot, q: CallbackQuery, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  bot
+    .answer_callback_query(q.id)
+    .text(lang.t("callback.expired"))
+    .show_alert(true)
+    .await?;
+  Ok(())
+}
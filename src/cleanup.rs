@@ -0,0 +1,408 @@
+use chrono::Utc;
+use std::{collections::HashSet, sync::Arc, time::Duration};
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardButton, InlineKeyboardButtonKind, InlineKeyboardMarkup},
+};
+
+use crate::{
+  audit,
+  auth::Access,
+  callback_data,
+  config::{CleanupConfig, Config},
+  db::Db,
+  digest::DryRuns,
+  i18n::{self, Lang},
+  parental::Parental,
+  torrent::{
+    events::{TorrentEvents, TorrentSnapshot},
+    TorrentApi,
+  },
+  undo::{self, Undos},
+  HandlerResult,
+};
+
+// monthly quotas look back at most one calendar month
+const QUOTA_RETENTION_DAYS: i64 = 32;
+
+// Telegram allows at most 100 buttons per keyboard, leave room for the controls
+const MAX_CANDIDATES: usize = 90;
+
+const CHECKED: &str = "✅";
+const UNCHECKED: &str = "⬜";
+
+/// Rows removed by one cleanup run.
+#[derive(Debug, Default)]
+pub struct Purged {
+  pub audit: usize,
+  pub quota: usize,
+  pub tokens: usize,
+}
+
+/// Prunes the old records, or with `dry_run` only counts the ones it would prune.
+pub fn run(db: &Db, config: &CleanupConfig, dry_run: bool) -> rusqlite::Result<Purged> {
+  let day = 24 * 60 * 60;
+  let now = Utc::now().timestamp();
+  let audit = match config.audit_retention_days {
+    Some(days) if dry_run => db.count_audit_log(now - days as i64 * day)?,
+    Some(days) => db.prune_audit_log(now - days as i64 * day)?,
+    None => 0,
+  };
+  let quota_before = now - QUOTA_RETENTION_DAYS * day;
+  let quota = if dry_run {
+    db.count_quota_usage(quota_before)?
+  } else {
+    db.prune_quota_usage(quota_before)?
+  };
+  // expired buttons do nothing anymore, so they go even in a dry run
+  let tokens = db.prune_callback_tokens(now - callback_data::TTL_DAYS * day)?;
+  Ok(Purged {
+    audit,
+    quota,
+    tokens,
+  })
+}
+
+/// Runs the cleanup every `cleanup.interval_mins`, and checks for dead torrents as often. With
+/// `dry_run` on, what it would prune goes to the digest instead.
+pub fn spawn(bot: Bot, db: Db, config: Arc<Config>, events: TorrentEvents, dry_runs: DryRuns) {
+  tokio::spawn(async move {
+    let every = Duration::from_secs(config.cleanup.interval_mins.max(1) * 60);
+    let mut ticker = tokio::time::interval(every);
+    let mut reported = HashSet::new();
+    loop {
+      ticker.tick().await;
+      match run(&db, &config.cleanup, config.dry_run) {
+        Ok(purged) if config.dry_run && purged.audit + purged.quota > 0 => {
+          dry_runs.records(format!(
+            "{} audit log entries and {} quota records",
+            purged.audit, purged.quota
+          ))
+        }
+        Ok(purged) => log::debug!("Cleanup purged {purged:?}"),
+        Err(err) => log::error!("Cleanup failed: {err}"),
+      }
+      if let Some(chat_id) = config.admin_chat_id.map(ChatId) {
+        let res = report_dead(&bot, &db, chat_id, &config.cleanup, &events, &mut reported).await;
+        if let Err(err) = res {
+          log::warn!("Could not report dead torrents: {err}");
+        }
+      }
+    }
+  });
+}
+
+/// Sends the admin chat the dead torrents when any died since the last check, ticked in the
+/// cleanup keyboard so removing them is a single tap.
+async fn report_dead(
+  bot: &Bot,
+  db: &Db,
+  chat_id: ChatId,
+  config: &CleanupConfig,
+  events: &TorrentEvents,
+  reported: &mut HashSet<String>,
+) -> HandlerResult {
+  let now = Utc::now().timestamp();
+  let torrents = events.torrents().borrow().clone();
+  let dead: Vec<_> = torrents
+    .values()
+    .filter_map(|torrent| match Reason::of(torrent, config, now) {
+      Some(reason @ Reason::Dead(_)) => Some((torrent, reason)),
+      _ => None,
+    })
+    .collect();
+  reported.retain(|hash| dead.iter().any(|(torrent, _)| torrent.hash == *hash));
+  if dead
+    .iter()
+    .all(|(torrent, _)| reported.contains(&torrent.hash))
+  {
+    return Ok(());
+  }
+  reported.extend(dead.iter().map(|(torrent, _)| torrent.hash.clone()));
+
+  let lang = i18n::chat_lang(db, chat_id);
+  bot
+    .send_message(
+      chat_id,
+      lang.tf("cleanup.dead_found", &[("count", &dead.len())]),
+    )
+    .reply_markup(keyboard(dead, lang))
+    .await?;
+  Ok(())
+}
+
+/// Why a torrent is offered by /cleanup.
+enum Reason {
+  Dead(i64),
+  Ratio,
+  SeedingTime,
+  Inactive(i64),
+  Complete,
+}
+
+impl Reason {
+  fn of(torrent: &TorrentSnapshot, config: &CleanupConfig, now: i64) -> Option<Self> {
+    let inactive = (now - torrent.last_activity) / (24 * 60 * 60);
+    // a torrent that never transferred anything has been idle since it was added
+    let idle_hours = (now - torrent.last_activity.max(torrent.added_on)) / (60 * 60);
+    let dead = !torrent.is_complete()
+      && torrent.num_seeds == 0
+      && config
+        .dead_after_hours
+        .is_some_and(|hours| idle_hours >= hours as i64);
+    if dead {
+      Some(Reason::Dead(idle_hours))
+    } else if torrent.is_complete()
+      && torrent.max_ratio >= 0.0
+      && torrent.ratio >= torrent.max_ratio
+    {
+      Some(Reason::Ratio)
+    } else if torrent.is_complete()
+      && torrent.max_seeding_time >= 0
+      && torrent.seeding_time >= torrent.max_seeding_time * 60
+    {
+      Some(Reason::SeedingTime)
+    } else if torrent.last_activity > 0 && inactive >= config.inactive_days as i64 {
+      Some(Reason::Inactive(inactive))
+    } else if torrent.is_complete() {
+      Some(Reason::Complete)
+    } else {
+      None
+    }
+  }
+
+  /// Plain completed torrents are listed, but only the others start selected.
+  fn preselected(&self) -> bool {
+    !matches!(self, Reason::Complete)
+  }
+
+  fn label(&self, lang: Lang) -> String {
+    match self {
+      Reason::Dead(hours) => lang.tf("cleanup.dead", &[("hours", hours)]),
+      Reason::Ratio => lang.t("cleanup.ratio").to_string(),
+      Reason::SeedingTime => lang.t("cleanup.seeding_time").to_string(),
+      Reason::Inactive(days) => lang.tf("cleanup.inactive", &[("days", days)]),
+      Reason::Complete => lang.t("cleanup.complete").to_string(),
+    }
+  }
+}
+
+fn candidate_button(hash: &str, text: &str, checked: bool) -> InlineKeyboardButton {
+  let mark = if checked { CHECKED } else { UNCHECKED };
+  callback_data::button(format!("{mark} {text}"), format!("cl:t:{hash}"))
+}
+
+fn controls(lang: Lang) -> Vec<Vec<InlineKeyboardButton>> {
+  vec![
+    vec![
+      callback_data::button(lang.t("cleanup.delete"), "cl:del"),
+      callback_data::button(lang.t("cleanup.delete_data"), "cl:data"),
+    ],
+    vec![callback_data::button(lang.t("cleanup.cancel"), "cl:x")],
+  ]
+}
+
+/// One row per candidate, the ones worth deleting first and ticked, then the controls.
+fn keyboard(mut candidates: Vec<(&TorrentSnapshot, Reason)>, lang: Lang) -> InlineKeyboardMarkup {
+  candidates.sort_by_key(|(torrent, reason)| (!reason.preselected(), torrent.added_on));
+  candidates.truncate(MAX_CANDIDATES);
+  let mut rows: Vec<_> = candidates
+    .iter()
+    .map(|(torrent, reason)| {
+      let text = format!("{} · {}", torrent.name, reason.label(lang));
+      vec![candidate_button(&torrent.hash, &text, reason.preselected())]
+    })
+    .collect();
+  rows.extend(controls(lang));
+  InlineKeyboardMarkup::new(rows)
+}
+
+/// Hashes ticked in the keyboard, which is the only place the selection is kept.
+fn selected(markup: &InlineKeyboardMarkup) -> Vec<String> {
+  markup
+    .inline_keyboard
+    .iter()
+    .flatten()
+    .filter_map(|button| match &button.kind {
+      InlineKeyboardButtonKind::CallbackData(data) if button.text.starts_with(CHECKED) => {
+        callback_data::resolve(data)?
+          .strip_prefix("cl:t:")
+          .map(str::to_string)
+      }
+      _ => None,
+    })
+    .collect()
+}
+
+/// `/cleanup` offers finished torrents for deletion, `/cleanup now` prunes old records.
+#[allow(clippy::too_many_arguments)]
+pub async fn cleanup(
+  bot: Bot,
+  msg: Message,
+  arg: String,
+  access: Access,
+  config: Arc<Config>,
+  db: Db,
+  events: TorrentEvents,
+  parental: Parental,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(user) = msg.from().filter(|user| access.is_admin(user.id)) else {
+    bot.send_message(msg.chat.id, lang.t("admin.only")).await?;
+    return Ok(());
+  };
+  match arg.trim() {
+    "" => {}
+    "now" => {
+      // asked for by an admin, so the dry run doesn't apply
+      let purged = run(&db, &config.cleanup, false)?;
+      audit::record(&db, user, "ran the cleanup", None);
+      bot
+        .send_message(
+          msg.chat.id,
+          lang.tf(
+            "cleanup.done",
+            &[
+              ("audit", &purged.audit),
+              ("quota", &purged.quota),
+              ("tokens", &purged.tokens),
+            ],
+          ),
+        )
+        .await?;
+      return Ok(());
+    }
+    _ => {
+      bot
+        .send_message(msg.chat.id, lang.t("cleanup.usage"))
+        .await?;
+      return Ok(());
+    }
+  }
+
+  let now = Utc::now().timestamp();
+  let torrents = events.torrents().borrow().clone();
+  let candidates: Vec<_> = torrents
+    .values()
+    .filter(|torrent| !parental.hides(msg.chat.id, torrent))
+    .filter_map(|torrent| Reason::of(torrent, &config.cleanup, now).map(|reason| (torrent, reason)))
+    .collect();
+  if candidates.is_empty() {
+    bot
+      .send_message(msg.chat.id, lang.t("cleanup.none"))
+      .await?;
+    return Ok(());
+  }
+  bot
+    .send_message(msg.chat.id, lang.t("cleanup.prompt"))
+    .reply_markup(keyboard(candidates, lang))
+    .await?;
+  Ok(())
+}
+
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  access: Access,
+  torrent: TorrentApi,
+  undos: Undos,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  if !access.is_admin(q.from.id) {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("admin.only"))
+      .await?;
+    return Ok(());
+  }
+  let (Some(data), Some(msg)) = (q.data.as_deref(), q.message.as_ref()) else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+  let Some(markup) = msg.reply_markup() else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+
+  let delete_files = match data {
+    "cl:del" => false,
+    "cl:data" => true,
+    "cl:x" => {
+      bot.answer_callback_query(q.id).await?;
+      bot
+        .edit_message_text(msg.chat.id, msg.id, lang.t("cleanup.cancelled"))
+        .await?;
+      return Ok(());
+    }
+    _ => {
+      let Some(hash) = data.strip_prefix("cl:t:") else {
+        bot.answer_callback_query(q.id).await?;
+        return Ok(());
+      };
+      let rows: Vec<Vec<InlineKeyboardButton>> = markup
+        .inline_keyboard
+        .iter()
+        .map(|row| {
+          row
+            .iter()
+            .map(|button| match &button.kind {
+              InlineKeyboardButtonKind::CallbackData(data)
+                if callback_data::resolve(data) == Some(format!("cl:t:{hash}")) =>
+              {
+                let checked = button.text.starts_with(CHECKED);
+                let text = button.text.split_once(' ').map_or("", |(_, text)| text);
+                candidate_button(hash, text, !checked)
+              }
+              _ => button.clone(),
+            })
+            .collect()
+        })
+        .collect();
+      bot.answer_callback_query(q.id).await?;
+      bot
+        .edit_message_reply_markup(msg.chat.id, msg.id)
+        .reply_markup(InlineKeyboardMarkup::new(rows))
+        .await?;
+      return Ok(());
+    }
+  };
+
+  let hashes = selected(markup);
+  if hashes.is_empty() {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("cleanup.nothing_selected"))
+      .await?;
+    return Ok(());
+  }
+  let deleted = undo::prepare(&torrent, &hashes).await;
+  torrent.delete(&hashes.join("|"), delete_files).await?;
+  let action = if delete_files {
+    format!("cleaned up {} torrent(s) with their data", hashes.len())
+  } else {
+    format!("cleaned up {} torrent(s)", hashes.len())
+  };
+  audit::record(&db, &q.from, action, None);
+
+  let key = if delete_files {
+    "cleanup.deleted_data"
+  } else {
+    "cleanup.deleted"
+  };
+  let undo = undos.keep(deleted);
+  let mut edit = bot.edit_message_text(
+    msg.chat.id,
+    msg.id,
+    lang.tf(key, &[("count", &hashes.len())]),
+  );
+  if let Some(id) = undo {
+    edit = edit.reply_markup(undo::keyboard(id, lang));
+  }
+  bot.answer_callback_query(q.id).await?;
+  edit.await?;
+  if let Some(id) = undo {
+    undos.expire(&bot, msg.chat.id, msg.id, id);
+  }
+  Ok(())
+}
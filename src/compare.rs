@@ -0,0 +1,223 @@
+use std::{path::Path, time::Duration};
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardMarkup, ParseMode},
+};
+use tokio::process::Command;
+
+use crate::{
+  callback_data,
+  db::Db,
+  delete,
+  format::Units,
+  i18n,
+  parental::Parental,
+  render, settings,
+  torrent::{
+    events::{TorrentEvents, TorrentSnapshot},
+    TorrentApi, TorrentFile,
+  },
+  watch, HandlerResult, MyDialogue,
+};
+
+const FFPROBE_TIMEOUT: Duration = Duration::from_secs(10);
+const NAME_WIDTH: usize = 24;
+
+/// What we know about one side of the comparison.
+struct Side {
+  hash: String,
+  name: String,
+  size: u64,
+  progress: f64,
+  seeds: u64,
+  leechers: u64,
+  ratio: f64,
+  files: usize,
+  codec: Option<String>,
+}
+
+/// Video codec of the largest file, once it is fully downloaded and ffprobe is installed.
+async fn probe_codec(save_path: &str, files: &[TorrentFile]) -> Option<String> {
+  let largest = files.iter().max_by_key(|file| file.size)?;
+  if largest.progress < 1.0 {
+    return None;
+  }
+  let path = Path::new(save_path).join(&largest.name);
+  let probe = Command::new("ffprobe")
+    .args([
+      "-v",
+      "error",
+      "-select_streams",
+      "v:0",
+      "-show_entries",
+      "stream=codec_name",
+      "-of",
+      "csv=p=0",
+    ])
+    .arg(path)
+    .output();
+  let output = tokio::time::timeout(FFPROBE_TIMEOUT, probe)
+    .await
+    .ok()?
+    .ok()?;
+  let codec = String::from_utf8_lossy(&output.stdout).trim().to_string();
+  (output.status.success() && !codec.is_empty()).then_some(codec)
+}
+
+fn shorten(name: &str) -> String {
+  if name.chars().count() <= NAME_WIDTH {
+    name.to_string()
+  } else {
+    let short: String = name.chars().take(NAME_WIDTH - 1).collect();
+    format!("{short}…")
+  }
+}
+
+fn render(a: &Side, b: &Side, units: Units) -> String {
+  let rows = [
+    ("Name", shorten(&a.name), shorten(&b.name)),
+    ("Size", units.bytes(a.size), units.bytes(b.size)),
+    (
+      "Done",
+      format!("{:.1}%", a.progress * 100.0),
+      format!("{:.1}%", b.progress * 100.0),
+    ),
+    ("Seeds", a.seeds.to_string(), b.seeds.to_string()),
+    ("Leechers", a.leechers.to_string(), b.leechers.to_string()),
+    (
+      "Ratio",
+      format!("{:.2}", a.ratio),
+      format!("{:.2}", b.ratio),
+    ),
+    ("Files", a.files.to_string(), b.files.to_string()),
+    (
+      "Codec",
+      a.codec.clone().unwrap_or_else(|| "?".to_string()),
+      b.codec.clone().unwrap_or_else(|| "?".to_string()),
+    ),
+  ];
+  let width = rows
+    .iter()
+    .map(|(_, left, _)| left.chars().count())
+    .max()
+    .unwrap_or(0);
+  let mut table = format!("{:<8} {:<width$} B\n", "", "A");
+  for (label, left, right) in rows {
+    table.push_str(&format!("{label:<8} {left:<width$} {right}\n"));
+  }
+  render::pre(&table)
+}
+
+async fn side(
+  torrent: &TorrentApi,
+  events: &TorrentEvents,
+  visible: impl Fn(&TorrentSnapshot) -> bool,
+  hash: &str,
+) -> Option<Side> {
+  let snapshot = {
+    let torrents = events.torrents();
+    let current = torrents.borrow();
+    let all: Vec<_> = current
+      .values()
+      .filter(|torrent| visible(torrent))
+      .collect();
+    watch::find(&all, hash).cloned()
+  }?;
+  let files = torrent.files(&snapshot.hash).await.unwrap_or_default();
+  Some(Side {
+    codec: probe_codec(&snapshot.save_path, &files).await,
+    files: files.len(),
+    hash: snapshot.hash,
+    name: snapshot.name,
+    size: snapshot.size,
+    progress: snapshot.progress,
+    seeds: snapshot.num_seeds,
+    leechers: snapshot.num_leechs,
+    ratio: snapshot.ratio,
+  })
+}
+
+/// `/compare <hash1> <hash2>`, hashes may be shortened to an unambiguous prefix.
+pub async fn compare(
+  bot: Bot,
+  msg: Message,
+  args: String,
+  torrent: TorrentApi,
+  events: TorrentEvents,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let visible = |torrent: &TorrentSnapshot| !parental.hides(msg.chat.id, torrent);
+  let hashes: Vec<&str> = args.split_whitespace().collect();
+  let [first, second] = hashes[..] else {
+    bot
+      .send_message(msg.chat.id, lang.t("compare.usage"))
+      .await?;
+    return Ok(());
+  };
+  let (Some(a), Some(b)) = (
+    side(&torrent, &events, visible, first).await,
+    side(&torrent, &events, visible, second).await,
+  ) else {
+    bot
+      .send_message(msg.chat.id, lang.t("compare.not_found"))
+      .await?;
+    return Ok(());
+  };
+
+  let keyboard = InlineKeyboardMarkup::new([[a.hash.as_str(), b.hash.as_str()]
+    .into_iter()
+    .zip(["A", "B"])
+    .map(|(hash, label)| {
+      callback_data::button(
+        lang.tf("compare.delete", &[("side", &label)]),
+        format!("cmp:del:{hash}"),
+      )
+    })
+    .collect::<Vec<_>>()]);
+  bot
+    .send_message(
+      msg.chat.id,
+      render(&a, &b, settings::of(&db, msg.from()).units()),
+    )
+    .parse_mode(ParseMode::Html)
+    .reply_markup(keyboard)
+    .await?;
+  Ok(())
+}
+
+/// Deleting a release takes its files, so the name has to be typed to confirm.
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  dialogue: MyDialogue,
+  events: TorrentEvents,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  bot.answer_callback_query(q.id).await?;
+  let (Some(hash), Some(msg)) = (
+    q.data
+      .as_deref()
+      .and_then(|data| data.strip_prefix("cmp:del:")),
+    q.message.as_ref(),
+  ) else {
+    return Ok(());
+  };
+  let Some(name) = events
+    .torrents()
+    .borrow()
+    .get(hash)
+    .map(|torrent| torrent.name.clone())
+  else {
+    bot
+      .send_message(msg.chat.id, lang.t("delete.not_found"))
+      .await?;
+    return Ok(());
+  };
+
+  // the other button would delete the keeper, take both away
+  bot.edit_message_reply_markup(msg.chat.id, msg.id).await?;
+  delete::ask(&bot, &dialogue, msg.chat.id, hash, &name, lang).await
+}
@@ -0,0 +1,216 @@
+use teloxide::prelude::*;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+  audit,
+  auth::Access,
+  db::{CompletionActions, Db},
+  features::{Feature, Features},
+  i18n::{self, Lang},
+  torrent::{
+    events::{TorrentEvent, TorrentEvents, TorrentSnapshot},
+    ApiResult, TorrentApi,
+  },
+  upload, HandlerResult,
+};
+
+async fn apply(
+  bot: &Bot,
+  torrent: &TorrentApi,
+  features: &Features,
+  completed: &TorrentSnapshot,
+  actions: &CompletionActions,
+) -> ApiResult<()> {
+  let hash = completed.hash.as_str();
+  if let Some(tag) = &actions.tag {
+    torrent.add_tags(hash, tag).await?;
+  }
+  // before moving, while the content is still where qBittorrent said it is
+  if let Some(remote) = &actions.upload_to {
+    let chat = actions.notify_chat.map(ChatId);
+    if let Err(err) = upload::rclone(bot, chat, completed, remote).await {
+      log::warn!("Could not upload {} to {remote}: {err}", completed.name);
+    }
+  }
+  if let Some(path) = &actions.move_to {
+    torrent.set_location(hash, path).await?;
+  }
+  if actions.pause {
+    torrent.pause(hash).await?;
+  }
+  if let Some(chat_id) = actions.notify_chat {
+    if features.enabled(Feature::Notifications) {
+      let text = format!(
+        "✅ {} has finished downloading ({})",
+        completed.name, completed.category
+      );
+      if let Err(err) = bot.send_message(ChatId(chat_id), text).await {
+        log::warn!("Could not notify {chat_id} about {}: {err}", completed.name);
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Runs the /oncomplete actions of a torrent's category once it finishes.
+pub fn spawn(bot: Bot, torrent: TorrentApi, db: Db, features: Features, events: TorrentEvents) {
+  let mut events = events.subscribe();
+  tokio::spawn(async move {
+    loop {
+      let completed = match events.recv().await {
+        Ok(TorrentEvent::Completed(completed)) => completed,
+        Ok(_) => continue,
+        Err(RecvError::Lagged(skipped)) => {
+          log::warn!("Completion actions skipped {skipped} events");
+          continue;
+        }
+        Err(RecvError::Closed) => break,
+      };
+      if completed.category.is_empty() || features.in_maintenance() {
+        continue;
+      }
+      let actions = match db.category_actions(&completed.category) {
+        Ok(Some(actions)) => actions,
+        Ok(None) => continue,
+        Err(err) => {
+          log::error!("Could not load actions for {}: {err}", completed.category);
+          continue;
+        }
+      };
+      // uploads can take hours, so every torrent gets its own task
+      let (bot, torrent, features) = (bot.clone(), torrent.clone(), features.clone());
+      tokio::spawn(async move {
+        if let Err(err) = apply(&bot, &torrent, &features, &completed, &actions).await {
+          log::warn!("Completion actions failed for {}: {err}", completed.name);
+        }
+      });
+    }
+  });
+}
+
+fn describe(category: &str, actions: &CompletionActions, lang: Lang) -> String {
+  let mut parts = Vec::new();
+  if let Some(path) = &actions.move_to {
+    parts.push(lang.tf("oncomplete.move", &[("path", path)]));
+  }
+  if let Some(tag) = &actions.tag {
+    parts.push(lang.tf("oncomplete.tag", &[("tag", tag)]));
+  }
+  if actions.pause {
+    parts.push(lang.t("oncomplete.pause").to_string());
+  }
+  if let Some(chat_id) = actions.notify_chat {
+    parts.push(lang.tf("oncomplete.notify", &[("chat", &chat_id)]));
+  }
+  if let Some(remote) = &actions.upload_to {
+    parts.push(lang.tf("oncomplete.upload", &[("remote", remote)]));
+  }
+  format!("{category}: {}", parts.join(", "))
+}
+
+/// `/oncomplete <category> move <path>|tag <tag>|pause|notify [chat id]|upload <remote>|clear`,
+/// or without arguments the configured actions. `off` as the value removes a single action.
+pub async fn oncomplete(
+  bot: Bot,
+  msg: Message,
+  args: String,
+  access: Access,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(user) = msg.from().filter(|user| access.is_admin(user.id)) else {
+    bot.send_message(msg.chat.id, lang.t("admin.only")).await?;
+    return Ok(());
+  };
+
+  let mut words = args.split_whitespace();
+  let (Some(category), Some(action)) = (words.next(), words.next()) else {
+    let configured = db.completion_actions()?;
+    let text = if configured.is_empty() {
+      lang.t("oncomplete.none").to_string()
+    } else {
+      configured
+        .iter()
+        .map(|(category, actions)| describe(category, actions, lang))
+        .collect::<Vec<_>>()
+        .join("\n")
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    bot
+      .send_message(msg.chat.id, lang.t("oncomplete.usage"))
+      .await?;
+    return Ok(());
+  };
+  let value = words.collect::<Vec<_>>().join(" ");
+  let off = value == "off";
+
+  if action == "clear" {
+    db.clear_category_actions(category)?;
+    audit::record(
+      &db,
+      user,
+      format!("cleared the completion actions of {category}"),
+      None,
+    );
+    bot
+      .send_message(
+        msg.chat.id,
+        lang.tf("oncomplete.cleared", &[("category", &category)]),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let mut actions = db.category_actions(category)?.unwrap_or_default();
+  let valid = match action {
+    "move" if !value.is_empty() => {
+      actions.move_to = (!off).then_some(value);
+      true
+    }
+    "tag" if !value.is_empty() => {
+      actions.tag = (!off).then_some(value);
+      true
+    }
+    "upload" if !value.is_empty() => {
+      actions.upload_to = (!off).then_some(value);
+      true
+    }
+    "pause" => {
+      actions.pause = !off;
+      true
+    }
+    "notify" if off => {
+      actions.notify_chat = None;
+      true
+    }
+    "notify" if value.is_empty() || value == "here" => {
+      actions.notify_chat = Some(msg.chat.id.0);
+      true
+    }
+    "notify" => match value.parse() {
+      Ok(chat_id) => {
+        actions.notify_chat = Some(chat_id);
+        true
+      }
+      Err(_) => false,
+    },
+    _ => false,
+  };
+  if !valid {
+    bot
+      .send_message(msg.chat.id, lang.t("oncomplete.usage"))
+      .await?;
+    return Ok(());
+  }
+  db.set_category_actions(category, &actions)?;
+  audit::record(
+    &db,
+    user,
+    format!("changed the completion actions of {category}"),
+    None,
+  );
+  bot
+    .send_message(msg.chat.id, describe(category, &actions, lang))
+    .await?;
+  Ok(())
+}
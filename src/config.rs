@@ -0,0 +1,318 @@
+use serde::Deserialize;
+use std::{collections::HashMap, env, fs, io::ErrorKind, path::PathBuf, time::Duration};
+
+use crate::{policy::Policy, profile::Profile, recovery::RecoveryRule, webhook::Webhook};
+
+/// Optional settings read from the JSON file at `CHATQBIT_CONFIG` (default `config.json`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+  pub profiles: Vec<Profile>,
+  /// How often `sync/maindata` is polled for torrent events.
+  pub poll_interval_secs: u64,
+  pub notify: NotifyConfig,
+  pub digest: DigestConfig,
+  pub dashboard: DashboardConfig,
+  pub webhooks: Vec<Webhook>,
+  /// Only used when built with the `mqtt` feature.
+  pub mqtt: Option<MqttConfig>,
+  pub quota: QuotaConfig,
+  /// Shell command run by /qbrestart after shutting qBittorrent down, e.g.
+  /// `systemctl restart qbittorrent-nox` or `docker restart qbittorrent`.
+  pub restart_command: Option<String>,
+  /// Extra command names, e.g. `"s": "status"`, resolved before commands are parsed.
+  pub aliases: HashMap<String, String>,
+  pub parental: ParentalConfig,
+  pub cleanup: CleanupConfig,
+  /// Auto-removal rules applied on every poll, previewed with /policies.
+  pub policies: Vec<Policy>,
+  /// Lists what the policies and the periodic cleanup would delete in the digest, without
+  /// deleting anything.
+  pub dry_run: bool,
+  /// Steps taken for stalled downloads, the rule for a torrent's category wins over one without.
+  pub recovery: Vec<RecoveryRule>,
+  pub watch_folder: Option<WatchFolderConfig>,
+  /// Gets panics and lasting qBittorrent outages.
+  pub admin_chat_id: Option<i64>,
+  /// Enables posters, years and ratings from TMDB for torrents that look like movies or shows.
+  pub tmdb_api_key: Option<String>,
+  pub opensubtitles: Option<OpenSubtitlesConfig>,
+  /// Indexers /search asks.
+  pub search: Vec<TorznabConfig>,
+  /// Library managers /sonarr and /radarr hand releases to.
+  pub sonarr: Option<ArrConfig>,
+  pub radarr: Option<ArrConfig>,
+  pub announce: Option<AnnounceConfig>,
+  pub guard: GuardConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+  /// Chats that receive proactive notifications.
+  pub chat_ids: Vec<i64>,
+  /// How long a torrent has to stay errored or stalled before anyone is told.
+  pub problem_after_secs: u64,
+  /// Completions within this many seconds of each other are sent as one message, `0` disables.
+  pub batch_window_secs: u64,
+  /// Discord webhook URLs that get every notification.
+  pub discord_webhooks: Vec<String>,
+  pub matrix: Option<MatrixConfig>,
+  /// Torrents marked with "Send when done" get their files up to this size uploaded.
+  pub auto_send_max_mb: u64,
+  /// Sent to Telegram after a completion notification, by the torrent's category.
+  pub completion_media: HashMap<String, CompletionMedia>,
+}
+
+/// A sticker or GIF, by Telegram file id or URL.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionMedia {
+  Sticker(String),
+  Animation(String),
+}
+
+/// Matrix room that gets every notification, posted with the access token of a bot account.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MatrixConfig {
+  pub homeserver: String,
+  pub access_token: String,
+  pub room_id: String,
+}
+
+/// OpenSubtitles account used by /subtitles, the API key comes from its consumer page.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenSubtitlesConfig {
+  pub api_key: String,
+  pub username: String,
+  pub password: String,
+  /// ISO 639-1 codes, one .srt per language and video.
+  #[serde(default = "default_subtitle_languages")]
+  pub languages: Vec<String>,
+}
+
+fn default_subtitle_languages() -> Vec<String> {
+  vec!["en".to_string()]
+}
+
+/// A Torznab feed, e.g. Jackett's `.../api/v2.0/indexers/all/results/torznab/api` or
+/// Prowlarr's `.../<indexer id>/api`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TorznabConfig {
+  pub name: String,
+  pub url: String,
+  pub api_key: String,
+}
+
+/// A Sonarr or Radarr instance, the API key is under Settings → General.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArrConfig {
+  pub url: String,
+  pub api_key: String,
+}
+
+/// Channel that gets a post for every finished torrent, the bot has to be an admin there.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnnounceConfig {
+  pub chat_id: i64,
+  /// Only torrents in these categories are announced, all of them when empty.
+  #[serde(default)]
+  pub categories: Vec<String>,
+  /// Link added to each post, `{hash}` and `{name}` are filled in, e.g. a media server URL.
+  pub link: Option<String>,
+}
+
+/// Summary digest schedule, disabled unless `time` is set.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DigestConfig {
+  /// `HH:MM` in UTC.
+  pub time: Option<String>,
+  /// Makes the digest weekly on this day (`mon`, `tue`, ...) instead of daily.
+  pub weekday: Option<String>,
+}
+
+/// Refresh rate and lifetime of the live /transfer dashboard.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DashboardConfig {
+  pub refresh_secs: u64,
+  pub duration_mins: u64,
+}
+
+impl Default for DashboardConfig {
+  fn default() -> Self {
+    DashboardConfig {
+      refresh_secs: 5,
+      duration_mins: 10,
+    }
+  }
+}
+
+/// Per-user limits on the total size of added torrents, unlimited when unset.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct QuotaConfig {
+  pub daily_gib: Option<u64>,
+  pub monthly_gib: Option<u64>,
+}
+
+/// Limits checked before a torrent is added, an admin can add a rejected one anyway.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct GuardConfig {
+  pub max_size_gib: Option<u64>,
+  /// Without the dot, e.g. `exe`, compared case-insensitively.
+  pub banned_extensions: Vec<String>,
+  /// Tracker domains, their subdomains are banned too.
+  pub banned_trackers: Vec<String>,
+  /// Downloads running at once, queued and paused ones don't count.
+  pub max_active_downloads: Option<usize>,
+}
+
+/// Periodic pruning of old database rows, also run on demand with `/cleanup now`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CleanupConfig {
+  pub interval_mins: u64,
+  /// Audit log entries older than this are deleted, kept forever when unset.
+  pub audit_retention_days: Option<u64>,
+  /// /cleanup suggests torrents without any activity for this long.
+  pub inactive_days: u64,
+  /// Downloads without seeds or progress for this long are dead, reported to the admin chat
+  /// and preselected by /cleanup. Never when unset.
+  pub dead_after_hours: Option<u64>,
+}
+
+impl Default for CleanupConfig {
+  fn default() -> Self {
+    CleanupConfig {
+      interval_mins: 60,
+      audit_retention_days: Some(90),
+      inactive_days: 30,
+      dead_after_hours: Some(72),
+    }
+  }
+}
+
+/// What chats switched on with /parental don't get to see or add.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ParentalConfig {
+  pub hidden_categories: Vec<String>,
+  pub hidden_tags: Vec<String>,
+  /// Case-insensitive words that block adding a torrent whose name contains them.
+  pub denylist: Vec<String>,
+  /// Lifts the restriction of a chat for a while with `/unlock <pin>`.
+  pub pin: Option<String>,
+}
+
+/// MQTT broker for the Home Assistant integration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+  pub host: String,
+  pub port: u16,
+  pub username: Option<String>,
+  pub password: Option<String>,
+  pub base_topic: String,
+  pub discovery_prefix: String,
+  /// Accept `pause_all` / `resume_all` on `<base_topic>/command`.
+  pub commands: bool,
+}
+
+impl Default for MqttConfig {
+  fn default() -> Self {
+    MqttConfig {
+      host: "localhost".to_string(),
+      port: 1883,
+      username: None,
+      password: None,
+      base_topic: "chatqbit".to_string(),
+      discovery_prefix: "homeassistant".to_string(),
+      commands: false,
+    }
+  }
+}
+
+/// Directory scanned for dropped .torrent files.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WatchFolderConfig {
+  pub path: PathBuf,
+  pub interval_secs: u64,
+  /// Where added files are announced, the `notify.chat_ids` when unset.
+  pub chat_id: Option<i64>,
+}
+
+impl Default for WatchFolderConfig {
+  fn default() -> Self {
+    WatchFolderConfig {
+      path: PathBuf::from("watch"),
+      interval_secs: 10,
+      chat_id: None,
+    }
+  }
+}
+
+impl Default for NotifyConfig {
+  fn default() -> Self {
+    NotifyConfig {
+      chat_ids: Vec::new(),
+      problem_after_secs: 10 * 60,
+      batch_window_secs: 60,
+      discord_webhooks: Vec::new(),
+      matrix: None,
+      auto_send_max_mb: 50,
+      completion_media: HashMap::new(),
+    }
+  }
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Config {
+      profiles: Profile::defaults(),
+      poll_interval_secs: 5,
+      notify: NotifyConfig::default(),
+      digest: DigestConfig::default(),
+      dashboard: DashboardConfig::default(),
+      webhooks: Vec::new(),
+      mqtt: None,
+      quota: QuotaConfig::default(),
+      restart_command: None,
+      aliases: HashMap::new(),
+      parental: ParentalConfig::default(),
+      cleanup: CleanupConfig::default(),
+      policies: Vec::new(),
+      dry_run: false,
+      recovery: Vec::new(),
+      watch_folder: None,
+      admin_chat_id: None,
+      tmdb_api_key: None,
+      opensubtitles: None,
+      search: Vec::new(),
+      sonarr: None,
+      radarr: None,
+      announce: None,
+      guard: GuardConfig::default(),
+    }
+  }
+}
+
+impl Config {
+  pub fn poll_interval(&self) -> Duration {
+    Duration::from_secs(self.poll_interval_secs.max(1))
+  }
+
+  /// The defaults when there is no config file. A file that can't be read or parsed is an
+  /// error, running without its restrictions would be worse than not running.
+  pub fn load() -> Result<Self, String> {
+    let path = env::var("CHATQBIT_CONFIG").unwrap_or_else(|_| "config.json".to_string());
+    match fs::read_to_string(&path) {
+      Ok(raw) => serde_json::from_str(&raw).map_err(|err| format!("Invalid config {path}: {err}")),
+      Err(err) if err.kind() == ErrorKind::NotFound => Ok(Config::default()),
+      Err(err) => Err(format!("Could not read the config {path}: {err}")),
+    }
+  }
+}
@@ -0,0 +1,978 @@
+use chrono_tz::Tz;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{
+  env,
+  sync::{Arc, Mutex},
+};
+
+use crate::{audit::AuditEntry, format::Units};
+
+/// SQLite store for everything the bot remembers per user, at `CHATQBIT_DB`.
+#[derive(Clone)]
+pub struct Db {
+  conn: Arc<Mutex<Connection>>,
+}
+
+/// The /quiethours window (UTC) and what was changed when it began, so it can be undone.
+#[derive(Debug, Clone, Default)]
+pub struct QuietHours {
+  /// `HH:MM`, `end` before `start` spans midnight.
+  pub start: String,
+  pub end: String,
+  /// Pause everything instead of switching to the alternative speed limits.
+  pub pause: bool,
+  pub active: bool,
+  /// Whether the window turned the alternative limits on, they stay on otherwise.
+  pub toggled_alt_speed: bool,
+  /// Torrents paused by the window, resumed when it ends.
+  pub paused: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NotifyPrefs {
+  pub completed: bool,
+  pub errors: bool,
+  pub digest: bool,
+}
+
+/// Per-user preferences changed through /settings.
+#[derive(Debug, Clone)]
+pub struct UserSettings {
+  pub page_size: u32,
+  pub watch_refresh_secs: u64,
+  pub inline_results: usize,
+  /// Torrent lists show size, ratio and peers too.
+  pub detailed_list: bool,
+  /// Default /list order, e.g. `added` or `size`.
+  pub list_sort: String,
+  /// Files per page in /files and the file picker.
+  pub files_page_size: u32,
+  /// IANA name, e.g. `Europe/Madrid`, timestamps are shown in it.
+  pub timezone: String,
+  /// Bit flags: 1 for SI sizes, 2 for speeds in bits.
+  pub unit_style: u8,
+}
+
+impl Default for UserSettings {
+  fn default() -> Self {
+    UserSettings {
+      page_size: 10,
+      watch_refresh_secs: 5,
+      inline_results: 50,
+      detailed_list: false,
+      list_sort: "added".to_string(),
+      files_page_size: 20,
+      timezone: "UTC".to_string(),
+      unit_style: 0,
+    }
+  }
+}
+
+impl UserSettings {
+  pub fn tz(&self) -> Tz {
+    self.timezone.parse().unwrap_or(Tz::UTC)
+  }
+
+  pub fn units(&self) -> Units {
+    Units {
+      si: self.unit_style & 1 != 0,
+      bits: self.unit_style & 2 != 0,
+    }
+  }
+}
+
+/// What happens to a torrent of a category once it completes, set with /oncomplete.
+#[derive(Debug, Clone, Default)]
+pub struct CompletionActions {
+  pub move_to: Option<String>,
+  pub tag: Option<String>,
+  pub pause: bool,
+  pub notify_chat: Option<i64>,
+  /// rclone destination, e.g. `gdrive:Media/TV`.
+  pub upload_to: Option<String>,
+}
+
+/// Options a user saved under a name with /template, picked from a keyboard when adding.
+#[derive(Debug, Clone, Default)]
+pub struct AddTemplate {
+  pub name: String,
+  pub category: Option<String>,
+  pub save_path: Option<String>,
+  /// Comma-separated.
+  pub tags: Option<String>,
+  pub paused: bool,
+  pub sequential: bool,
+}
+
+/// A magnet /addlater adds once `at` (unix seconds) has passed.
+#[derive(Debug, Clone)]
+pub struct ScheduledAdd {
+  pub id: i64,
+  pub user_id: u64,
+  pub chat_id: i64,
+  pub link: String,
+  pub at: i64,
+}
+
+/// Adds a column that tables created by older versions lack.
+fn add_column(
+  conn: &Connection,
+  table: &str,
+  column: &str,
+  definition: &str,
+) -> rusqlite::Result<()> {
+  let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+  let exists = stmt
+    .query_map([], |row| row.get::<_, String>(1))?
+    .any(|name| name.is_ok_and(|name| name == column));
+  if !exists {
+    conn.execute(
+      &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+      [],
+    )?;
+  }
+  Ok(())
+}
+
+impl Db {
+  pub fn open() -> rusqlite::Result<Self> {
+    let path = env::var("CHATQBIT_DB").unwrap_or_else(|_| "chatqbit.db".to_string());
+    let conn = Connection::open(path)?;
+    conn.execute_batch(
+      "CREATE TABLE IF NOT EXISTS notify_prefs (
+        user_id INTEGER PRIMARY KEY,
+        completed INTEGER NOT NULL DEFAULT 0,
+        errors INTEGER NOT NULL DEFAULT 0,
+        digest INTEGER NOT NULL DEFAULT 0
+      );
+      CREATE TABLE IF NOT EXISTS quota_usage (
+        user_id INTEGER NOT NULL,
+        hash TEXT NOT NULL,
+        bytes INTEGER,
+        added_at INTEGER NOT NULL,
+        PRIMARY KEY (user_id, hash)
+      );
+      CREATE TABLE IF NOT EXISTS features (
+        name TEXT PRIMARY KEY,
+        enabled INTEGER NOT NULL
+      );
+      CREATE TABLE IF NOT EXISTS audit_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        user_id INTEGER NOT NULL,
+        user_name TEXT NOT NULL,
+        action TEXT NOT NULL,
+        hash TEXT,
+        at INTEGER NOT NULL
+      );
+      CREATE TABLE IF NOT EXISTS languages (
+        user_id INTEGER PRIMARY KEY,
+        code TEXT NOT NULL
+      );
+      CREATE TABLE IF NOT EXISTS user_settings (
+        user_id INTEGER PRIMARY KEY,
+        page_size INTEGER NOT NULL,
+        watch_refresh_secs INTEGER NOT NULL,
+        inline_results INTEGER NOT NULL,
+        detailed_list INTEGER NOT NULL DEFAULT 0,
+        list_sort TEXT NOT NULL DEFAULT 'added',
+        files_page_size INTEGER NOT NULL DEFAULT 20,
+        timezone TEXT NOT NULL DEFAULT 'UTC',
+        unit_style INTEGER NOT NULL DEFAULT 0
+      );
+      CREATE TABLE IF NOT EXISTS restricted_chats (
+        chat_id INTEGER PRIMARY KEY
+      );
+      CREATE TABLE IF NOT EXISTS auto_send (
+        hash TEXT PRIMARY KEY,
+        chat_id INTEGER NOT NULL
+      );
+      CREATE TABLE IF NOT EXISTS completion_actions (
+        category TEXT PRIMARY KEY,
+        move_to TEXT,
+        tag TEXT,
+        pause INTEGER NOT NULL DEFAULT 0,
+        notify_chat INTEGER,
+        upload_to TEXT
+      );
+      CREATE TABLE IF NOT EXISTS quiet_hours (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        start_time TEXT NOT NULL,
+        end_time TEXT NOT NULL,
+        pause INTEGER NOT NULL DEFAULT 0,
+        active INTEGER NOT NULL DEFAULT 0,
+        toggled_alt_speed INTEGER NOT NULL DEFAULT 0,
+        paused TEXT NOT NULL DEFAULT ''
+      );
+      CREATE TABLE IF NOT EXISTS milestones (
+        hash TEXT NOT NULL,
+        chat_id INTEGER NOT NULL,
+        PRIMARY KEY (hash, chat_id)
+      );
+      CREATE TABLE IF NOT EXISTS callback_tokens (
+        token TEXT PRIMARY KEY,
+        payload TEXT NOT NULL,
+        used_at INTEGER NOT NULL
+      );
+      CREATE TABLE IF NOT EXISTS add_templates (
+        user_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        category TEXT,
+        save_path TEXT,
+        tags TEXT,
+        paused INTEGER NOT NULL DEFAULT 0,
+        sequential INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (user_id, name)
+      );
+      CREATE TABLE IF NOT EXISTS scheduled_adds (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        user_id INTEGER NOT NULL,
+        chat_id INTEGER NOT NULL,
+        link TEXT NOT NULL,
+        at INTEGER NOT NULL
+      );
+      CREATE TABLE IF NOT EXISTS guest_invites (
+        token TEXT PRIMARY KEY,
+        hours INTEGER NOT NULL,
+        created_by INTEGER NOT NULL,
+        expires_at INTEGER NOT NULL
+      );
+      CREATE TABLE IF NOT EXISTS guests (
+        user_id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        until INTEGER NOT NULL
+      );",
+    )?;
+    add_column(
+      &conn,
+      "user_settings",
+      "detailed_list",
+      "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column(
+      &conn,
+      "user_settings",
+      "list_sort",
+      "TEXT NOT NULL DEFAULT 'added'",
+    )?;
+    add_column(
+      &conn,
+      "user_settings",
+      "files_page_size",
+      "INTEGER NOT NULL DEFAULT 20",
+    )?;
+    add_column(&conn, "completion_actions", "upload_to", "TEXT")?;
+    add_column(
+      &conn,
+      "user_settings",
+      "timezone",
+      "TEXT NOT NULL DEFAULT 'UTC'",
+    )?;
+    add_column(
+      &conn,
+      "user_settings",
+      "unit_style",
+      "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    Ok(Db {
+      conn: Arc::new(Mutex::new(conn)),
+    })
+  }
+
+  fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+    self
+      .conn
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  pub fn notify_prefs(&self, user_id: u64) -> rusqlite::Result<NotifyPrefs> {
+    let prefs = self
+      .conn()
+      .query_row(
+        "SELECT completed, errors, digest FROM notify_prefs WHERE user_id = ?1",
+        params![user_id],
+        |row| {
+          Ok(NotifyPrefs {
+            completed: row.get(0)?,
+            errors: row.get(1)?,
+            digest: row.get(2)?,
+          })
+        },
+      )
+      .optional()?;
+    Ok(prefs.unwrap_or_default())
+  }
+
+  pub fn set_notify_prefs(&self, user_id: u64, prefs: NotifyPrefs) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "INSERT INTO notify_prefs (user_id, completed, errors, digest) VALUES (?1, ?2, ?3, ?4)
+       ON CONFLICT(user_id) DO UPDATE SET
+         completed = excluded.completed, errors = excluded.errors, digest = excluded.digest",
+      params![user_id, prefs.completed, prefs.errors, prefs.digest],
+    )?;
+    Ok(())
+  }
+
+  /// Users that opted into the notification picked by `wants`.
+  pub fn notify_subscribers(&self, wants: fn(&NotifyPrefs) -> bool) -> rusqlite::Result<Vec<u64>> {
+    let conn = self.conn();
+    let mut stmt = conn.prepare("SELECT user_id, completed, errors, digest FROM notify_prefs")?;
+    let rows = stmt.query_map([], |row| {
+      Ok((
+        row.get::<_, u64>(0)?,
+        NotifyPrefs {
+          completed: row.get(1)?,
+          errors: row.get(2)?,
+          digest: row.get(3)?,
+        },
+      ))
+    })?;
+
+    let mut users = Vec::new();
+    for row in rows {
+      let (user_id, prefs) = row?;
+      if wants(&prefs) {
+        users.push(user_id);
+      }
+    }
+    Ok(users)
+  }
+
+  /// Remembers that `user_id` added `hash`, with its size when that is already known.
+  pub fn record_add(
+    &self,
+    user_id: u64,
+    hash: &str,
+    added_at: i64,
+    bytes: Option<u64>,
+  ) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "INSERT OR IGNORE INTO quota_usage (user_id, hash, bytes, added_at) VALUES (?1, ?2, ?3, ?4)",
+      params![user_id, hash, bytes, added_at],
+    )?;
+    Ok(())
+  }
+
+  /// Torrents added by `user_id` since `since`, with their size if already known.
+  pub fn adds_since(
+    &self,
+    user_id: u64,
+    since: i64,
+  ) -> rusqlite::Result<Vec<(String, Option<u64>)>> {
+    let conn = self.conn();
+    let mut stmt =
+      conn.prepare("SELECT hash, bytes FROM quota_usage WHERE user_id = ?1 AND added_at >= ?2")?;
+    let rows = stmt.query_map(params![user_id, since], |row| {
+      Ok((row.get(0)?, row.get(1)?))
+    })?;
+    rows.collect()
+  }
+
+  /// Fills in the size of the adds of `hash` that didn't have one, returning who made them.
+  pub fn set_added_size(&self, hash: &str, bytes: u64) -> rusqlite::Result<Vec<u64>> {
+    let conn = self.conn();
+    let mut stmt =
+      conn.prepare("SELECT user_id FROM quota_usage WHERE hash = ?1 AND bytes IS NULL")?;
+    let users = stmt
+      .query_map(params![hash], |row| row.get(0))?
+      .collect::<rusqlite::Result<Vec<u64>>>()?;
+    conn.execute(
+      "UPDATE quota_usage SET bytes = ?2 WHERE hash = ?1 AND bytes IS NULL",
+      params![hash, bytes],
+    )?;
+    Ok(users)
+  }
+
+  /// Everyone recorded as having added `hash`.
+  pub fn adders(&self, hash: &str) -> rusqlite::Result<Vec<u64>> {
+    let conn = self.conn();
+    let mut stmt = conn.prepare("SELECT user_id FROM quota_usage WHERE hash = ?1")?;
+    let users = stmt
+      .query_map(params![hash], |row| row.get(0))?
+      .collect::<rusqlite::Result<Vec<u64>>>()?;
+    Ok(users)
+  }
+
+  pub fn remove_add(&self, user_id: u64, hash: &str) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "DELETE FROM quota_usage WHERE user_id = ?1 AND hash = ?2",
+      params![user_id, hash],
+    )?;
+    Ok(())
+  }
+
+  pub fn feature(&self, name: &str) -> rusqlite::Result<Option<bool>> {
+    self
+      .conn()
+      .query_row(
+        "SELECT enabled FROM features WHERE name = ?1",
+        params![name],
+        |row| row.get(0),
+      )
+      .optional()
+  }
+
+  pub fn set_feature(&self, name: &str, enabled: bool) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "INSERT INTO features (name, enabled) VALUES (?1, ?2)
+       ON CONFLICT(name) DO UPDATE SET enabled = excluded.enabled",
+      params![name, enabled],
+    )?;
+    Ok(())
+  }
+
+  pub fn log_action(&self, entry: &AuditEntry) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "INSERT INTO audit_log (user_id, user_name, action, hash, at) VALUES (?1, ?2, ?3, ?4, ?5)",
+      params![
+        entry.user_id,
+        entry.user_name,
+        entry.action,
+        entry.hash,
+        entry.at
+      ],
+    )?;
+    Ok(())
+  }
+
+  /// Audit log entries, newest first.
+  pub fn history(&self, limit: u32, offset: u32) -> rusqlite::Result<Vec<AuditEntry>> {
+    let conn = self.conn();
+    let mut stmt = conn.prepare(
+      "SELECT user_id, user_name, action, hash, at FROM audit_log
+       ORDER BY id DESC LIMIT ?1 OFFSET ?2",
+    )?;
+    let rows = stmt.query_map(params![limit, offset], |row| {
+      Ok(AuditEntry {
+        user_id: row.get(0)?,
+        user_name: row.get(1)?,
+        action: row.get(2)?,
+        hash: row.get(3)?,
+        at: row.get(4)?,
+      })
+    })?;
+    rows.collect()
+  }
+
+  pub fn history_len(&self) -> rusqlite::Result<u32> {
+    self
+      .conn()
+      .query_row("SELECT COUNT(*) FROM audit_log", [], |row| row.get(0))
+  }
+
+  pub fn language(&self, user_id: u64) -> rusqlite::Result<Option<String>> {
+    self
+      .conn()
+      .query_row(
+        "SELECT code FROM languages WHERE user_id = ?1",
+        params![user_id],
+        |row| row.get(0),
+      )
+      .optional()
+  }
+
+  pub fn set_language(&self, user_id: u64, code: &str) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "INSERT INTO languages (user_id, code) VALUES (?1, ?2)
+       ON CONFLICT(user_id) DO UPDATE SET code = excluded.code",
+      params![user_id, code],
+    )?;
+    Ok(())
+  }
+
+  pub fn settings(&self, user_id: u64) -> rusqlite::Result<UserSettings> {
+    let settings = self
+      .conn()
+      .query_row(
+        "SELECT page_size, watch_refresh_secs, inline_results, detailed_list, list_sort,
+           files_page_size, timezone, unit_style
+         FROM user_settings WHERE user_id = ?1",
+        params![user_id],
+        |row| {
+          Ok(UserSettings {
+            page_size: row.get(0)?,
+            watch_refresh_secs: row.get(1)?,
+            inline_results: row.get(2)?,
+            detailed_list: row.get(3)?,
+            list_sort: row.get(4)?,
+            files_page_size: row.get(5)?,
+            timezone: row.get(6)?,
+            unit_style: row.get(7)?,
+          })
+        },
+      )
+      .optional()?;
+    Ok(settings.unwrap_or_default())
+  }
+
+  pub fn set_settings(&self, user_id: u64, settings: &UserSettings) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "INSERT INTO user_settings
+         (user_id, page_size, watch_refresh_secs, inline_results, detailed_list, list_sort,
+          files_page_size, timezone, unit_style)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+       ON CONFLICT(user_id) DO UPDATE SET
+         page_size = excluded.page_size,
+         watch_refresh_secs = excluded.watch_refresh_secs,
+         inline_results = excluded.inline_results,
+         detailed_list = excluded.detailed_list,
+         list_sort = excluded.list_sort,
+         files_page_size = excluded.files_page_size,
+         timezone = excluded.timezone,
+         unit_style = excluded.unit_style",
+      params![
+        user_id,
+        settings.page_size,
+        settings.watch_refresh_secs,
+        settings.inline_results,
+        settings.detailed_list,
+        settings.list_sort,
+        settings.files_page_size,
+        settings.timezone,
+        settings.unit_style
+      ],
+    )?;
+    Ok(())
+  }
+
+  pub fn restricted_chats(&self) -> rusqlite::Result<Vec<i64>> {
+    let conn = self.conn();
+    let mut stmt = conn.prepare("SELECT chat_id FROM restricted_chats")?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+  }
+
+  pub fn set_restricted_chat(&self, chat_id: i64, restricted: bool) -> rusqlite::Result<()> {
+    let sql = if restricted {
+      "INSERT OR IGNORE INTO restricted_chats (chat_id) VALUES (?1)"
+    } else {
+      "DELETE FROM restricted_chats WHERE chat_id = ?1"
+    };
+    self.conn().execute(sql, params![chat_id])?;
+    Ok(())
+  }
+
+  pub fn prune_audit_log(&self, before: i64) -> rusqlite::Result<usize> {
+    self
+      .conn()
+      .execute("DELETE FROM audit_log WHERE at < ?1", params![before])
+  }
+
+  pub fn count_audit_log(&self, before: i64) -> rusqlite::Result<usize> {
+    self.conn().query_row(
+      "SELECT COUNT(*) FROM audit_log WHERE at < ?1",
+      params![before],
+      |row| row.get(0),
+    )
+  }
+
+  pub fn count_quota_usage(&self, before: i64) -> rusqlite::Result<usize> {
+    self.conn().query_row(
+      "SELECT COUNT(*) FROM quota_usage WHERE added_at < ?1",
+      params![before],
+      |row| row.get(0),
+    )
+  }
+
+  pub fn prune_quota_usage(&self, before: i64) -> rusqlite::Result<usize> {
+    self.conn().execute(
+      "DELETE FROM quota_usage WHERE added_at < ?1",
+      params![before],
+    )
+  }
+
+  pub fn set_auto_send(&self, hash: &str, chat_id: i64) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "INSERT INTO auto_send (hash, chat_id) VALUES (?1, ?2)
+       ON CONFLICT(hash) DO UPDATE SET chat_id = excluded.chat_id",
+      params![hash, chat_id],
+    )?;
+    Ok(())
+  }
+
+  /// The chat waiting for `hash`, removed so the files are only sent once.
+  pub fn take_auto_send(&self, hash: &str) -> rusqlite::Result<Option<i64>> {
+    let conn = self.conn();
+    let chat_id = conn
+      .query_row(
+        "SELECT chat_id FROM auto_send WHERE hash = ?1",
+        params![hash],
+        |row| row.get(0),
+      )
+      .optional()?;
+    conn.execute("DELETE FROM auto_send WHERE hash = ?1", params![hash])?;
+    Ok(chat_id)
+  }
+
+  pub fn completion_actions(&self) -> rusqlite::Result<Vec<(String, CompletionActions)>> {
+    let conn = self.conn();
+    let mut stmt = conn.prepare(
+      "SELECT category, move_to, tag, pause, notify_chat, upload_to FROM completion_actions
+       ORDER BY category",
+    )?;
+    let rows = stmt.query_map([], |row| {
+      Ok((
+        row.get(0)?,
+        CompletionActions {
+          move_to: row.get(1)?,
+          tag: row.get(2)?,
+          pause: row.get(3)?,
+          notify_chat: row.get(4)?,
+          upload_to: row.get(5)?,
+        },
+      ))
+    })?;
+    rows.collect()
+  }
+
+  pub fn category_actions(&self, category: &str) -> rusqlite::Result<Option<CompletionActions>> {
+    self
+      .conn()
+      .query_row(
+        "SELECT move_to, tag, pause, notify_chat, upload_to FROM completion_actions
+         WHERE category = ?1",
+        params![category],
+        |row| {
+          Ok(CompletionActions {
+            move_to: row.get(0)?,
+            tag: row.get(1)?,
+            pause: row.get(2)?,
+            notify_chat: row.get(3)?,
+            upload_to: row.get(4)?,
+          })
+        },
+      )
+      .optional()
+  }
+
+  pub fn set_category_actions(
+    &self,
+    category: &str,
+    actions: &CompletionActions,
+  ) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "INSERT INTO completion_actions (category, move_to, tag, pause, notify_chat, upload_to)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+       ON CONFLICT(category) DO UPDATE SET
+         move_to = excluded.move_to,
+         tag = excluded.tag,
+         pause = excluded.pause,
+         notify_chat = excluded.notify_chat,
+         upload_to = excluded.upload_to",
+      params![
+        category,
+        actions.move_to,
+        actions.tag,
+        actions.pause,
+        actions.notify_chat,
+        actions.upload_to
+      ],
+    )?;
+    Ok(())
+  }
+
+  pub fn clear_category_actions(&self, category: &str) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "DELETE FROM completion_actions WHERE category = ?1",
+      params![category],
+    )?;
+    Ok(())
+  }
+
+  /// The payload behind `token` and when it was last put on a keyboard.
+  pub fn callback_token(&self, token: &str) -> rusqlite::Result<Option<(String, i64)>> {
+    self
+      .conn()
+      .query_row(
+        "SELECT payload, used_at FROM callback_tokens WHERE token = ?1",
+        params![token],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+      )
+      .optional()
+  }
+
+  pub fn set_callback_token(&self, token: &str, payload: &str, at: i64) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "INSERT INTO callback_tokens (token, payload, used_at) VALUES (?1, ?2, ?3)
+       ON CONFLICT(token) DO UPDATE SET payload = excluded.payload, used_at = excluded.used_at",
+      params![token, payload, at],
+    )?;
+    Ok(())
+  }
+
+  pub fn prune_callback_tokens(&self, before: i64) -> rusqlite::Result<usize> {
+    self.conn().execute(
+      "DELETE FROM callback_tokens WHERE used_at < ?1",
+      params![before],
+    )
+  }
+
+  pub fn quiet_hours(&self) -> rusqlite::Result<Option<QuietHours>> {
+    self
+      .conn()
+      .query_row(
+        "SELECT start_time, end_time, pause, active, toggled_alt_speed, paused
+         FROM quiet_hours WHERE id = 1",
+        [],
+        |row| {
+          let paused: String = row.get(5)?;
+          Ok(QuietHours {
+            start: row.get(0)?,
+            end: row.get(1)?,
+            pause: row.get(2)?,
+            active: row.get(3)?,
+            toggled_alt_speed: row.get(4)?,
+            paused: paused
+              .split('|')
+              .filter(|hash| !hash.is_empty())
+              .map(str::to_string)
+              .collect(),
+          })
+        },
+      )
+      .optional()
+  }
+
+  pub fn set_quiet_hours(&self, hours: &QuietHours) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "INSERT INTO quiet_hours (id, start_time, end_time, pause, active, toggled_alt_speed, paused)
+       VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6)
+       ON CONFLICT(id) DO UPDATE SET
+         start_time = excluded.start_time,
+         end_time = excluded.end_time,
+         pause = excluded.pause,
+         active = excluded.active,
+         toggled_alt_speed = excluded.toggled_alt_speed,
+         paused = excluded.paused",
+      params![
+        hours.start,
+        hours.end,
+        hours.pause,
+        hours.active,
+        hours.toggled_alt_speed,
+        hours.paused.join("|")
+      ],
+    )?;
+    Ok(())
+  }
+
+  pub fn clear_quiet_hours(&self) -> rusqlite::Result<()> {
+    self.conn().execute("DELETE FROM quiet_hours", [])?;
+    Ok(())
+  }
+
+  /// Subscribes `chat_id` to the progress milestones of `hash`, or unsubscribes it when it
+  /// already was. Returns whether it is subscribed now.
+  pub fn toggle_milestones(&self, hash: &str, chat_id: i64) -> rusqlite::Result<bool> {
+    let conn = self.conn();
+    let removed = conn.execute(
+      "DELETE FROM milestones WHERE hash = ?1 AND chat_id = ?2",
+      params![hash, chat_id],
+    )?;
+    if removed > 0 {
+      return Ok(false);
+    }
+    conn.execute(
+      "INSERT INTO milestones (hash, chat_id) VALUES (?1, ?2)",
+      params![hash, chat_id],
+    )?;
+    Ok(true)
+  }
+
+  pub fn milestone_chats(&self, hash: &str) -> rusqlite::Result<Vec<i64>> {
+    let conn = self.conn();
+    let mut stmt = conn.prepare("SELECT chat_id FROM milestones WHERE hash = ?1")?;
+    let rows = stmt.query_map(params![hash], |row| row.get(0))?;
+    rows.collect()
+  }
+
+  pub fn clear_milestones(&self, hash: &str) -> rusqlite::Result<()> {
+    self
+      .conn()
+      .execute("DELETE FROM milestones WHERE hash = ?1", params![hash])?;
+    Ok(())
+  }
+
+  pub fn templates(&self, user_id: u64) -> rusqlite::Result<Vec<AddTemplate>> {
+    let conn = self.conn();
+    let mut stmt = conn.prepare(
+      "SELECT name, category, save_path, tags, paused, sequential FROM add_templates
+       WHERE user_id = ?1 ORDER BY name",
+    )?;
+    let rows = stmt.query_map(params![user_id], |row| {
+      Ok(AddTemplate {
+        name: row.get(0)?,
+        category: row.get(1)?,
+        save_path: row.get(2)?,
+        tags: row.get(3)?,
+        paused: row.get(4)?,
+        sequential: row.get(5)?,
+      })
+    })?;
+    rows.collect()
+  }
+
+  pub fn template(&self, user_id: u64, name: &str) -> rusqlite::Result<Option<AddTemplate>> {
+    self
+      .conn()
+      .query_row(
+        "SELECT name, category, save_path, tags, paused, sequential FROM add_templates
+         WHERE user_id = ?1 AND name = ?2",
+        params![user_id, name],
+        |row| {
+          Ok(AddTemplate {
+            name: row.get(0)?,
+            category: row.get(1)?,
+            save_path: row.get(2)?,
+            tags: row.get(3)?,
+            paused: row.get(4)?,
+            sequential: row.get(5)?,
+          })
+        },
+      )
+      .optional()
+  }
+
+  pub fn set_template(&self, user_id: u64, template: &AddTemplate) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "INSERT INTO add_templates (user_id, name, category, save_path, tags, paused, sequential)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+       ON CONFLICT(user_id, name) DO UPDATE SET
+         category = excluded.category,
+         save_path = excluded.save_path,
+         tags = excluded.tags,
+         paused = excluded.paused,
+         sequential = excluded.sequential",
+      params![
+        user_id,
+        template.name,
+        template.category,
+        template.save_path,
+        template.tags,
+        template.paused,
+        template.sequential
+      ],
+    )?;
+    Ok(())
+  }
+
+  /// Whether there was a template of that name.
+  pub fn delete_template(&self, user_id: u64, name: &str) -> rusqlite::Result<bool> {
+    let deleted = self.conn().execute(
+      "DELETE FROM add_templates WHERE user_id = ?1 AND name = ?2",
+      params![user_id, name],
+    )?;
+    Ok(deleted > 0)
+  }
+
+  /// Returns the id of the new entry.
+  pub fn schedule_add(
+    &self,
+    user_id: u64,
+    chat_id: i64,
+    link: &str,
+    at: i64,
+  ) -> rusqlite::Result<i64> {
+    let conn = self.conn();
+    conn.execute(
+      "INSERT INTO scheduled_adds (user_id, chat_id, link, at) VALUES (?1, ?2, ?3, ?4)",
+      params![user_id, chat_id, link, at],
+    )?;
+    Ok(conn.last_insert_rowid())
+  }
+
+  /// Pending adds, soonest first, of one user or everyone's that are due by `until`.
+  pub fn scheduled_adds(
+    &self,
+    user_id: Option<u64>,
+    until: i64,
+  ) -> rusqlite::Result<Vec<ScheduledAdd>> {
+    let conn = self.conn();
+    let mut stmt = conn.prepare(
+      "SELECT id, user_id, chat_id, link, at FROM scheduled_adds
+       WHERE (?1 IS NULL OR user_id = ?1) AND at <= ?2 ORDER BY at",
+    )?;
+    let rows = stmt.query_map(params![user_id, until], |row| {
+      Ok(ScheduledAdd {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        chat_id: row.get(2)?,
+        link: row.get(3)?,
+        at: row.get(4)?,
+      })
+    })?;
+    rows.collect()
+  }
+
+  /// Whether the user had a pending add with that id.
+  pub fn cancel_scheduled_add(&self, id: i64, user_id: u64) -> rusqlite::Result<bool> {
+    let deleted = self.conn().execute(
+      "DELETE FROM scheduled_adds WHERE id = ?1 AND user_id = ?2",
+      params![id, user_id],
+    )?;
+    Ok(deleted > 0)
+  }
+
+  pub fn clear_scheduled_add(&self, id: i64) -> rusqlite::Result<()> {
+    self
+      .conn()
+      .execute("DELETE FROM scheduled_adds WHERE id = ?1", params![id])?;
+    Ok(())
+  }
+
+  pub fn add_guest_invite(
+    &self,
+    token: &str,
+    hours: u32,
+    created_by: u64,
+    expires_at: i64,
+  ) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "INSERT INTO guest_invites (token, hours, created_by, expires_at) VALUES (?1, ?2, ?3, ?4)",
+      params![token, hours, created_by, expires_at],
+    )?;
+    Ok(())
+  }
+
+  /// The hours an invite grants if it hasn't expired, removed so each link works once.
+  pub fn take_guest_invite(&self, token: &str, now: i64) -> rusqlite::Result<Option<u32>> {
+    let conn = self.conn();
+    let hours = conn
+      .query_row(
+        "SELECT hours FROM guest_invites WHERE token = ?1 AND expires_at > ?2",
+        params![token, now],
+        |row| row.get(0),
+      )
+      .optional()?;
+    conn.execute(
+      "DELETE FROM guest_invites WHERE token = ?1 OR expires_at <= ?2",
+      params![token, now],
+    )?;
+    Ok(hours)
+  }
+
+  /// Guests with their name and when their access ends, soonest first.
+  pub fn guests(&self) -> rusqlite::Result<Vec<(u64, String, i64)>> {
+    let conn = self.conn();
+    let mut stmt = conn.prepare("SELECT user_id, name, until FROM guests ORDER BY until")?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    rows.collect()
+  }
+
+  pub fn set_guest(&self, user_id: u64, name: &str, until: i64) -> rusqlite::Result<()> {
+    self.conn().execute(
+      "INSERT INTO guests (user_id, name, until) VALUES (?1, ?2, ?3)
+       ON CONFLICT(user_id) DO UPDATE SET name = excluded.name, until = excluded.until",
+      params![user_id, name, until],
+    )?;
+    Ok(())
+  }
+
+  /// Whether the user was a guest.
+  pub fn remove_guest(&self, user_id: u64) -> rusqlite::Result<bool> {
+    let deleted = self
+      .conn()
+      .execute("DELETE FROM guests WHERE user_id = ?1", params![user_id])?;
+    Ok(deleted > 0)
+  }
+}
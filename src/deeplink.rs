@@ -0,0 +1,20 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use crate::torrent::hash_magnet;
+
+/// The magnet behind a `t.me/<bot>?start=<payload>` link. Telegram only allows `A-Z a-z 0-9 _ -`
+/// in the payload, so it's an info hash (hex or base32) or URL-safe base64 of a magnet or of the raw 20-byte
+/// hash.
+pub fn decode(payload: &str) -> Option<String> {
+  let payload = payload.trim();
+  if let Some(magnet) = hash_magnet(payload) {
+    return Some(magnet);
+  }
+  let bytes = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+  if bytes.len() == 20 {
+    return Some(format!("magnet:?xt=urn:btih:{}", hex::encode(bytes)));
+  }
+  String::from_utf8(bytes)
+    .ok()
+    .filter(|text| text.starts_with("magnet:?"))
+}
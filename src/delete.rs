@@ -0,0 +1,160 @@
+use teloxide::{prelude::*, types::InlineKeyboardMarkup};
+
+use crate::{
+  audit, callback_data,
+  db::Db,
+  i18n::{self, Lang},
+  parental::Parental,
+  select::{self, Action},
+  torrent::{events::TorrentEvents, TorrentApi},
+  undo::{self, Undos},
+  watch, HandlerResult, MyDialogue, State,
+};
+
+fn short_hash(hash: &str) -> &str {
+  hash.get(..8).unwrap_or(hash)
+}
+
+/// Starts the confirmation: the next message has to be the torrent's name or short hash.
+pub async fn ask(
+  bot: &Bot,
+  dialogue: &MyDialogue,
+  chat: ChatId,
+  hash: &str,
+  name: &str,
+  lang: Lang,
+) -> HandlerResult {
+  dialogue
+    .update(State::ConfirmDeleteData {
+      hash: hash.to_string(),
+      name: name.to_string(),
+    })
+    .await?;
+  bot
+    .send_message(
+      chat,
+      lang.tf(
+        "delete.type_name",
+        &[("name", &name), ("short", &short_hash(hash))],
+      ),
+    )
+    .await?;
+  Ok(())
+}
+
+/// `/deletedata <hash>` offers to delete a torrent together with its files, without a hash it
+/// asks which one.
+pub async fn deletedata(
+  bot: Bot,
+  msg: Message,
+  hash: String,
+  events: TorrentEvents,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  if hash.trim().is_empty() {
+    return select::offer(&bot, &msg, Action::DeleteData, &events, &parental, &db).await;
+  }
+  let lang = i18n::lang(&db, msg.from());
+  let snapshot = {
+    let torrents = events.torrents();
+    let current = torrents.borrow();
+    let all: Vec<_> = current
+      .values()
+      .filter(|torrent| !parental.hides(msg.chat.id, torrent))
+      .collect();
+    watch::find(&all, hash.trim()).cloned()
+  };
+  let Some(snapshot) = snapshot else {
+    bot
+      .send_message(msg.chat.id, lang.t("delete.not_found"))
+      .await?;
+    return Ok(());
+  };
+
+  let keyboard = InlineKeyboardMarkup::new([[callback_data::button(
+    lang.t("delete.button"),
+    format!("deldata:{}", snapshot.hash),
+  )]]);
+  bot
+    .send_message(
+      msg.chat.id,
+      lang.tf("delete.prompt", &[("name", &snapshot.name)]),
+    )
+    .reply_markup(keyboard)
+    .await?;
+  Ok(())
+}
+
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  dialogue: MyDialogue,
+  events: TorrentEvents,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  bot.answer_callback_query(q.id).await?;
+  let (Some(hash), Some(msg)) = (
+    q.data
+      .as_deref()
+      .and_then(|data| data.strip_prefix("deldata:")),
+    q.message.as_ref(),
+  ) else {
+    return Ok(());
+  };
+  let Some(name) = events
+    .torrents()
+    .borrow()
+    .get(hash)
+    .map(|torrent| torrent.name.clone())
+  else {
+    bot
+      .send_message(msg.chat.id, lang.t("delete.not_found"))
+      .await?;
+    return Ok(());
+  };
+
+  bot.edit_message_reply_markup(msg.chat.id, msg.id).await?;
+  ask(&bot, &dialogue, msg.chat.id, hash, &name, lang).await
+}
+
+/// Deletes the data only when the reply names the torrent, anything else calls it off.
+pub async fn confirm(
+  bot: Bot,
+  dialogue: MyDialogue,
+  (hash, name): (String, String),
+  msg: Message,
+  torrent: TorrentApi,
+  undos: Undos,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  dialogue.exit().await?;
+  let typed = msg.text().map(str::trim).unwrap_or_default();
+  let confirmed =
+    typed.eq_ignore_ascii_case(name.trim()) || typed.eq_ignore_ascii_case(short_hash(&hash));
+  if !confirmed {
+    bot
+      .send_message(msg.chat.id, lang.t("delete.mismatch"))
+      .await?;
+    return Ok(());
+  }
+
+  let deleted = undo::prepare(&torrent, std::slice::from_ref(&hash)).await;
+  torrent.delete(&hash, true).await?;
+  if let Some(user) = msg.from() {
+    audit::record(&db, user, "deleted a torrent and its files", Some(&hash));
+  }
+
+  let undo = undos.keep(deleted);
+  let mut reply = bot.send_message(msg.chat.id, lang.tf("delete.done", &[("name", &name)]));
+  if let Some(id) = undo {
+    reply = reply.reply_markup(undo::keyboard(id, lang));
+  }
+  let sent = reply.await?;
+  if let Some(id) = undo {
+    undos.expire(&bot, msg.chat.id, sent.id, id);
+  }
+  Ok(())
+}
@@ -0,0 +1,211 @@
+use regex::Regex;
+use std::sync::{Arc, OnceLock};
+use teloxide::{net::Download, prelude::*, types::InlineKeyboardMarkup};
+
+use crate::{
+  add_keyboard, audit, callback_data,
+  config::Config,
+  db::Db,
+  duplicates,
+  guard::{self, Candidate},
+  i18n,
+  parental::Parental,
+  quota::Quotas,
+  torrent::{self, metainfo, AddTorrentArg, TorrentApi},
+  HandlerResult,
+};
+
+fn magnet_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"magnet:\?\S+").expect("magnet pattern is valid"))
+}
+
+/// What a plain message would add, if anything.
+enum Found {
+  Magnet(String),
+  File { id: String, name: String },
+}
+
+/// A [`Found`] with the .torrent file downloaded.
+enum Loaded {
+  Magnet(String),
+  File { name: String, contents: Vec<u8> },
+}
+
+/// File id and name of an attached .torrent file.
+pub fn torrent_file(msg: &Message) -> Option<(String, String)> {
+  let doc = msg.document()?;
+  let name = doc.file_name.clone().unwrap_or_default();
+  let is_torrent = name.to_lowercase().ends_with(".torrent")
+    || doc
+      .mime_type
+      .as_ref()
+      .is_some_and(|mime| mime.essence_str() == "application/x-bittorrent");
+  is_torrent.then(|| (doc.file.id.clone(), name))
+}
+
+fn find(msg: &Message) -> Option<Found> {
+  if msg.document().is_some() {
+    return torrent_file(msg).map(|(id, name)| Found::File { id, name });
+  }
+
+  let text = msg.text()?;
+  if let Some(magnet) = magnet_re().find(text) {
+    return Some(Found::Magnet(magnet.as_str().to_string()));
+  }
+  torrent::hash_magnet(text).map(Found::Magnet)
+}
+
+pub fn has_torrent(msg: Message) -> bool {
+  find(&msg).is_some()
+}
+
+/// Offers to add a magnet, info hash or .torrent file sent without /magnet.
+pub async fn offer(bot: Bot, msg: Message, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let keyboard = InlineKeyboardMarkup::new([[
+    callback_data::button(lang.t("detect.add"), "add:yes"),
+    callback_data::button(lang.t("detect.dismiss"), "add:no"),
+  ]]);
+  bot
+    .send_message(msg.chat.id, lang.t("detect.prompt"))
+    .reply_to_message_id(msg.id)
+    .reply_markup(keyboard)
+    .await?;
+  Ok(())
+}
+
+/// The torrent is read back from the message the prompt replied to, so nothing has to fit
+/// into the callback data.
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  let Some(prompt) = q.message.as_ref() else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+  if q.data.as_deref() != Some("add:yes") {
+    bot.answer_callback_query(q.id).await?;
+    bot.delete_message(prompt.chat.id, prompt.id).await?;
+    return Ok(());
+  }
+  let Some(found) = prompt.reply_to_message().and_then(find) else {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("detect.gone"))
+      .await?;
+    return Ok(());
+  };
+  // a .torrent file tells everything the checks want to know, so it's read first
+  let (found, candidate, file_hash, file_name) = match found {
+    Found::File { id, name } => {
+      let file = bot.get_file(id).await?;
+      let mut contents = Vec::new();
+      bot.download_file(&file.path, &mut contents).await?;
+      let metainfo = metainfo::parse(&contents);
+      let candidate = match &metainfo {
+        Some(metainfo) => Candidate::from_metainfo(metainfo),
+        None => Candidate {
+          name: Some(name.clone()),
+          ..Candidate::default()
+        },
+      };
+      let hash = metainfo.map(|metainfo| metainfo.hash);
+      let file_name = Some(name.clone());
+      (Loaded::File { name, contents }, candidate, hash, file_name)
+    }
+    Found::Magnet(magnet) => {
+      let candidate = Candidate::from_link(&magnet);
+      (Loaded::Magnet(magnet), candidate, None, None)
+    }
+  };
+  if candidate
+    .name
+    .iter()
+    .chain(&file_name)
+    .any(|name| parental.blocks(prompt.chat.id, name))
+  {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("parental.blocked"))
+      .await?;
+    return Ok(());
+  }
+  if let Err(reason) = quotas.check(q.from.id.0, candidate.size, lang) {
+    bot.answer_callback_query(q.id).text(reason).await?;
+    return Ok(());
+  }
+
+  let link = match &found {
+    Loaded::Magnet(magnet) => Some(magnet.clone()),
+    Loaded::File { .. } => None,
+  };
+  if let Some(rejection) = guard::check(&torrent, &config.guard, &candidate).await? {
+    bot.answer_callback_query(q.id).await?;
+    bot.delete_message(prompt.chat.id, prompt.id).await?;
+    return guard::reject(&bot, prompt.chat.id, &rejection, link.as_deref(), lang).await;
+  }
+
+  let (arg, hash) = match found {
+    Loaded::Magnet(magnet) => {
+      let hash = torrent::extract_info_hash(&magnet);
+      let duplicate = duplicates::check_duplicates(
+        &torrent,
+        hash.as_deref(),
+        candidate.name.as_deref(),
+        candidate.size,
+      )
+      .await?;
+      if let Some(duplicate) = duplicate {
+        bot.answer_callback_query(q.id).await?;
+        bot.delete_message(prompt.chat.id, prompt.id).await?;
+        return duplicates::warn(&bot, prompt.chat.id, &duplicate, &magnet, lang).await;
+      }
+      let arg = AddTorrentArg {
+        urls: vec![magnet],
+        ..Default::default()
+      };
+      (arg, hash)
+    }
+    Loaded::File { name, contents } => {
+      let arg = AddTorrentArg {
+        files: vec![(name, contents)],
+        ..Default::default()
+      };
+      (arg, file_hash)
+    }
+  };
+
+  bot.answer_callback_query(q.id).await?;
+  let reply = match torrent.add_torrent(&arg).await {
+    Ok(()) => {
+      if let Some(hash) = &hash {
+        quotas.record(q.from.id.0, hash, candidate.size);
+        // a file's content was checked already
+        if link.is_some() {
+          guard::recheck(hash, prompt.chat.id);
+        }
+      }
+      audit::record(&db, &q.from, "added a torrent", hash.as_deref());
+      lang.t("torrent.added").to_string()
+    }
+    Err(err) => err.to_string(),
+  };
+  let edit = bot.edit_message_text(prompt.chat.id, prompt.id, reply);
+  match hash {
+    Some(hash) => {
+      edit
+        .reply_markup(add_keyboard(&hash, &config, lang))
+        .await?
+    }
+    None => edit.await?,
+  };
+  Ok(())
+}
@@ -0,0 +1,204 @@
+use chrono::{DateTime, Datelike, NaiveTime, Utc, Weekday};
+use std::{
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+  config::DigestConfig,
+  format::format_bytes,
+  i18n::Lang,
+  notify::{Notifiers, NotifyKind},
+  torrent::events::{ServerState, TorrentEvent, TorrentEvents, TorrentSnapshot},
+};
+
+// keeps the message readable on busy days
+const MAX_LISTED: usize = 20;
+
+/// What the policies and the cleanup would have deleted with `dry_run` on, reported by the next
+/// digest.
+#[derive(Clone, Default)]
+pub struct DryRuns(Arc<Mutex<DryRunLog>>);
+
+#[derive(Default)]
+struct DryRunLog {
+  torrents: Vec<String>,
+  // only the latest count, every cleanup run finds the old rows again
+  records: Option<String>,
+}
+
+impl DryRuns {
+  pub fn torrent(&self, line: String) {
+    log::info!("Dry run, not removing {line}");
+    self.log().torrents.push(line);
+  }
+
+  pub fn records(&self, line: String) {
+    log::info!("Dry run, not pruning {line}");
+    self.log().records = Some(line);
+  }
+
+  fn log(&self) -> std::sync::MutexGuard<'_, DryRunLog> {
+    self
+      .0
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+struct Schedule {
+  time: NaiveTime,
+  weekday: Option<Weekday>,
+}
+
+impl Schedule {
+  fn from_config(config: &DigestConfig) -> Option<Self> {
+    let time = NaiveTime::parse_from_str(config.time.as_deref()?, "%H:%M")
+      .map_err(|err| log::error!("Invalid digest time: {err}"))
+      .ok()?;
+    let weekday = match config.weekday.as_deref() {
+      Some(day) => Some(
+        day
+          .parse::<Weekday>()
+          .map_err(|_| log::error!("Invalid digest weekday: {day}"))
+          .ok()?,
+      ),
+      None => None,
+    };
+    Some(Schedule { time, weekday })
+  }
+
+  fn until_next(&self, now: DateTime<Utc>) -> Duration {
+    let mut next = now.date_naive().and_time(self.time).and_utc();
+    while next <= now || self.weekday.is_some_and(|day| next.weekday() != day) {
+      next += chrono::Duration::days(1);
+    }
+    (next - now).to_std().unwrap_or_default()
+  }
+
+  fn title(&self, lang: Lang) -> &'static str {
+    match self.weekday {
+      Some(_) => lang.t("digest.weekly"),
+      None => lang.t("digest.daily"),
+    }
+  }
+}
+
+/// Posts a summary of completed torrents, transfer totals and dry-run deletions at the
+/// configured time (UTC).
+pub fn spawn(notifier: Notifiers, config: &DigestConfig, events: TorrentEvents, dry_runs: DryRuns) {
+  let Some(schedule) = Schedule::from_config(config) else {
+    return;
+  };
+  let mut torrent_events = events.subscribe();
+  let server_state = events.server_state();
+
+  tokio::spawn(async move {
+    let mut completed = Vec::new();
+    // all-time totals at the previous digest, to report what moved in between
+    let mut baseline = None;
+
+    loop {
+      let sleep = tokio::time::sleep(schedule.until_next(Utc::now()));
+      tokio::pin!(sleep);
+      loop {
+        tokio::select! {
+          _ = &mut sleep => break,
+          event = torrent_events.recv() => match event {
+            Ok(TorrentEvent::Completed(torrent)) => completed.push(torrent),
+            Ok(_) => {}
+            Err(RecvError::Lagged(skipped)) => log::warn!("Digest skipped {skipped} events"),
+            Err(RecvError::Closed) => return,
+          },
+        }
+      }
+
+      let state = server_state.borrow().clone();
+      // notifications go to every configured chat, so they stay in the default language
+      let lang = Lang::En;
+      let mut text = render(schedule.title(lang), &completed, &state, baseline, lang);
+      text.push_str(&render_dry_runs(
+        &std::mem::take(&mut *dry_runs.log()),
+        lang,
+      ));
+      baseline = Some((state.alltime_dl, state.alltime_ul));
+      completed.clear();
+      notifier.send(NotifyKind::Digest, &text).await;
+    }
+  });
+}
+
+fn render(
+  title: &str,
+  completed: &[TorrentSnapshot],
+  state: &Arc<ServerState>,
+  baseline: Option<(u64, u64)>,
+  lang: Lang,
+) -> String {
+  let mut text = lang.tf(
+    "digest.completed",
+    &[("title", &title), ("count", &completed.len())],
+  );
+  text.push('\n');
+  for torrent in completed.iter().take(MAX_LISTED) {
+    text.push_str(&format!(
+      "• {} ({})\n",
+      torrent.name,
+      format_bytes(torrent.size)
+    ));
+  }
+  if completed.len() > MAX_LISTED {
+    let more = completed.len() - MAX_LISTED;
+    text.push_str(&lang.tf("digest.more", &[("count", &more)]));
+    text.push('\n');
+  }
+
+  let (downloaded, uploaded, key) = match baseline {
+    Some((dl, ul)) => (
+      state.alltime_dl.saturating_sub(dl),
+      state.alltime_ul.saturating_sub(ul),
+      "digest.since_last",
+    ),
+    None => (
+      state.dl_info_data,
+      state.up_info_data,
+      "digest.this_session",
+    ),
+  };
+  let transfer = lang.tf(
+    key,
+    &[
+      ("downloaded", &format_bytes(downloaded)),
+      ("uploaded", &format_bytes(uploaded)),
+    ],
+  );
+  let totals = lang.tf(
+    "digest.totals",
+    &[
+      ("free", &format_bytes(state.free_space_on_disk)),
+      ("ratio", &state.global_ratio),
+    ],
+  );
+  text.push_str(&format!("\n{transfer}\n{totals}"));
+  text
+}
+
+fn render_dry_runs(log: &DryRunLog, lang: Lang) -> String {
+  if log.torrents.is_empty() && log.records.is_none() {
+    return String::new();
+  }
+  let mut text = format!("\n\n{}\n", lang.t("digest.dry_run"));
+  for line in log.torrents.iter().take(MAX_LISTED) {
+    text.push_str(&format!("• {line}\n"));
+  }
+  if log.torrents.len() > MAX_LISTED {
+    let more = log.torrents.len() - MAX_LISTED;
+    text.push_str(&lang.tf("digest.more", &[("count", &more)]));
+    text.push('\n');
+  }
+  if let Some(records) = &log.records {
+    text.push_str(&format!("• {records}\n"));
+  }
+  text.trim_end().to_string()
+}
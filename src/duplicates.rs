@@ -0,0 +1,181 @@
+use std::sync::Arc;
+use teloxide::{prelude::*, types::InlineKeyboardMarkup};
+
+use crate::{
+  add_keyboard, audit, callback_data,
+  config::Config,
+  db::Db,
+  guard::{self, Candidate},
+  i18n::{self, Lang},
+  parental::{self, Parental},
+  quota::Quotas,
+  torrent::{self, AddTorrentArg, ApiResult, TorrentApi},
+  HandlerResult,
+};
+
+/// A torrent already in qBittorrent that an addition looks like.
+pub enum Duplicate {
+  /// The very same torrent, adding it again does nothing.
+  SameHash(String),
+  /// Most likely the same content from another tracker.
+  SameContent(String),
+}
+
+/// Lowercase words without the bracketed tags release groups and trackers put around names,
+/// so `[TGx] Show.Name.S01.1080p` and `Show Name S01 1080p` compare equal.
+fn normalize(name: &str) -> String {
+  let mut words = String::new();
+  let mut depth = 0;
+  for c in name.to_lowercase().chars() {
+    match c {
+      '[' | '(' | '{' => depth += 1,
+      ']' | ')' | '}' => depth = (depth - 1).max(0),
+      c if depth == 0 && c.is_alphanumeric() => words.push(c),
+      _ if depth == 0 => words.push(' '),
+      _ => {}
+    }
+  }
+  let words: Vec<&str> = words.split_whitespace().collect();
+  // a file extension isn't part of the name
+  match words.split_last() {
+    Some((last, rest)) if !rest.is_empty() && ["mkv", "mp4", "avi"].contains(last) => {
+      rest.join(" ")
+    }
+    _ => words.join(" "),
+  }
+}
+
+/// Sizes within a percent of each other, releases of the same content rarely differ more.
+fn similar_size(a: u64, b: u64) -> bool {
+  a.abs_diff(b) <= a.max(b) / 100
+}
+
+/// The exact length a magnet announces with `xl=`, if any.
+pub fn magnet_size(magnet: &str) -> Option<u64> {
+  magnet
+    .split(['?', '&'])
+    .find_map(|param| param.strip_prefix("xl="))
+    .and_then(|size| size.parse().ok())
+}
+
+/// Looks for a torrent with the same info hash, or with the same normalized name and (when
+/// both are known) a similar size.
+pub async fn check_duplicates(
+  torrent: &TorrentApi,
+  hash: Option<&str>,
+  name: Option<&str>,
+  size: Option<u64>,
+) -> ApiResult<Option<Duplicate>> {
+  let normalized = name.map(normalize).filter(|name| !name.is_empty());
+  for existing in torrent.list().await? {
+    if hash.is_some_and(|hash| existing.has_hash(hash)) {
+      return Ok(Some(Duplicate::SameHash(existing.name)));
+    }
+    let same_name = normalized
+      .as_ref()
+      .is_some_and(|name| *name == normalize(&existing.name));
+    let size_agrees = match size {
+      Some(size) => similar_size(size, existing.size),
+      None => true,
+    };
+    if same_name && size_agrees {
+      return Ok(Some(Duplicate::SameContent(existing.name)));
+    }
+  }
+  Ok(None)
+}
+
+/// Tells the user what is already there, a likely duplicate comes with an "Add anyway" button.
+pub async fn warn(
+  bot: &Bot,
+  chat: ChatId,
+  duplicate: &Duplicate,
+  link: &str,
+  lang: Lang,
+) -> HandlerResult {
+  match duplicate {
+    Duplicate::SameHash(name) => {
+      let text = lang.tf("duplicate.same_hash", &[("name", name)]);
+      bot.send_message(chat, text).await?;
+    }
+    Duplicate::SameContent(name) => {
+      let keyboard = InlineKeyboardMarkup::new([[callback_data::button(
+        lang.t("duplicate.add_anyway"),
+        format!("dup:{link}"),
+      )]]);
+      let text = lang.tf("duplicate.same_content", &[("name", name)]);
+      bot.send_message(chat, text).reply_markup(keyboard).await?;
+    }
+  }
+  Ok(())
+}
+
+/// "Add anyway" skips the duplicate check, the parental, quota and add policy checks still
+/// apply.
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  let (Some(link), Some(prompt)) = (
+    q.data
+      .as_deref()
+      .and_then(|data| data.strip_prefix("dup:"))
+      .map(str::to_string),
+    q.message.as_ref(),
+  ) else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+  if parental::magnet_name(&link).is_some_and(|name| parental.blocks(prompt.chat.id, &name)) {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("parental.blocked"))
+      .await?;
+    return Ok(());
+  }
+  if let Err(reason) = quotas.check(q.from.id.0, magnet_size(&link), lang) {
+    bot.answer_callback_query(q.id).text(reason).await?;
+    return Ok(());
+  }
+
+  bot.answer_callback_query(q.id).await?;
+  let candidate = Candidate::from_link(&link);
+  if let Some(rejection) = guard::check(&torrent, &config.guard, &candidate).await? {
+    bot
+      .edit_message_reply_markup(prompt.chat.id, prompt.id)
+      .await?;
+    return guard::reject(&bot, prompt.chat.id, &rejection, Some(&link), lang).await;
+  }
+  let hash = torrent::extract_info_hash(&link);
+  let arg = AddTorrentArg {
+    urls: vec![link],
+    ..Default::default()
+  };
+  let reply = match torrent.add_torrent(&arg).await {
+    Ok(()) => {
+      if let Some(hash) = &hash {
+        quotas.record(q.from.id.0, hash, candidate.size);
+        guard::recheck(hash, prompt.chat.id);
+      }
+      audit::record(&db, &q.from, "added a duplicate anyway", hash.as_deref());
+      lang.t("torrent.added").to_string()
+    }
+    Err(err) => err.to_string(),
+  };
+  let edit = bot.edit_message_text(prompt.chat.id, prompt.id, reply);
+  match hash {
+    Some(hash) => {
+      edit
+        .reply_markup(add_keyboard(&hash, &config, lang))
+        .await?
+    }
+    None => edit.await?,
+  };
+  Ok(())
+}
@@ -0,0 +1,195 @@
+use std::{
+  collections::HashMap,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, RwLock,
+  },
+};
+use teloxide::{prelude::*, types::InlineKeyboardMarkup};
+
+use crate::{audit, auth::Access, callback_data, db::Db, i18n, HandlerResult};
+
+/// Subsystems that can be switched off at runtime with /features. All start enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+  Notifications,
+  Digest,
+  Webhooks,
+  Quotas,
+  InlineSearch,
+}
+
+impl Feature {
+  const ALL: [Feature; 5] = [
+    Feature::Notifications,
+    Feature::Digest,
+    Feature::Webhooks,
+    Feature::Quotas,
+    Feature::InlineSearch,
+  ];
+
+  fn key(self) -> &'static str {
+    match self {
+      Feature::Notifications => "notifications",
+      Feature::Digest => "digest",
+      Feature::Webhooks => "webhooks",
+      Feature::Quotas => "quotas",
+      Feature::InlineSearch => "inline",
+    }
+  }
+
+  fn label(self) -> &'static str {
+    match self {
+      Feature::Notifications => "Notifications",
+      Feature::Digest => "Summary digest",
+      Feature::Webhooks => "Webhooks",
+      Feature::Quotas => "Download quotas",
+      Feature::InlineSearch => "Inline search",
+    }
+  }
+
+  fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|feature| feature.key() == key)
+  }
+}
+
+// stored next to the features, but switched with /maintenance instead of /features
+const MAINTENANCE: &str = "maintenance";
+
+/// Runtime on/off switches, cached in memory and persisted in the database.
+#[derive(Clone)]
+pub struct Features {
+  db: Db,
+  state: Arc<RwLock<HashMap<Feature, bool>>>,
+  maintenance: Arc<AtomicBool>,
+}
+
+impl Features {
+  pub fn load(db: Db) -> Self {
+    let mut state = HashMap::new();
+    for feature in Feature::ALL {
+      match db.feature(feature.key()) {
+        Ok(Some(enabled)) => {
+          state.insert(feature, enabled);
+        }
+        Ok(None) => {}
+        Err(err) => log::error!("Could not load feature {}: {err}", feature.key()),
+      }
+    }
+    let maintenance = db.feature(MAINTENANCE).unwrap_or_else(|err| {
+      log::error!("Could not load maintenance mode: {err}");
+      None
+    });
+    Features {
+      db,
+      state: Arc::new(RwLock::new(state)),
+      maintenance: Arc::new(AtomicBool::new(maintenance.unwrap_or(false))),
+    }
+  }
+
+  pub fn in_maintenance(&self) -> bool {
+    self.maintenance.load(Ordering::Relaxed)
+  }
+
+  pub fn set_maintenance(&self, on: bool) -> rusqlite::Result<()> {
+    self.db.set_feature(MAINTENANCE, on)?;
+    self.maintenance.store(on, Ordering::Relaxed);
+    Ok(())
+  }
+
+  pub fn enabled(&self, feature: Feature) -> bool {
+    let state = self
+      .state
+      .read()
+      .unwrap_or_else(|poisoned| poisoned.into_inner());
+    state.get(&feature).copied().unwrap_or(true)
+  }
+
+  /// Flips the feature and returns its new state.
+  pub fn toggle(&self, feature: Feature) -> rusqlite::Result<bool> {
+    let enabled = !self.enabled(feature);
+    self.db.set_feature(feature.key(), enabled)?;
+    self
+      .state
+      .write()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+      .insert(feature, enabled);
+    Ok(enabled)
+  }
+
+  fn keyboard(&self) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(Feature::ALL.map(|feature| {
+      let mark = if self.enabled(feature) {
+        "🟢"
+      } else {
+        "🔴"
+      };
+      vec![callback_data::button(
+        format!("{mark} {}", feature.label()),
+        format!("feature:{}", feature.key()),
+      )]
+    }))
+  }
+}
+
+pub async fn features(
+  bot: Bot,
+  msg: Message,
+  access: Access,
+  features: Features,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  if !msg.from().is_some_and(|user| access.is_admin(user.id)) {
+    bot
+      .send_message(msg.chat.id, lang.t("features.admins_only"))
+      .await?;
+    return Ok(());
+  }
+  bot
+    .send_message(msg.chat.id, lang.t("features.prompt"))
+    .reply_markup(features.keyboard())
+    .await?;
+  Ok(())
+}
+
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  access: Access,
+  features: Features,
+  db: Db,
+) -> HandlerResult {
+  if !access.is_admin(q.from.id) {
+    bot
+      .answer_callback_query(q.id)
+      .text(i18n::lang(&db, Some(&q.from)).t("features.admins_only"))
+      .await?;
+    return Ok(());
+  }
+  let Some(feature) = q
+    .data
+    .as_deref()
+    .and_then(|data| data.strip_prefix("feature:"))
+    .and_then(Feature::from_key)
+  else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+
+  let enabled = features.toggle(feature)?;
+  let action = format!(
+    "switched {} {}",
+    feature.label(),
+    if enabled { "on" } else { "off" }
+  );
+  audit::record(&db, &q.from, action, None);
+  bot.answer_callback_query(q.id).await?;
+  if let Some(msg) = q.message {
+    bot
+      .edit_message_reply_markup(msg.chat.id, msg.id)
+      .reply_markup(features.keyboard())
+      .await?;
+  }
+  Ok(())
+}
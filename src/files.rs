@@ -0,0 +1,135 @@
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardMarkup, ParseMode},
+};
+
+use crate::{
+  callback_data,
+  db::{Db, UserSettings},
+  i18n::{self, Lang},
+  list::page_row,
+  parental::Parental,
+  render,
+  select::{self, Action},
+  settings,
+  torrent::{events::TorrentEvents, ApiResult, TorrentApi},
+  watch, HandlerResult,
+};
+
+/// One page of the files of `hash`, `page` is clamped to the last one.
+pub async fn page(
+  torrent: &TorrentApi,
+  hash: &str,
+  name: &str,
+  page: usize,
+  settings: &UserSettings,
+  lang: Lang,
+) -> ApiResult<(String, InlineKeyboardMarkup)> {
+  let page_size = settings.files_page_size as usize;
+  let files = torrent.files(hash).await?;
+  if files.is_empty() {
+    return Ok((
+      lang.t("picker.no_metadata").to_string(),
+      InlineKeyboardMarkup::default(),
+    ));
+  }
+  let pages = files.len().div_ceil(page_size);
+  let page = page.min(pages - 1);
+
+  let mut text = lang.tf(
+    "files.title",
+    &[
+      ("name", &render::bold(name)),
+      ("count", &files.len()),
+      ("page", &(page + 1)),
+      ("pages", &pages),
+    ],
+  );
+  for file in files.iter().skip(page * page_size).take(page_size) {
+    text.push_str(&format!("\n{}", render::file_line(file, settings.units())));
+  }
+
+  let row = page_row(page, page + 1 < pages, lang, |page| {
+    format!("fl:{hash}:{page}")
+  });
+  let priorities = vec![callback_data::button(
+    lang.t("files.priorities"),
+    format!("files:{hash}:{page}"),
+  )];
+  Ok((text, InlineKeyboardMarkup::new([row, priorities])))
+}
+
+/// `/files <hash>` lists a torrent's files a page at a time, without a hash it asks which one.
+pub async fn files(
+  bot: Bot,
+  msg: Message,
+  hash: String,
+  torrent: TorrentApi,
+  events: TorrentEvents,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  if hash.trim().is_empty() {
+    return select::offer(&bot, &msg, Action::Files, &events, &parental, &db).await;
+  }
+  let lang = i18n::lang(&db, msg.from());
+  let snapshot = {
+    let torrents = events.torrents();
+    let current = torrents.borrow();
+    let all: Vec<_> = current
+      .values()
+      .filter(|torrent| !parental.hides(msg.chat.id, torrent))
+      .collect();
+    watch::find(&all, hash.trim()).cloned()
+  };
+  let Some(snapshot) = snapshot else {
+    bot
+      .send_message(msg.chat.id, lang.t("files.not_found"))
+      .await?;
+    return Ok(());
+  };
+
+  let settings = settings::of(&db, msg.from());
+  let (text, keyboard) = page(&torrent, &snapshot.hash, &snapshot.name, 0, &settings, lang).await?;
+  bot
+    .send_message(msg.chat.id, text)
+    .parse_mode(ParseMode::Html)
+    .reply_markup(keyboard)
+    .await?;
+  Ok(())
+}
+
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  torrent: TorrentApi,
+  events: TorrentEvents,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  bot.answer_callback_query(q.id).await?;
+  let mut parts = q.data.as_deref().unwrap_or_default().split(':').skip(1);
+  let (Some(hash), Some(number), Some(msg)) = (parts.next(), parts.next(), q.message.as_ref())
+  else {
+    return Ok(());
+  };
+  let Some(name) = events
+    .torrents()
+    .borrow()
+    .get(hash)
+    .filter(|torrent| !parental.hides(msg.chat.id, torrent))
+    .map(|torrent| torrent.name.clone())
+  else {
+    return Ok(());
+  };
+
+  let lang = i18n::lang(&db, Some(&q.from));
+  let settings = settings::of(&db, Some(&q.from));
+  let (text, keyboard) = page(&torrent, hash, &name, number.parse()?, &settings, lang).await?;
+  bot
+    .edit_message_text(msg.chat.id, msg.id, text)
+    .parse_mode(ParseMode::Html)
+    .reply_markup(keyboard)
+    .await?;
+  Ok(())
+}
@@ -0,0 +1,123 @@
+use teloxide::{prelude::*, types::InlineKeyboardMarkup};
+
+use crate::{
+  audit, callback_data,
+  db::Db,
+  i18n::{self, Lang},
+  parental::Parental,
+  send, settings,
+  torrent::{
+    events::{TorrentEvents, TorrentSnapshot},
+    TorrentApi,
+  },
+  HandlerResult,
+};
+
+/// How well `name` matches `query`, lower is better: a substring, every word somewhere,
+/// or the query's letters in order ("brkbad" finds "Breaking Bad").
+fn score(name: &str, query: &str) -> Option<u8> {
+  let name = name.to_lowercase();
+  if name.contains(query) {
+    return Some(0);
+  }
+  if query.split_whitespace().all(|word| name.contains(word)) {
+    return Some(1);
+  }
+  let mut letters = name.chars();
+  query
+    .chars()
+    .filter(|c| !c.is_whitespace())
+    .all(|c| letters.any(|letter| letter == c))
+    .then_some(2)
+}
+
+fn keyboard(torrent: &TorrentSnapshot, lang: Lang) -> InlineKeyboardMarkup {
+  let hash = &torrent.hash;
+  InlineKeyboardMarkup::new([
+    vec![
+      callback_data::button(lang.t("find.pause"), format!("find:pause:{hash}")),
+      callback_data::button(lang.t("find.resume"), format!("find:resume:{hash}")),
+    ],
+    vec![
+      send::auto_send_button(hash, lang),
+      callback_data::button(lang.t("delete.button"), format!("deldata:{hash}")),
+    ],
+  ])
+}
+
+/// `/find <text>` searches the torrent names, each match comes with its own buttons.
+pub async fn find(
+  bot: Bot,
+  msg: Message,
+  query: String,
+  events: TorrentEvents,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let query = query.trim().to_lowercase();
+  if query.is_empty() {
+    bot.send_message(msg.chat.id, lang.t("find.usage")).await?;
+    return Ok(());
+  }
+
+  let torrents = events.torrents().borrow().clone();
+  let mut matches: Vec<_> = torrents
+    .values()
+    .filter(|torrent| !parental.hides(msg.chat.id, torrent))
+    .filter_map(|torrent| score(&torrent.name, &query).map(|score| (score, torrent)))
+    .collect();
+  if matches.is_empty() {
+    bot.send_message(msg.chat.id, lang.t("find.none")).await?;
+    return Ok(());
+  }
+  matches.sort_by(|(a, x), (b, y)| a.cmp(b).then_with(|| x.name.cmp(&y.name)));
+
+  let page_size = settings::of(&db, msg.from()).page_size as usize;
+  for (_, torrent) in matches.iter().take(page_size) {
+    let text = format!(
+      "{}\n{:.0}% · {}",
+      torrent.name,
+      torrent.progress * 100.0,
+      &torrent.hash[..torrent.hash.len().min(8)]
+    );
+    bot
+      .send_message(msg.chat.id, text)
+      .reply_markup(keyboard(torrent, lang))
+      .await?;
+  }
+  if matches.len() > page_size {
+    bot
+      .send_message(
+        msg.chat.id,
+        lang.tf("find.more", &[("count", &(matches.len() - page_size))]),
+      )
+      .await?;
+  }
+  Ok(())
+}
+
+pub async fn callback(bot: Bot, q: CallbackQuery, torrent: TorrentApi, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  let mut parts = q.data.as_deref().unwrap_or_default().splitn(3, ':').skip(1);
+  let (action, hash) = (parts.next(), parts.next());
+  let reply = match (action, hash) {
+    (Some("pause"), Some(hash)) => {
+      torrent.pause(hash).await?;
+      audit::record(&db, &q.from, "paused a torrent", Some(hash));
+      Some(lang.t("find.paused"))
+    }
+    (Some("resume"), Some(hash)) => {
+      torrent.resume(hash).await?;
+      audit::record(&db, &q.from, "resumed a torrent", Some(hash));
+      Some(lang.t("find.resumed"))
+    }
+    _ => None,
+  };
+  let mut answer = bot.answer_callback_query(q.id);
+  if let Some(reply) = reply {
+    answer = answer.text(reply);
+  }
+  answer.await?;
+  Ok(())
+}
@@ -0,0 +1,212 @@
+use chrono::{TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::{i18n::Lang, torrent::Tracker};
+
+// Telegram rejects longer messages
+const MAX_MESSAGE_CHARS: usize = 4096;
+
+const BINARY_UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+const SI_UNITS: [&str; 5] = ["B", "kB", "MB", "GB", "TB"];
+const BIT_UNITS: [&str; 5] = ["bit", "kbit", "Mbit", "Gbit", "Tbit"];
+
+fn scaled(amount: u64, base: f64, units: &[&str; 5]) -> String {
+  let mut value = amount as f64;
+  let mut unit = 0;
+  while value >= base && unit < units.len() - 1 {
+    value /= base;
+    unit += 1;
+  }
+  if unit == 0 {
+    format!("{amount} {}", units[0])
+  } else {
+    format!("{value:.2} {}", units[unit])
+  }
+}
+
+/// How a user wants sizes and speeds shown, picked in /settings. The default is binary bytes,
+/// which is also what shared chats get.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Units {
+  /// Powers of 1000 (kB, MB) instead of 1024 (KiB, MiB).
+  pub si: bool,
+  /// Speeds in bits per second (Mbit/s), always powers of 1000 like network gear shows them.
+  pub bits: bool,
+}
+
+impl Units {
+  pub fn bytes(self, bytes: u64) -> String {
+    if self.si {
+      scaled(bytes, 1000.0, &SI_UNITS)
+    } else {
+      scaled(bytes, 1024.0, &BINARY_UNITS)
+    }
+  }
+
+  pub fn speed(self, bytes_per_sec: u64) -> String {
+    if self.bits {
+      format!("{}/s", scaled(bytes_per_sec * 8, 1000.0, &BIT_UNITS))
+    } else {
+      format!("{}/s", self.bytes(bytes_per_sec))
+    }
+  }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+  Units::default().bytes(bytes)
+}
+
+pub fn format_speed(bytes_per_sec: u64) -> String {
+  Units::default().speed(bytes_per_sec)
+}
+
+/// qBittorrent reports 8640000 (100 days) when it can't estimate.
+pub fn format_eta(seconds: i64) -> String {
+  match seconds {
+    s if !(0..8_640_000).contains(&s) => "∞".to_string(),
+    s if s >= 3600 => format!("{}h {}m", s / 3600, s % 3600 / 60),
+    s if s >= 60 => format!("{}m {}s", s / 60, s % 60),
+    s => format!("{s}s"),
+  }
+}
+
+/// `3d 4h 12m`, leaving out leading zero units.
+pub fn format_duration(seconds: i64) -> String {
+  let (days, hours, minutes) = (
+    seconds / 86_400,
+    seconds % 86_400 / 3600,
+    seconds % 3600 / 60,
+  );
+  match (days, hours) {
+    (0, 0) => format!("{minutes}m"),
+    (0, _) => format!("{hours}h {minutes}m"),
+    _ => format!("{days}d {hours}h {minutes}m"),
+  }
+}
+
+/// A Unix timestamp in the user's time zone, e.g. `2024-05-01 21:30 CEST`.
+pub fn format_timestamp(timestamp: i64, timezone: Tz) -> String {
+  Utc
+    .timestamp_opt(timestamp, 0)
+    .single()
+    .map(|at| {
+      at.with_timezone(&timezone)
+        .format("%Y-%m-%d %H:%M %Z")
+        .to_string()
+    })
+    .unwrap_or_default()
+}
+
+/// Trackers that failed or had something to say, one line each, or nothing when all is well.
+pub fn format_trackers(trackers: &[Tracker], lang: Lang) -> Option<String> {
+  let lines: Vec<String> = trackers
+    .iter()
+    // DHT, PeX and LSD are listed as disabled trackers
+    .filter(|tracker| tracker.status != 0 && (!tracker.is_working() || !tracker.msg.is_empty()))
+    .map(|tracker| {
+      let host = reqwest::Url::parse(&tracker.url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| tracker.url.clone());
+      let mark = if tracker.is_working() {
+        "ℹ️"
+      } else {
+        "❌"
+      };
+      match tracker.msg.as_str() {
+        "" => format!("{mark} {host}: {}", lang.t("trackers.not_working")),
+        msg => format!("{mark} {host}: {msg}"),
+      }
+    })
+    .collect();
+  (!lines.is_empty()).then(|| format!("{}\n{}", lang.t("trackers.title"), lines.join("\n")))
+}
+
+/// Buckets for qBittorrent's many torrent states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateGroup {
+  Downloading,
+  Stalled,
+  Seeding,
+  Paused,
+  Errored,
+  Other,
+}
+
+impl StateGroup {
+  pub const ALL: [StateGroup; 6] = [
+    StateGroup::Downloading,
+    StateGroup::Stalled,
+    StateGroup::Seeding,
+    StateGroup::Paused,
+    StateGroup::Errored,
+    StateGroup::Other,
+  ];
+
+  pub fn of(state: &str) -> Self {
+    match state {
+      "downloading" | "forcedDL" | "metaDL" | "forcedMetaDL" | "queuedDL" | "checkingDL"
+      | "allocating" => StateGroup::Downloading,
+      "stalledDL" => StateGroup::Stalled,
+      "uploading" | "forcedUP" | "stalledUP" | "queuedUP" | "checkingUP" => StateGroup::Seeding,
+      // qBittorrent 5 renamed paused to stopped
+      "pausedDL" | "pausedUP" | "stoppedDL" | "stoppedUP" => StateGroup::Paused,
+      "error" | "missingFiles" => StateGroup::Errored,
+      _ => StateGroup::Other,
+    }
+  }
+
+  pub fn emoji(self) -> &'static str {
+    match self {
+      StateGroup::Downloading => "⬇️",
+      StateGroup::Stalled => "🐢",
+      StateGroup::Seeding => "🌱",
+      StateGroup::Paused => "⏸",
+      StateGroup::Errored => "❗",
+      StateGroup::Other => "❔",
+    }
+  }
+
+  /// i18n key of the group's name.
+  pub fn key(self) -> &'static str {
+    match self {
+      StateGroup::Downloading => "state.downloading",
+      StateGroup::Stalled => "state.stalled",
+      StateGroup::Seeding => "state.seeding",
+      StateGroup::Paused => "state.paused",
+      StateGroup::Errored => "state.errored",
+      StateGroup::Other => "state.other",
+    }
+  }
+}
+
+const BAR_SEGMENTS: usize = 10;
+
+/// `progress` (0 to 1) as a 10-segment bar, e.g. `██████░░░░`.
+pub fn progress_bar(progress: f64) -> String {
+  let filled = ((progress.clamp(0.0, 1.0) * BAR_SEGMENTS as f64).round()) as usize;
+  format!(
+    "{}{}",
+    "█".repeat(filled),
+    "░".repeat(BAR_SEGMENTS - filled)
+  )
+}
+
+/// Splits `text` at line breaks into pieces Telegram accepts as single messages.
+pub fn split_message(text: &str) -> Vec<String> {
+  let mut pieces = vec![String::new()];
+  for line in text.lines() {
+    let current = pieces.last_mut().expect("starts with one piece");
+    if !current.is_empty() && current.chars().count() + line.chars().count() + 1 > MAX_MESSAGE_CHARS
+    {
+      pieces.push(String::new());
+    }
+    let current = pieces.last_mut().expect("starts with one piece");
+    if !current.is_empty() {
+      current.push('\n');
+    }
+    // a single line that is too long still has to be cut somewhere
+    current.extend(line.chars().take(MAX_MESSAGE_CHARS));
+  }
+  pieces
+}
@@ -0,0 +1,434 @@
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex, OnceLock},
+};
+use teloxide::{prelude::*, types::InlineKeyboardMarkup};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+  add_keyboard, audit,
+  auth::Access,
+  callback_data,
+  config::{Config, GuardConfig},
+  db::Db,
+  duplicates,
+  format::{format_bytes, StateGroup},
+  i18n::{self, Lang},
+  parental::{self, Parental},
+  quota::{Quotas, GIB},
+  torrent::{
+    self,
+    events::{TorrentEvent, TorrentEvents},
+    metainfo::Metainfo,
+    AddTorrentArg, ApiResult, TorrentApi,
+  },
+  HandlerResult,
+};
+
+/// What is known about a torrent before it's added. .torrent files tell everything, magnets
+/// only what they carry, and nothing is known about plain URLs.
+#[derive(Debug, Clone, Default)]
+pub struct Candidate {
+  pub name: Option<String>,
+  pub size: Option<u64>,
+  pub files: Vec<String>,
+  pub trackers: Vec<String>,
+}
+
+impl Candidate {
+  pub fn from_link(link: &str) -> Self {
+    if !link.starts_with("magnet:") {
+      return Candidate::default();
+    }
+    Candidate {
+      name: parental::magnet_name(link),
+      size: duplicates::magnet_size(link),
+      trackers: link
+        .split(['?', '&'])
+        .filter_map(|param| param.strip_prefix("tr="))
+        .map(parental::percent_decode)
+        .collect(),
+      ..Candidate::default()
+    }
+  }
+
+  pub fn from_metainfo(metainfo: &Metainfo) -> Self {
+    Candidate {
+      name: Some(metainfo.name.clone()),
+      size: Some(metainfo.size),
+      files: metainfo.files.clone(),
+      trackers: metainfo.trackers.clone(),
+    }
+  }
+}
+
+/// Why an add was turned down.
+pub enum Rejection {
+  TooLarge { size: u64, max: u64 },
+  BannedExtension(String),
+  BannedTracker(String),
+  TooManyDownloads(usize),
+}
+
+impl Rejection {
+  pub fn message(&self, lang: Lang) -> String {
+    match self {
+      Rejection::TooLarge { size, max } => lang.tf(
+        "guard.too_large",
+        &[("size", &format_bytes(*size)), ("max", &format_bytes(*max))],
+      ),
+      Rejection::BannedExtension(extension) => {
+        lang.tf("guard.extension", &[("extension", extension)])
+      }
+      Rejection::BannedTracker(tracker) => lang.tf("guard.tracker", &[("tracker", tracker)]),
+      Rejection::TooManyDownloads(max) => lang.tf("guard.downloads", &[("max", max)]),
+    }
+  }
+}
+
+fn banned_extension(guard: &GuardConfig, name: &str) -> Option<String> {
+  let (_, extension) = name.trim().rsplit_once('.')?;
+  guard
+    .banned_extensions
+    .iter()
+    .map(|banned| banned.trim_start_matches('.'))
+    .find(|banned| banned.eq_ignore_ascii_case(extension))
+    .map(str::to_lowercase)
+}
+
+/// The banned domain `tracker` belongs to, subdomains included.
+fn banned_tracker(guard: &GuardConfig, tracker: &str) -> Option<String> {
+  let host = reqwest::Url::parse(tracker)
+    .ok()?
+    .host_str()?
+    .to_lowercase();
+  guard
+    .banned_trackers
+    .iter()
+    .map(|domain| domain.trim().to_lowercase())
+    .find(|domain| host == *domain || host.ends_with(&format!(".{domain}")))
+}
+
+/// Downloads that are moving data or looking for peers, queued and paused ones wait anyway.
+async fn active_downloads(torrent: &TorrentApi) -> ApiResult<usize> {
+  let active = torrent
+    .list()
+    .await?
+    .iter()
+    .filter(|info| info.state != "queuedDL")
+    .filter(|info| {
+      matches!(
+        StateGroup::of(&info.state),
+        StateGroup::Downloading | StateGroup::Stalled
+      )
+    })
+    .count();
+  Ok(active)
+}
+
+/// The first limit on size, names or trackers the candidate breaks. Only what the candidate
+/// tells is checked, so an unknown size passes the size limit.
+fn check_content(guard: &GuardConfig, candidate: &Candidate) -> Option<Rejection> {
+  let max_size = guard.max_size_gib.map(|gib| gib.saturating_mul(GIB));
+  if let (Some(size), Some(max)) = (candidate.size, max_size) {
+    if size > max {
+      return Some(Rejection::TooLarge { size, max });
+    }
+  }
+  if let Some(extension) = candidate
+    .name
+    .iter()
+    .chain(&candidate.files)
+    .find_map(|name| banned_extension(guard, name))
+  {
+    return Some(Rejection::BannedExtension(extension));
+  }
+  candidate
+    .trackers
+    .iter()
+    .find_map(|tracker| banned_tracker(guard, tracker))
+    .map(Rejection::BannedTracker)
+}
+
+/// The first configured limit the candidate breaks, the downloads limit included.
+pub async fn check(
+  torrent: &TorrentApi,
+  guard: &GuardConfig,
+  candidate: &Candidate,
+) -> ApiResult<Option<Rejection>> {
+  if let Some(rejection) = check_content(guard, candidate) {
+    return Ok(Some(rejection));
+  }
+  if let Some(max) = guard.max_active_downloads {
+    if active_downloads(torrent).await? >= max {
+      return Ok(Some(Rejection::TooManyDownloads(max)));
+    }
+  }
+  Ok(None)
+}
+
+// magnets added without knowing their files, with the chat to tell if they break the policy
+fn pending() -> &'static Mutex<HashMap<String, ChatId>> {
+  static PENDING: OnceLock<Mutex<HashMap<String, ChatId>>> = OnceLock::new();
+  PENDING.get_or_init(Default::default)
+}
+
+/// Checks the torrent again once its metadata is known, see [`spawn`]. Only for adds whose
+/// files weren't known up front, i.e. magnets.
+pub fn recheck(hash: &str, chat: ChatId) {
+  pending()
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner())
+    .insert(hash.to_string(), chat);
+}
+
+/// Removes magnets, with what they downloaded so far, whose metadata shows they break the
+/// size or extension limits, or whose real name the restricted profile of their chat denies,
+/// and tells the chat that added them.
+pub fn spawn(
+  bot: Bot,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  db: Db,
+  parental: Parental,
+  events: TorrentEvents,
+) {
+  let mut events = events.subscribe();
+  tokio::spawn(async move {
+    loop {
+      let snapshot = match events.recv().await {
+        Ok(TorrentEvent::MetadataReceived(snapshot)) => snapshot,
+        Ok(TorrentEvent::Removed { hash, .. }) => {
+          pending()
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&hash);
+          continue;
+        }
+        Ok(_) => continue,
+        Err(RecvError::Lagged(skipped)) => {
+          log::warn!("The add policy missed {skipped} events");
+          continue;
+        }
+        Err(RecvError::Closed) => break,
+      };
+      let chat = pending()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .remove(&snapshot.hash);
+      let Some(chat) = chat else {
+        continue;
+      };
+
+      let lang = i18n::chat_lang(&db, chat);
+      // magnets without a dn= went past the parental check with no name at all
+      let text = if parental.blocks(chat, &snapshot.name) {
+        lang.tf("parental.removed", &[("name", &snapshot.name)])
+      } else {
+        let files = match torrent.files(&snapshot.hash).await {
+          Ok(files) => files.into_iter().map(|file| file.name).collect(),
+          Err(err) => {
+            log::warn!("Could not list the files of {}: {err}", snapshot.hash);
+            Vec::new()
+          }
+        };
+        let candidate = Candidate {
+          name: Some(snapshot.name.clone()),
+          size: Some(snapshot.size),
+          files,
+          trackers: Vec::new(),
+        };
+        let Some(rejection) = check_content(&config.guard, &candidate) else {
+          continue;
+        };
+        lang.tf(
+          "guard.removed",
+          &[
+            ("name", &snapshot.name),
+            ("reason", &rejection.message(lang)),
+          ],
+        )
+      };
+      if let Err(err) = torrent.delete(&snapshot.hash, true).await {
+        log::error!(
+          "Could not remove {} after its metadata: {err}",
+          snapshot.hash
+        );
+        continue;
+      }
+      if let Err(err) = bot.send_message(chat, text).await {
+        log::warn!(
+          "Could not tell {chat} about removing {}: {err}",
+          snapshot.hash
+        );
+      }
+    }
+  });
+}
+
+/// Explains the rejection. With a `link` to add, admins get a button to add it anyway.
+pub async fn reject(
+  bot: &Bot,
+  chat: ChatId,
+  rejection: &Rejection,
+  link: Option<&str>,
+  lang: Lang,
+) -> HandlerResult {
+  let text = rejection.message(lang);
+  let mut send = bot.send_message(chat, text);
+  if let Some(link) = link {
+    send = send.reply_markup(InlineKeyboardMarkup::new([[callback_data::button(
+      lang.t("guard.override"),
+      format!("guard:{link}"),
+    )]]));
+  }
+  send.await?;
+  Ok(())
+}
+
+/// The admin override: skips the add policies, the duplicate, parental and quota checks still
+/// apply.
+#[allow(clippy::too_many_arguments)]
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  quotas: Quotas,
+  parental: Parental,
+  access: Access,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  let (Some(link), Some(prompt)) = (
+    q.data
+      .as_deref()
+      .and_then(|data| data.strip_prefix("guard:"))
+      .map(str::to_string),
+    q.message.as_ref(),
+  ) else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+  if !access.is_admin(q.from.id) {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("admin.only"))
+      .show_alert(true)
+      .await?;
+    return Ok(());
+  }
+  if parental::magnet_name(&link).is_some_and(|name| parental.blocks(prompt.chat.id, &name)) {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("parental.blocked"))
+      .await?;
+    return Ok(());
+  }
+  if let Err(reason) = quotas.check(q.from.id.0, duplicates::magnet_size(&link), lang) {
+    bot.answer_callback_query(q.id).text(reason).await?;
+    return Ok(());
+  }
+
+  bot.answer_callback_query(q.id).await?;
+  let hash = torrent::extract_info_hash(&link);
+  let candidate = Candidate::from_link(&link);
+  let duplicate = duplicates::check_duplicates(
+    &torrent,
+    hash.as_deref(),
+    candidate.name.as_deref(),
+    candidate.size,
+  )
+  .await?;
+  if let Some(duplicate) = duplicate {
+    bot
+      .edit_message_reply_markup(prompt.chat.id, prompt.id)
+      .await?;
+    return duplicates::warn(&bot, prompt.chat.id, &duplicate, &link, lang).await;
+  }
+  let arg = AddTorrentArg {
+    urls: vec![link],
+    ..Default::default()
+  };
+  let reply = match torrent.add_torrent(&arg).await {
+    Ok(()) => {
+      if let Some(hash) = &hash {
+        quotas.record(q.from.id.0, hash, candidate.size);
+      }
+      audit::record(
+        &db,
+        &q.from,
+        "added a torrent despite the add policy",
+        hash.as_deref(),
+      );
+      lang.t("torrent.added").to_string()
+    }
+    Err(err) => err.to_string(),
+  };
+  let edit = bot.edit_message_text(prompt.chat.id, prompt.id, reply);
+  match hash {
+    Some(hash) => {
+      edit
+        .reply_markup(add_keyboard(&hash, &config, lang))
+        .await?
+    }
+    None => edit.await?,
+  };
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn guard() -> GuardConfig {
+    GuardConfig {
+      banned_extensions: vec!["exe".to_string(), ".SCR".to_string()],
+      banned_trackers: vec![" Tracker.Example.com ".to_string()],
+      ..GuardConfig::default()
+    }
+  }
+
+  #[test]
+  fn bans_extensions_in_any_case() {
+    let guard = guard();
+    assert_eq!(
+      banned_extension(&guard, "setup.EXE").as_deref(),
+      Some("exe")
+    );
+    assert_eq!(
+      banned_extension(&guard, "dir/file.scr ").as_deref(),
+      Some("scr")
+    );
+    assert_eq!(banned_extension(&guard, "movie.mkv"), None);
+    assert_eq!(banned_extension(&guard, "exe"), None);
+  }
+
+  #[test]
+  fn bans_tracker_domains_with_subdomains() {
+    let guard = guard();
+    assert_eq!(
+      banned_tracker(&guard, "udp://tracker.example.com:1337/announce").as_deref(),
+      Some("tracker.example.com")
+    );
+    assert!(banned_tracker(&guard, "https://a.TRACKER.example.com/announce").is_some());
+    assert_eq!(
+      banned_tracker(&guard, "https://eviltracker.example.com/"),
+      None
+    );
+    assert_eq!(banned_tracker(&guard, "not a url"), None);
+  }
+
+  #[test]
+  fn checks_file_names_too() {
+    let candidate = Candidate {
+      name: Some("Release".to_string()),
+      files: vec![
+        "Release/readme.txt".to_string(),
+        "Release/crack.exe".to_string(),
+      ],
+      ..Candidate::default()
+    };
+    assert!(check_content(&guard(), &candidate).is_some());
+  }
+}
@@ -0,0 +1,250 @@
+use chrono::Utc;
+use chrono_tz::Tz;
+use std::sync::Arc;
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardMarkup, Me, UpdateKind},
+};
+
+use crate::{
+  audit,
+  auth::Access,
+  callback_data,
+  config::Config,
+  db::Db,
+  format::format_timestamp,
+  i18n::{self, Lang},
+  parse_command, settings, HandlerResult,
+};
+
+/// `/start` payloads of invite links look like `guest_<token>`.
+const PAYLOAD_PREFIX: &str = "guest_";
+
+/// An invite nobody used stops working after this long.
+const INVITE_TTL_HOURS: i64 = 24;
+
+/// The longest a single link lets a guest in, 30 days.
+const MAX_HOURS: u32 = 30 * 24;
+
+// these only page through or refresh what a guest can already see
+const READ_ONLY_CALLBACKS: [&str; 6] = ["list:", "transfer:", "live:", "help:", "lang:", "fl:"];
+
+/// The token of a `/start guest_<token>` message.
+#[derive(Debug, Clone)]
+pub struct Invite(String);
+
+pub fn invite(msg: Message) -> Option<Invite> {
+  let payload = msg.text()?.strip_prefix("/start ")?.trim();
+  let token = payload.strip_prefix(PAYLOAD_PREFIX)?;
+  Some(Invite(token.to_string()))
+}
+
+fn new_token() -> Result<String, String> {
+  let mut bytes = [0u8; 16];
+  getrandom::getrandom(&mut bytes)
+    .map_err(|err| format!("No randomness for a guest link: {err}"))?;
+  Ok(hex::encode(bytes))
+}
+
+/// `/guest <hours>` makes a one-time link to read-only access, without hours it lists the guests.
+pub async fn guest(
+  bot: Bot,
+  msg: Message,
+  arg: String,
+  access: Access,
+  me: Me,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(user) = msg.from().filter(|user| access.is_admin(user.id)) else {
+    bot.send_message(msg.chat.id, lang.t("admin.only")).await?;
+    return Ok(());
+  };
+
+  let arg = arg.trim();
+  if arg.is_empty() {
+    let (text, keyboard) = listing(&db, lang, settings::of(&db, Some(user)).tz())?;
+    bot
+      .send_message(msg.chat.id, text)
+      .reply_markup(keyboard)
+      .await?;
+    return Ok(());
+  }
+  let Some(hours) = arg
+    .trim_end_matches('h')
+    .parse::<u32>()
+    .ok()
+    .filter(|hours| (1..=MAX_HOURS).contains(hours))
+  else {
+    bot.send_message(msg.chat.id, lang.t("guest.usage")).await?;
+    return Ok(());
+  };
+
+  let token = new_token()?;
+  let expires_at = Utc::now().timestamp() + INVITE_TTL_HOURS * 60 * 60;
+  db.add_guest_invite(&token, hours, user.id.0, expires_at)?;
+  audit::record(&db, user, format!("created a {hours}h guest link"), None);
+
+  let link = format!(
+    "https://t.me/{}?start={PAYLOAD_PREFIX}{token}",
+    me.username()
+  );
+  bot
+    .send_message(
+      msg.chat.id,
+      lang.tf("guest.link", &[("hours", &hours), ("link", &link)]),
+    )
+    .await?;
+  Ok(())
+}
+
+/// Current guests, each with a button taking their access away.
+fn listing(db: &Db, lang: Lang, timezone: Tz) -> rusqlite::Result<(String, InlineKeyboardMarkup)> {
+  let now = Utc::now().timestamp();
+  let guests: Vec<_> = db
+    .guests()?
+    .into_iter()
+    .filter(|(_, _, until)| *until > now)
+    .collect();
+  if guests.is_empty() {
+    return Ok((
+      lang.t("guest.none").to_string(),
+      InlineKeyboardMarkup::default(),
+    ));
+  }
+
+  let mut text = lang.t("guest.title").to_string();
+  let mut buttons = Vec::new();
+  for (user_id, name, until) in guests {
+    let until = format_timestamp(until, timezone);
+    text.push_str(&format!(
+      "\n• {name} — {}",
+      lang.tf("guest.until", &[("until", &until)])
+    ));
+    buttons.push(vec![callback_data::button(
+      lang.tf("guest.revoke", &[("name", &name)]),
+      format!("guest:revoke:{user_id}"),
+    )]);
+  }
+  Ok((text, InlineKeyboardMarkup::new(buttons)))
+}
+
+pub async fn callback(bot: Bot, q: CallbackQuery, access: Access, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  if !access.is_admin(q.from.id) {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("admin.only"))
+      .await?;
+    return Ok(());
+  }
+  let Some(user_id) = q
+    .data
+    .as_deref()
+    .and_then(|data| data.strip_prefix("guest:revoke:"))
+    .and_then(|id| id.parse::<u64>().ok())
+  else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+
+  let removed = db.remove_guest(user_id)?;
+  access.revoke_guest(UserId(user_id));
+  let key = if removed {
+    audit::record(
+      &db,
+      &q.from,
+      format!("revoked the guest access of {user_id}"),
+      None,
+    );
+    "guest.revoked"
+  } else {
+    "guest.gone"
+  };
+  bot.answer_callback_query(q.id).text(lang.t(key)).await?;
+
+  if let (true, Some(msg)) = (removed, &q.message) {
+    let (text, keyboard) = listing(&db, lang, settings::of(&db, Some(&q.from)).tz())?;
+    bot
+      .edit_message_text(msg.chat.id, msg.id, text)
+      .reply_markup(keyboard)
+      .await?;
+  }
+  Ok(())
+}
+
+/// Lets whoever opened an invite link in as a guest, once per link.
+pub async fn redeem(
+  bot: Bot,
+  msg: Message,
+  upd: Update,
+  invite: Invite,
+  access: Access,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(user) = msg.from() else {
+    return Ok(());
+  };
+  // a member opening the link shouldn't use it up for the person it was meant for
+  if access.admits(&upd) {
+    bot
+      .send_message(msg.chat.id, lang.t("guest.member"))
+      .await?;
+    return Ok(());
+  }
+
+  let now = Utc::now().timestamp();
+  let Some(hours) = db.take_guest_invite(&invite.0, now)? else {
+    log::warn!("Unknown or expired guest link opened by {}", user.id);
+    bot
+      .send_message(msg.chat.id, lang.t("guest.invalid"))
+      .await?;
+    return Ok(());
+  };
+  let until = now + i64::from(hours) * 60 * 60;
+  db.set_guest(user.id.0, &user.full_name(), until)?;
+  access.admit_guest(user.id, until);
+  audit::record(&db, user, format!("joined as a guest for {hours}h"), None);
+
+  let until = format_timestamp(until, settings::of(&db, Some(user)).tz());
+  bot
+    .send_message(msg.chat.id, lang.tf("guest.welcome", &[("until", &until)]))
+    .await?;
+  Ok(())
+}
+
+/// What guests may do: look at the torrents and follow them, but not change anything.
+pub fn may(upd: &Update, me: Me, config: Arc<Config>) -> bool {
+  match &upd.kind {
+    UpdateKind::Message(msg) => {
+      parse_command(msg.clone(), me, config).is_some_and(|cmd| cmd.is_read_only())
+    }
+    UpdateKind::CallbackQuery(q) => q
+      .data
+      .as_deref()
+      .and_then(callback_data::resolve)
+      .is_some_and(|data| {
+        READ_ONLY_CALLBACKS
+          .iter()
+          .any(|prefix| data.starts_with(prefix))
+      }),
+    UpdateKind::InlineQuery(_) => true,
+    _ => false,
+  }
+}
+
+/// Answers guests trying something beyond looking.
+pub async fn read_only(bot: Bot, upd: Update, db: Db) -> HandlerResult {
+  let text = i18n::lang(&db, upd.user()).t("guest.read_only");
+  match upd.kind {
+    UpdateKind::Message(msg) => {
+      bot.send_message(msg.chat.id, text).await?;
+    }
+    UpdateKind::CallbackQuery(q) => {
+      bot.answer_callback_query(q.id).text(text).await?;
+    }
+    _ => {}
+  }
+  Ok(())
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+use teloxide::{prelude::*, types::InlineKeyboardMarkup};
+
+use crate::{
+  alias, callback_data,
+  config::Config,
+  db::Db,
+  i18n::{self, Lang},
+  HandlerResult,
+};
+
+/// Pages of the /help browser, each listing its commands with an example.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+  Basics,
+  Control,
+  Streaming,
+  Config,
+  Admin,
+}
+
+impl Section {
+  const ALL: [Section; 5] = [
+    Section::Basics,
+    Section::Control,
+    Section::Streaming,
+    Section::Config,
+    Section::Admin,
+  ];
+
+  fn key(self) -> &'static str {
+    match self {
+      Section::Basics => "basics",
+      Section::Control => "control",
+      Section::Streaming => "streaming",
+      Section::Config => "config",
+      Section::Admin => "admin",
+    }
+  }
+
+  fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|section| section.key() == key)
+  }
+
+  fn render(self, config: &Config, lang: Lang) -> String {
+    let mut text = lang.t(&format!("help.{}", self.key())).to_string();
+    // aliases are personal shorthand, they belong with the other settings
+    if self == Section::Config && !config.aliases.is_empty() {
+      text.push_str(&format!(
+        "\n\n{}\n{}",
+        lang.t("help.aliases"),
+        alias::describe(&config.aliases)
+      ));
+    }
+    text
+  }
+
+  fn keyboard(self, lang: Lang) -> InlineKeyboardMarkup {
+    let buttons: Vec<_> = Self::ALL
+      .into_iter()
+      .map(|section| {
+        let title = lang.t(&format!("help.title.{}", section.key()));
+        let label = if section == self {
+          format!("• {title} •")
+        } else {
+          title.to_string()
+        };
+        callback_data::button(label, format!("help:{}", section.key()))
+      })
+      .collect();
+    InlineKeyboardMarkup::new(buttons.chunks(3).map(<[_]>::to_vec))
+  }
+}
+
+pub async fn help(bot: Bot, msg: Message, config: Arc<Config>, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let section = Section::Basics;
+  bot
+    .send_message(msg.chat.id, section.render(&config, lang))
+    .reply_markup(section.keyboard(lang))
+    .await?;
+  Ok(())
+}
+
+pub async fn callback(bot: Bot, q: CallbackQuery, config: Arc<Config>, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  bot.answer_callback_query(q.id).await?;
+  let section = q
+    .data
+    .as_deref()
+    .and_then(|data| data.strip_prefix("help:"))
+    .and_then(Section::from_key);
+  let (Some(section), Some(msg)) = (section, q.message) else {
+    return Ok(());
+  };
+
+  bot
+    .edit_message_text(msg.chat.id, msg.id, section.render(&config, lang))
+    .reply_markup(section.keyboard(lang))
+    .await?;
+  Ok(())
+}
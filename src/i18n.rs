@@ -0,0 +1,841 @@
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardMarkup, User},
+};
+
+use crate::{callback_data, db::Db, HandlerResult};
+
+/// Languages the bot can answer in. Messages missing from a locale fall back to English.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+  En,
+  Es,
+}
+
+impl Lang {
+  const ALL: [Lang; 2] = [Lang::En, Lang::Es];
+
+  fn code(self) -> &'static str {
+    match self {
+      Lang::En => "en",
+      Lang::Es => "es",
+    }
+  }
+
+  fn label(self) -> &'static str {
+    match self {
+      Lang::En => "🇬🇧 English",
+      Lang::Es => "🇪🇸 Español",
+    }
+  }
+
+  fn from_code(code: &str) -> Option<Self> {
+    // Telegram sends IETF tags like "es-419"
+    let code = code.split('-').next().unwrap_or(code);
+    Self::ALL.into_iter().find(|lang| lang.code() == code)
+  }
+
+  fn messages(self) -> &'static [(&'static str, &'static str)] {
+    match self {
+      Lang::En => EN,
+      Lang::Es => ES,
+    }
+  }
+
+  /// The message for `key`, with `{name}` placeholders left for [`Lang::tf`].
+  pub fn t(self, key: &str) -> &'static str {
+    let find = |messages: &'static [(&'static str, &'static str)]| {
+      messages
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, message)| *message)
+    };
+    find(self.messages())
+      .or_else(|| find(EN))
+      .unwrap_or_else(|| {
+        log::warn!("Missing message {key}");
+        ""
+      })
+  }
+
+  /// [`Lang::t`] with each `{name}` replaced by its value.
+  pub fn tf(self, key: &str, args: &[(&str, &(dyn std::fmt::Display + Sync))]) -> String {
+    let mut text = self.t(key).to_string();
+    for (name, value) in args {
+      text = text.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    text
+  }
+}
+
+/// The language picked with /language, else the one the user's Telegram app is set to.
+pub fn lang(db: &Db, user: Option<&User>) -> Lang {
+  let Some(user) = user else {
+    return Lang::En;
+  };
+  stored(db, user.id.0)
+    .or_else(|| user.language_code.as_deref().and_then(Lang::from_code))
+    .unwrap_or(Lang::En)
+}
+
+/// The language picked with /language for messages sent to `chat` without a user to ask, like
+/// background reports. Private chats share the user's id, groups get English.
+pub fn chat_lang(db: &Db, chat: ChatId) -> Lang {
+  u64::try_from(chat.0)
+    .ok()
+    .and_then(|user_id| stored(db, user_id))
+    .unwrap_or(Lang::En)
+}
+
+fn stored(db: &Db, user_id: u64) -> Option<Lang> {
+  match db.language(user_id) {
+    Ok(code) => code.as_deref().and_then(Lang::from_code),
+    Err(err) => {
+      log::warn!("Could not load the language of {user_id}: {err}");
+      None
+    }
+  }
+}
+
+fn keyboard() -> InlineKeyboardMarkup {
+  InlineKeyboardMarkup::new(Lang::ALL.map(|lang| {
+    vec![callback_data::button(
+      lang.label(),
+      format!("lang:{}", lang.code()),
+    )]
+  }))
+}
+
+pub async fn language(bot: Bot, msg: Message, db: Db) -> HandlerResult {
+  let lang = lang(&db, msg.from());
+  bot
+    .send_message(msg.chat.id, lang.t("language.choose"))
+    .reply_markup(keyboard())
+    .await?;
+  Ok(())
+}
+
+pub async fn callback(bot: Bot, q: CallbackQuery, db: Db) -> HandlerResult {
+  let Some(lang) = q
+    .data
+    .as_deref()
+    .and_then(|data| data.strip_prefix("lang:"))
+    .and_then(Lang::from_code)
+  else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+
+  db.set_language(q.from.id.0, lang.code())?;
+  bot.answer_callback_query(q.id).await?;
+  if let Some(msg) = q.message {
+    bot
+      .edit_message_text(msg.chat.id, msg.id, lang.t("language.set"))
+      .await?;
+  }
+  Ok(())
+}
+
+const EN: &[(&str, &str)] = &[
+  ("language.choose", "Choose the language I answer you in:"),
+  ("language.set", "I will talk to you in English from now on."),
+  (
+    "access.denied",
+    "Sorry, you are not allowed to use this bot.",
+  ),
+  ("dialogue.cancel", "Cancelling the dialogue."),
+  (
+    "dialogue.invalid",
+    "Unable to handle the message. Type /help to see the usage.",
+  ),
+  ("status.ok", "✅ qBittorrent {version} is reachable ({latency} ms)"),
+  ("status.down", "❌ qBittorrent is not reachable: {error}"),
+  ("magnet.ask", "Send me the magnet link or info hash"),
+  ("magnet.missing", "Please, send me your magnet link."),
+  ("torrent.added", "Torrent has been added to download queue"),
+  (
+    "sequential.ask",
+    "Send me a magnet link or info hash to add in sequential mode, or the hash of an existing torrent",
+  ),
+  (
+    "sequential.missing",
+    "Please, send me a magnet link or a torrent hash.",
+  ),
+  (
+    "sequential.enabled",
+    "Sequential download is enabled for the torrent",
+  ),
+  (
+    "sequential.added",
+    "Torrent has been added in sequential mode",
+  ),
+  (
+    "cookie.ask",
+    "Send me the tracker cookie, e.g. uid=123; pass=abc",
+  ),
+  ("cookie.missing", "Please, send me the cookie as text."),
+  ("cookie.ask_url", "Got it. Now send me the .torrent URL"),
+  ("cookie.missing_url", "Please, send me the .torrent URL."),
+  ("cookie.fetch_failed", "Could not download the .torrent: {error}"),
+  ("cookie.not_torrent", "That URL didn't return a .torrent file."),
+  ("picker.button", "📂 File priorities"),
+  (
+    "picker.no_metadata",
+    "Metadata is not available yet, try again in a moment.",
+  ),
+  ("picker.prompt", "Tap a file to cycle ⬇️ download → ⏫ high priority → ⏭ skip:"),
+  ("picker.empty", "At least one file has to be downloaded."),
+  ("picker.done", "Downloading {count} file(s)."),
+  ("notify.prompt", "Tap to choose what I message you about:"),
+  ("features.admins_only", "Only admins can change features."),
+  ("features.prompt", "Tap a feature to switch it on or off:"),
+  ("history.empty", "Nothing has happened yet."),
+  ("history.title", "Recent actions (page {page}/{pages}):"),
+  ("history.newer", "⬅️ Newer"),
+  ("history.older", "Older ➡️"),
+  (
+    "quota.error",
+    "Could not check your download quota, try again later.",
+  ),
+  (
+    "quota.daily",
+    "Your daily quota of {limit} is used up ({used} added). Try again tomorrow.",
+  ),
+  (
+    "quota.monthly",
+    "Your monthly quota of {limit} is used up ({used} added). Try again next month.",
+  ),
+  (
+    "quota.daily_exceeds",
+    "Adding {size} would go past your daily quota of {limit} ({used} added so far).",
+  ),
+  (
+    "quota.monthly_exceeds",
+    "Adding {size} would go past your monthly quota of {limit} ({used} added so far).",
+  ),
+  (
+    "quota.removed",
+    "🗑 {name} was removed once its size was known:\n{reason}",
+  ),
+  (
+    "quota.dropped",
+    "⚠️ {name} doesn't fit your quota now that its size is known:\n{reason}\nSomeone else added it too, so it stays and no longer counts towards yours.",
+  ),
+  (
+    "watch.not_found",
+    "No torrent matches that hash. Usage: /watch <hash or hash prefix>",
+  ),
+  ("watch.removed", "The torrent was removed."),
+  ("watch.stopped", "Stopped"),
+  ("watch.status", "👀 {name}\n\nState: {state}\nProgress: {progress}%\n⬇️ {dlspeed} ⬆️ {upspeed}\nETA: {eta}\nPeers: {seeds} seeds, {leechers} leechers"),
+  ("trackers.title", "Trackers:"),
+  ("trackers.not_working", "not working"),
+  ("recategorize.usage", "Usage: /recategorize <filters> <target> [move]\nFilters (all must match): tracker:<domain> name:<regex> category:<name>\nAdd \"move\" to also move the data into the target category's save path.\nExample: /recategorize tracker:example.org category:tv Series move"),
+  ("recategorize.unexpected", "Unexpected argument: {argument}"),
+  ("recategorize.bad_regex", "Invalid name pattern: {error}"),
+  ("recategorize.none", "No torrents match those filters."),
+  ("recategorize.moving", "Moving {count} torrent(s) to {category}..."),
+  ("recategorize.progress", "Moved {done}/{count} torrent(s) to {category}..."),
+  ("recategorize.done", "Moved {count} torrent(s) to {category}."),
+  ("recategorize.done_relocating", "Moved {count} torrent(s) to {category}, data is being relocated."),
+  ("profile.gone", "This profile no longer exists."),
+  ("profile.not_found", "No torrent matches that hash."),
+  ("profile.applied", "Applied the {name} profile"),
+  ("profile.failed", "Could not apply {name}: {error}"),
+  ("digest.daily", "Daily digest"),
+  ("digest.weekly", "Weekly digest"),
+  ("digest.completed", "📊 {title}\n\nCompleted: {count}"),
+  ("digest.more", "…and {count} more"),
+  ("digest.since_last", "Downloaded {downloaded}, uploaded {uploaded} since the last digest"),
+  ("digest.this_session", "Downloaded {downloaded}, uploaded {uploaded} this session"),
+  ("digest.totals", "Free disk space: {free}\nGlobal ratio: {ratio}"),
+  ("digest.dry_run", "🧪 Dry run, not deleted:"),
+  ("settings.prompt", "Tap a setting to change it:"),
+  ("settings.page_size", "📄 Items per page: {value}"),
+  (
+    "settings.watch_refresh",
+    "🔄 /watch refresh: every {value}s",
+  ),
+  (
+    "settings.inline_results",
+    "🔎 Inline search results: {value}",
+  ),
+  ("admin.only", "Only admins can do that."),
+  ("restart.shutdown_only", "qBittorrent was told to shut down. No restart_command is configured, so start it again yourself."),
+  ("restart.running", "qBittorrent is shutting down, running the restart command..."),
+  ("restart.failed", "❌ The restart command failed: {error}"),
+  ("restart.hook_timeout", "❌ The restart command was stopped after {secs}s without finishing."),
+  ("restart.done", "✅ qBittorrent {version} is back up"),
+  ("restart.timeout", "⚠️ The restart command ran, but qBittorrent is still not reachable."),
+  ("maintenance.busy", "🛠 The bot is down for maintenance, please try again later."),
+  ("maintenance.is_on", "Maintenance mode is on. Usage: /maintenance on|off"),
+  ("maintenance.is_off", "Maintenance mode is off. Usage: /maintenance on|off"),
+  ("maintenance.started", "🛠 Maintenance mode is on. Only admins can use the bot and notifications are paused."),
+  ("maintenance.ended", "✅ Maintenance mode is off."),
+  ("compare.usage", "Usage: /compare <hash1> <hash2>"),
+  ("compare.not_found", "Both hashes have to match exactly one torrent."),
+  ("compare.delete", "🗑 Delete {side}"),
+  ("detect.prompt", "Add this torrent?"),
+  ("detect.add", "✅ Add"),
+  ("detect.dismiss", "✖️ Dismiss"),
+  ("detect.gone", "The original message is gone, send the torrent again."),
+  ("album.added", "Added {count} torrent(s):"),
+  ("album.failed", "Could not download these files from Telegram:"),
+  ("parental.usage", "Usage: /parental on|off"),
+  ("parental.on", "🔒 This chat now uses the restricted profile."),
+  ("parental.off", "🔓 The restricted profile is off for this chat."),
+  ("parental.wrong_pin", "Wrong PIN."),
+  ("parental.unlocked", "🔓 Unlocked for {mins} minutes."),
+  ("parental.blocked", "🔒 This torrent can't be added here."),
+  (
+    "parental.removed",
+    "🔒 {name} was removed again, its name can't be added here.",
+  ),
+  (
+    "parental.off_admin_only",
+    "Only admins listed in ADMIN_USER_IDS can turn the restricted profile off.",
+  ),
+  (
+    "parental.locked_out",
+    "Too many wrong PINs. Try again in {mins} minutes.",
+  ),
+  ("cleanup.usage", "Usage: /cleanup to remove finished torrents, /cleanup now to prune old records"),
+  ("cleanup.done", "🧹 Removed {audit} audit log entries, {quota} old quota records and {tokens} expired button tokens."),
+  ("send.not_found", "No torrent matches that hash. Usage: /send <hash or hash prefix>"),
+  ("send.incomplete", "The torrent hasn't finished downloading yet."),
+  ("send.too_large", "These files are over Telegram's 50 MB limit for bots and were not sent:"),
+  ("send.auto_button", "📤 Send when done"),
+  ("send.auto_set", "I'll upload the files here once the download completes."),
+  ("cleanup.none", "Nothing to clean up."),
+  ("cleanup.prompt", "Pick the torrents to delete:"),
+  ("cleanup.delete", "🗑 Delete"),
+  ("cleanup.delete_data", "🗑 Delete with data"),
+  ("cleanup.cancel", "✖️ Cancel"),
+  ("cleanup.cancelled", "Cleanup cancelled."),
+  ("cleanup.nothing_selected", "Nothing selected."),
+  ("cleanup.deleted", "🗑 Deleted {count} torrent(s)."),
+  ("cleanup.deleted_data", "🗑 Deleted {count} torrent(s) and their data."),
+  ("cleanup.ratio", "ratio limit"),
+  ("cleanup.seeding_time", "seeding time limit"),
+  ("cleanup.inactive", "inactive {days}d"),
+  ("cleanup.complete", "complete"),
+  ("policies.none", "No auto-removal policies are configured."),
+  ("policies.nothing", "No torrent matches a policy right now."),
+  ("policies.title", "🧹 The policies would remove (🗑 with data):"),
+  ("policies.dry_run", "🧪 Dry run is on, these are only listed in the digest."),
+  ("oncomplete.none", "No completion actions are set."),
+  ("oncomplete.usage", "Usage: /oncomplete <category> move <path> | tag <tag> | pause | notify [chat id|here] | upload <rclone remote> | clear\nAdd off to remove one action, e.g. /oncomplete tv pause off"),
+  ("oncomplete.cleared", "Removed the completion actions of {category}."),
+  ("oncomplete.move", "move to {path}"),
+  ("oncomplete.tag", "tag {tag}"),
+  ("oncomplete.pause", "pause"),
+  ("oncomplete.notify", "notify {chat}"),
+  ("delete.not_found", "No torrent matches that hash."),
+  ("delete.prompt", "Delete {name} together with its files? This can't be undone."),
+  ("delete.button", "🗑 Delete with data"),
+  ("delete.type_name", "To delete {name} and its files, type its name or the first 8 characters of its hash ({short}). Anything else cancels."),
+  ("delete.mismatch", "That doesn't match, nothing was deleted."),
+  ("delete.done", "🗑 Deleted {name} and its files. Undo adds the torrent back, the files download again."),
+  ("undo.button", "↩️ Undo"),
+  ("undo.expired", "It's too late to undo that."),
+  ("undo.restored", "↩️ Added back: {names}"),
+  ("undo.failed", "Could not add back:"),
+  ("help.title.basics", "Basics"),
+  ("help.title.control", "Torrent control"),
+  ("help.title.streaming", "Streaming"),
+  ("help.title.config", "Config"),
+  ("help.title.admin", "Admin"),
+  ("help.aliases", "Aliases:"),
+  ("help.basics", "📖 Basics\n\n/magnet — add a magnet link, then send it\n/addwithcookie — add a .torrent URL behind a tracker login\n/template — save a category, path and tags to pick from when adding, e.g. /template movies category:Movies\n/addlater <time> <magnet> — add it later, e.g. off-peak at /addlater 02:00 magnet:?xt=...\n/status — qBittorrent, torrent counts and the bot's uptime\n/cancel — stop what you started\n\nSending a .torrent file (or an album of them) offers to add it.\nReply to one of my torrent messages with pause, resume, delete or files to act on it.\nInline search: @YourBot ubuntu"),
+  ("help.control", "🎛 Torrent control\n\n/watch <hash> — follow progress live, e.g. /watch 3f2a\n/compare <hash1> <hash2> — compare two releases, e.g. /compare 3f2a 9b1c\n/recategorize <filters> <target> [move] — e.g. /recategorize tracker:example.org TV move\n/deletedata [hash] — delete a torrent and its files, e.g. /deletedata 3f2a\n/list [filter|grouped] [sort:<key>] — your torrents, e.g. /list downloading sort:size\n/bycategory, /bytag — torrents grouped with counts and sizes\n/queue — the download queue in order (#1 starts next) with buttons to move torrents\n/files [hash] — a torrent's files, page by page, e.g. /files 3f2a\n/recent [24h|7d] — torrents added lately and how they are doing, e.g. /recent 7d\n/find <text> — search by name, e.g. /find breaking bad\n/search <text> — search the indexers for new torrents, e.g. /search ubuntu\n/sonarr, /radarr <magnet|number> — let Sonarr or Radarr fetch and file a release; reply to /search results with a number\n/top speed|size|ratio|peers — the heaviest torrents, e.g. /top ratio\n/transfer — global transfer statistics\n/ratio — global ratio, per category and the best and worst torrents\n/policies — what the auto-removal rules would delete now"),
+  ("help.streaming", "🎬 Streaming\n\n/sequential — add a magnet for streaming, or send a hash to stream an existing torrent\n/send <hash> — upload the finished files here, e.g. /send 3f2a\nThe \"Send when done\" button after adding does the same once the download completes.\n/subtitles <hash> — fetch subtitles from OpenSubtitles next to the videos"),
+  ("help.config", "⚙️ Config\n\n/settings — page sizes, refresh rate, inline results and list style\n/settings timezone <zone> — show times in your zone, e.g. /settings timezone Europe/Madrid\n/language — the language I answer in\n/notify — which notifications you receive\n/unlock <pin> — lift this chat's restriction for 30 minutes, e.g. /unlock 4321"),
+  ("help.admin", "🛡 Admin\n\n/features — switch bot features on or off\n/parental on|off — restrict what this chat sees\n/maintenance on|off — pause the bot for everyone else\n/guest <hours> — a one-time link to read-only access; /guest lists guests\n/quiethours <from>-<to> [altspeed|pause] — e.g. /quiethours 23:00-07:00\n/cleanup — delete finished torrents; /cleanup now prunes old records\n/oncomplete <category> <action> — e.g. /oncomplete tv move /media/tv\n/history — who did what recently\n/qbrestart — restart qBittorrent"),
+  ("top.usage", "Usage: /top speed|size|ratio|peers"),
+  ("top.empty", "There are no torrents yet."),
+  ("top.title", "🏆 Top torrents by {metric}:"),
+  ("settings.list_style", "📋 Torrent lists: {value}"),
+  ("settings.compact", "compact"),
+  ("settings.detailed", "detailed"),
+  ("list.usage", "Usage: /list [{filters}] [sort:{sorts}] or /list grouped"),
+  ("list.empty", "There are no torrents yet."),
+  ("list.title", "📋 {count} torrent(s):"),
+  ("list.more", "…and {count} more."),
+  ("state.downloading", "Downloading"),
+  ("state.stalled", "Stalled"),
+  ("state.seeding", "Seeding"),
+  ("state.paused", "Paused"),
+  ("state.errored", "Errored"),
+  ("state.other", "Other"),
+  ("list.page_title", "📋 Torrents ({filter}, by {sort}), page {page}:"),
+  ("list.previous", "◀️ Previous"),
+  ("list.next", "Next ▶️"),
+  ("find.usage", "Usage: /find <part of the name>"),
+  ("find.none", "No torrent name matches."),
+  ("find.more", "…and {count} more, try a longer search."),
+  ("find.pause", "⏸ Pause"),
+  ("find.resume", "▶️ Resume"),
+  ("find.paused", "Paused."),
+  ("find.resumed", "Resumed."),
+  ("list.sort_by", "🔃 Sort by…"),
+  ("labels.header", "📁 {name} — {count} torrent(s), {size}"),
+  ("labels.uncategorized", "No category"),
+  ("labels.untagged", "No tag"),
+  ("queue.empty", "Nothing is queued. Queue positions only exist with torrent queueing enabled in qBittorrent."),
+  ("queue.title", "🚦 Download queue, #1 starts first (⏫ top, 🔼 up, 🔽 down, ⏬ bottom):"),
+  ("callback.expired", "This button has expired, run the command again."),
+  ("files.title", "📂 {name}\n{count} file(s), page {page}/{pages} (⏫ high priority, ⏭ skipped):"),
+  ("files.not_found", "No torrent matches that hash."),
+  ("settings.files_page_size", "📂 Files per page: {value}"),
+  ("select.empty", "There are no torrents to choose from."),
+  ("select.files", "Which torrent's files?"),
+  ("select.deletedata", "Which torrent should be deleted with its files?"),
+  ("files.priorities", "🎚 Change priorities"),
+  ("status.torrents", "📦 {total} torrent(s), {active} active"),
+  ("status.uptime", "⏱ Bot up for {uptime}"),
+  ("status.last_error", "⚠️ Last error {ago} ago: {error}"),
+  ("status.no_errors", "👌 No errors since the bot started"),
+  ("qbit.offline", "⏳ qBittorrent is unreachable right now, I'm reconnecting. Try again in a moment or check /status."),
+  ("reply.not_found", "I can't tell which torrent that message is about."),
+  ("reply.ambiguous", "That message mentions several torrents, add part of the name, e.g. \"pause ubuntu\":"),
+  ("start.invalid", "That link doesn't carry a magnet or info hash I can read."),
+  ("subtitles.disabled", "Subtitles need an OpenSubtitles account in the `opensubtitles` config."),
+  ("subtitles.usage", "No torrent matches that hash. Usage: /subtitles <hash or hash prefix>"),
+  ("subtitles.none", "No subtitles found for the videos in this torrent."),
+  ("subtitles.found", "📝 Subtitles for {name}, saved next to the videos:"),
+  ("search.disabled", "No indexers are configured, add Jackett or Prowlarr under `search` in the config."),
+  ("search.usage", "Usage: /search <text>"),
+  ("search.none", "The indexers found nothing."),
+  ("search.title", "🔎 Results for {query}, tap a number to add it:"),
+  ("search.expired", "These results are too old, search again."),
+  ("search.added", "Added {name}"),
+  ("arr.disabled", "{app} isn't configured."),
+  ("arr.usage", "Usage: /{command} <magnet>, or reply to /search results with /{command} <number>"),
+  ("arr.sent", "Sent {title} to {app}."),
+  ("arr.rejected", "{app} rejected the release: {reasons}"),
+  ("arr.failed", "Could not reach {app}: {error}"),
+  ("oncomplete.upload", "upload to {remote}"),
+  ("quiet.usage", "Usage: /quiethours <HH:MM>-<HH:MM> [altspeed|pause] (UTC), or /quiethours off"),
+  ("quiet.off", "Quiet hours are off."),
+  ("quiet.on", "Quiet hours: {start}-{end} UTC, {mode}."),
+  ("quiet.active", "They are in effect right now."),
+  ("quiet.mode_altspeed", "alternative speed limits"),
+  ("quiet.mode_pause", "running torrents paused"),
+  ("duplicate.same_hash", "Already there: {name}"),
+  ("duplicate.same_content", "This looks like {name}, which is already there (same name and size, maybe from another tracker)."),
+  ("duplicate.add_anyway", "➕ Add anyway"),
+  ("milestones.button", "🔔 Progress updates"),
+  ("milestones.on", "I'll tell you here at 25, 50, 75, 90 and 100%."),
+  ("milestones.off", "No more progress updates for this torrent."),
+  ("cleanup.dead", "dead {hours}h"),
+  ("cleanup.dead_found", "💀 {count} download(s) have had no seeds and no progress for a while. Remove them?"),
+  ("ratio.global", "⚖️ Global ratio: {ratio} ({uploaded} up, {downloaded} down)"),
+  ("ratio.by_category", "By category:"),
+  ("ratio.best", "Best ratio:"),
+  ("ratio.worst", "Worst ratio:"),
+  ("recent.usage", "Usage: /recent [24h|7d]"),
+  ("recent.empty", "Nothing was added in the last {period}."),
+  ("recent.title", "🆕 {count} torrent(s) added in the last {period}:"),
+  ("recent.added", "added {at}, {ago} ago"),
+  ("settings.timezone", "🕒 Time zone: {value}"),
+  ("settings.usage", "Set your time zone with /settings timezone <zone>, e.g. /settings timezone Europe/Madrid"),
+  ("settings.timezone_invalid", "I don't know that time zone. Use a name like Europe/Madrid, America/New_York or UTC."),
+  ("settings.timezone_set", "Times are now shown in {zone}."),
+  ("settings.units", "📏 Units: {value}"),
+  ("limit.button", "🐢 Speed limit"),
+  ("limit.prompt", "Pick a download (⬇️) or upload (⬆️) limit for this torrent:"),
+  ("limit.unlimited", "Unlimited"),
+  ("limit.custom", "✏️ Custom"),
+  ("limit.ask_dl", "Send me the download limit, e.g. 2.5 (MB/s), 800k or unlimited. /cancel keeps the current one."),
+  ("limit.ask_up", "Send me the upload limit, e.g. 2.5 (MB/s), 800k or unlimited. /cancel keeps the current one."),
+  ("limit.invalid", "That isn't a speed I can read. Try 2.5, 800k, 10 MB/s or unlimited."),
+  ("limit.set_dl", "⬇️ Download limit: {limit}"),
+  ("limit.set_up", "⬆️ Upload limit: {limit}"),
+  ("template.usage", "Usage: /template <name> [category:<category>] [path:<save path>] [tags:<a,b>] [paused] [sequential], or /template <name> delete\nNames use letters, digits, - and _, e.g. /template movies category:Movies path:/data/movies"),
+  ("template.none", "You have no add templates yet."),
+  ("template.saved", "📐 Saved {template}"),
+  ("template.deleted", "Deleted the {name} template."),
+  ("template.unknown", "You have no template called {name}."),
+  ("template.category", "category {category}"),
+  ("template.path", "saved to {path}"),
+  ("template.tags", "tags {tags}"),
+  ("template.paused", "paused"),
+  ("template.sequential", "sequential"),
+  ("template.defaults", "qBittorrent's defaults"),
+  ("template.pick", "Add it with which template?"),
+  ("template.without", "➕ No template"),
+  ("later.usage", "Usage: /addlater <HH:MM|30m|2h|1d> <magnet or info hash>, e.g. /addlater 02:00 magnet:?xt=... Times are in your /settings time zone."),
+  ("later.empty", "No adds are scheduled."),
+  ("later.title", "⏰ Scheduled adds, tap one to cancel it:"),
+  ("later.scheduled", "⏰ I'll add {name} at {at}."),
+  ("later.cancelled", "Cancelled."),
+  ("later.gone", "That add already happened or was cancelled."),
+  ("later.added", "⏰ Added {name} as scheduled"),
+  ("later.failed", "⏰ Could not add {name}: {error}"),
+  ("watch_folder.added", "📂 Added {name} from the watch folder"),
+  ("watch_folder.failed", "⚠️ Could not add from the watch folder: {name}: {error}"),
+  (
+    "guard.too_large",
+    "🛡 Not added: it is {size}, adds are limited to {max}.",
+  ),
+  ("guard.extension", "🛡 Not added: .{extension} files aren't allowed."),
+  ("guard.tracker", "🛡 Not added: the tracker {tracker} is banned."),
+  (
+    "guard.downloads",
+    "🛡 Not added: {max} downloads are already running, that's the limit.",
+  ),
+  ("guard.override", "🛡 Add anyway (admins)"),
+  (
+    "guard.removed",
+    "🛡 {name} was removed again, its metadata broke the add policy:\n{reason}",
+  ),
+  ("guest.usage", "Usage: /guest <hours>, e.g. /guest 48 for two days of read-only access. Alone it lists the guests."),
+  ("guest.link", "🎟 Read-only access for {hours}h, the link works once within a day:\n{link}"),
+  ("guest.none", "There are no guests right now."),
+  ("guest.title", "🎟 Guests:"),
+  ("guest.until", "until {until}"),
+  ("guest.revoke", "✖️ Revoke {name}"),
+  ("guest.revoked", "Guest access revoked."),
+  ("guest.gone", "That guest's access already ended."),
+  ("guest.invalid", "This guest link is invalid, already used or expired."),
+  ("guest.member", "You already have full access, the link is left for someone else."),
+  ("guest.welcome", "👋 You're a guest until {until}. /list, /files, /recent, /find, /top, /ratio, /transfer, /watch, /send and /status are yours, adding or changing torrents isn't."),
+  ("guest.read_only", "🔒 Guests can only look around, ask an admin for that."),
+];
+
+const ES: &[(&str, &str)] = &[
+  ("language.choose", "Elige el idioma en el que te respondo:"),
+  ("language.set", "A partir de ahora te hablaré en español."),
+  ("access.denied", "Lo siento, no tienes permiso para usar este bot."),
+  ("dialogue.cancel", "Cancelando el diálogo."),
+  ("dialogue.invalid", "No puedo procesar el mensaje. Escribe /help para ver el uso."),
+  ("status.ok", "✅ qBittorrent {version} está accesible ({latency} ms)"),
+  ("status.down", "❌ qBittorrent no está accesible: {error}"),
+  ("magnet.ask", "Envíame el enlace magnet o el hash"),
+  ("magnet.missing", "Por favor, envíame tu enlace magnet."),
+  ("torrent.added", "El torrent se ha añadido a la cola de descargas"),
+  (
+    "sequential.ask",
+    "Envíame un enlace magnet o un hash para añadirlo en modo secuencial, o el hash de un torrent existente",
+  ),
+  ("sequential.missing", "Por favor, envíame un enlace magnet o el hash de un torrent."),
+  ("sequential.enabled", "La descarga secuencial está activada para el torrent"),
+  ("sequential.added", "El torrent se ha añadido en modo secuencial"),
+  ("cookie.ask", "Envíame la cookie del tracker, p. ej. uid=123; pass=abc"),
+  ("cookie.missing", "Por favor, envíame la cookie como texto."),
+  ("cookie.ask_url", "Entendido. Ahora envíame la URL del .torrent"),
+  ("cookie.missing_url", "Por favor, envíame la URL del .torrent."),
+  ("cookie.fetch_failed", "No se pudo descargar el .torrent: {error}"),
+  ("cookie.not_torrent", "Esa URL no devolvió un archivo .torrent."),
+  ("picker.button", "📂 Prioridad de archivos"),
+  ("picker.no_metadata", "Los metadatos aún no están disponibles, inténtalo en un momento."),
+  ("picker.prompt", "Toca un archivo para alternar ⬇️ descargar → ⏫ prioridad alta → ⏭ omitir:"),
+  ("picker.empty", "Hay que descargar al menos un archivo."),
+  ("picker.done", "Descargando {count} archivo(s)."),
+  ("notify.prompt", "Toca para elegir sobre qué te aviso:"),
+  ("features.admins_only", "Solo los administradores pueden cambiar funciones."),
+  ("features.prompt", "Toca una función para activarla o desactivarla:"),
+  ("history.empty", "Todavía no ha pasado nada."),
+  ("history.title", "Acciones recientes (página {page}/{pages}):"),
+  ("history.newer", "⬅️ Más recientes"),
+  ("history.older", "Más antiguas ➡️"),
+  ("quota.error", "No pude comprobar tu cuota de descarga, inténtalo más tarde."),
+  (
+    "quota.daily",
+    "Has agotado tu cuota diaria de {limit} ({used} añadidos). Vuelve a intentarlo mañana.",
+  ),
+  (
+    "quota.monthly",
+    "Has agotado tu cuota mensual de {limit} ({used} añadidos). Vuelve a intentarlo el mes que viene.",
+  ),
+  (
+    "quota.daily_exceeds",
+    "Añadir {size} superaría tu cuota diaria de {limit} ({used} añadidos hasta ahora).",
+  ),
+  (
+    "quota.monthly_exceeds",
+    "Añadir {size} superaría tu cuota mensual de {limit} ({used} añadidos hasta ahora).",
+  ),
+  (
+    "quota.removed",
+    "🗑 {name} se eliminó al conocerse su tamaño:\n{reason}",
+  ),
+  (
+    "quota.dropped",
+    "⚠️ {name} no cabe en tu cuota ahora que se conoce su tamaño:\n{reason}\nOtra persona también lo añadió, así que se queda y deja de contar para la tuya.",
+  ),
+  (
+    "watch.not_found",
+    "Ningún torrent coincide con ese hash. Uso: /watch <hash o prefijo del hash>",
+  ),
+  ("watch.removed", "El torrent se ha eliminado."),
+  ("watch.stopped", "Detenido"),
+  ("watch.status", "👀 {name}\n\nEstado: {state}\nProgreso: {progress}%\n⬇️ {dlspeed} ⬆️ {upspeed}\nTiempo restante: {eta}\nPares: {seeds} semillas, {leechers} descargando"),
+  ("trackers.title", "Trackers:"),
+  ("trackers.not_working", "no funciona"),
+  ("recategorize.usage", "Uso: /recategorize <filtros> <destino> [move]\nFiltros (deben cumplirse todos): tracker:<dominio> name:<regex> category:<nombre>\nAñade \"move\" para mover también los datos a la ruta de la categoría de destino.\nEjemplo: /recategorize tracker:example.org category:tv Series move"),
+  ("recategorize.unexpected", "Argumento inesperado: {argument}"),
+  ("recategorize.bad_regex", "Patrón de nombre no válido: {error}"),
+  ("recategorize.none", "Ningún torrent cumple esos filtros."),
+  ("recategorize.moving", "Moviendo {count} torrent(s) a {category}..."),
+  ("recategorize.progress", "Movidos {done}/{count} torrent(s) a {category}..."),
+  ("recategorize.done", "Movidos {count} torrent(s) a {category}."),
+  ("recategorize.done_relocating", "Movidos {count} torrent(s) a {category}, los datos se están trasladando."),
+  ("profile.gone", "Este perfil ya no existe."),
+  ("profile.not_found", "Ningún torrent coincide con ese hash."),
+  ("profile.applied", "Perfil {name} aplicado"),
+  ("profile.failed", "No se pudo aplicar {name}: {error}"),
+  ("digest.daily", "Resumen diario"),
+  ("digest.weekly", "Resumen semanal"),
+  ("digest.completed", "📊 {title}\n\nCompletados: {count}"),
+  ("digest.more", "…y {count} más"),
+  ("digest.since_last", "Descargado {downloaded}, subido {uploaded} desde el último resumen"),
+  ("digest.this_session", "Descargado {downloaded}, subido {uploaded} en esta sesión"),
+  ("digest.totals", "Espacio libre en disco: {free}\nRatio global: {ratio}"),
+  ("digest.dry_run", "🧪 Simulación, no se ha borrado:"),
+  ("settings.prompt", "Toca un ajuste para cambiarlo:"),
+  ("settings.page_size", "📄 Elementos por página: {value}"),
+  ("settings.watch_refresh", "🔄 Actualización de /watch: cada {value}s"),
+  ("settings.inline_results", "🔎 Resultados de búsqueda inline: {value}"),
+  ("admin.only", "Solo los administradores pueden hacer eso."),
+  ("restart.shutdown_only", "Se ha pedido a qBittorrent que se cierre. No hay restart_command configurado, así que tendrás que iniciarlo tú."),
+  ("restart.running", "qBittorrent se está cerrando, ejecutando el comando de reinicio..."),
+  ("restart.failed", "❌ El comando de reinicio ha fallado: {error}"),
+  ("restart.hook_timeout", "❌ El comando de reinicio se detuvo tras {secs} s sin terminar."),
+  ("restart.done", "✅ qBittorrent {version} vuelve a estar activo"),
+  ("restart.timeout", "⚠️ El comando de reinicio se ejecutó, pero qBittorrent sigue sin responder."),
+  ("maintenance.busy", "🛠 El bot está en mantenimiento, inténtalo de nuevo más tarde."),
+  ("maintenance.is_on", "El modo mantenimiento está activado. Uso: /maintenance on|off"),
+  ("maintenance.is_off", "El modo mantenimiento está desactivado. Uso: /maintenance on|off"),
+  ("maintenance.started", "🛠 Modo mantenimiento activado. Solo los administradores pueden usar el bot y las notificaciones están en pausa."),
+  ("maintenance.ended", "✅ Modo mantenimiento desactivado."),
+  ("compare.usage", "Uso: /compare <hash1> <hash2>"),
+  ("compare.not_found", "Cada hash tiene que coincidir con un único torrent."),
+  ("compare.delete", "🗑 Borrar {side}"),
+  ("detect.prompt", "¿Añadir este torrent?"),
+  ("detect.add", "✅ Añadir"),
+  ("detect.dismiss", "✖️ Descartar"),
+  ("detect.gone", "El mensaje original ya no está, vuelve a enviar el torrent."),
+  ("album.added", "Se han añadido {count} torrent(s):"),
+  ("album.failed", "No se pudieron descargar estos archivos de Telegram:"),
+  ("parental.usage", "Uso: /parental on|off"),
+  ("parental.on", "🔒 Este chat usa ahora el perfil restringido."),
+  ("parental.off", "🔓 El perfil restringido está desactivado en este chat."),
+  ("parental.wrong_pin", "PIN incorrecto."),
+  ("parental.unlocked", "🔓 Desbloqueado durante {mins} minutos."),
+  ("parental.blocked", "🔒 Este torrent no se puede añadir aquí."),
+  (
+    "parental.removed",
+    "🔒 Se eliminó {name}, su nombre no se puede añadir aquí.",
+  ),
+  (
+    "parental.off_admin_only",
+    "Solo los administradores de ADMIN_USER_IDS pueden desactivar el perfil restringido.",
+  ),
+  (
+    "parental.locked_out",
+    "Demasiados PIN incorrectos. Vuelve a intentarlo dentro de {mins} minutos.",
+  ),
+  ("cleanup.usage", "Uso: /cleanup para eliminar torrents terminados, /cleanup now para purgar registros antiguos"),
+  ("cleanup.done", "🧹 Se han eliminado {audit} entradas del historial, {quota} registros de cuota antiguos y {tokens} tokens de botones caducados."),
+  ("send.not_found", "Ningún torrent coincide con ese hash. Uso: /send <hash o prefijo del hash>"),
+  ("send.incomplete", "El torrent todavía no ha terminado de descargarse."),
+  ("send.too_large", "Estos archivos superan el límite de 50 MB de Telegram para bots y no se han enviado:"),
+  ("send.auto_button", "📤 Enviar al terminar"),
+  ("send.auto_set", "Subiré los archivos aquí cuando termine la descarga."),
+  ("cleanup.none", "No hay nada que limpiar."),
+  ("cleanup.prompt", "Elige los torrents a eliminar:"),
+  ("cleanup.delete", "🗑 Eliminar"),
+  ("cleanup.delete_data", "🗑 Eliminar con datos"),
+  ("cleanup.cancel", "✖️ Cancelar"),
+  ("cleanup.cancelled", "Limpieza cancelada."),
+  ("cleanup.nothing_selected", "No hay nada seleccionado."),
+  ("cleanup.deleted", "🗑 Se han eliminado {count} torrent(s)."),
+  ("cleanup.deleted_data", "🗑 Se han eliminado {count} torrent(s) y sus datos."),
+  ("cleanup.ratio", "límite de ratio"),
+  ("cleanup.seeding_time", "límite de tiempo de siembra"),
+  ("cleanup.inactive", "inactivo {days}d"),
+  ("cleanup.complete", "completo"),
+  ("policies.none", "No hay políticas de eliminación automática configuradas."),
+  ("policies.nothing", "Ningún torrent cumple una política ahora mismo."),
+  ("policies.title", "🧹 Las políticas eliminarían (🗑 con datos):"),
+  ("policies.dry_run", "🧪 El modo de prueba está activado, solo se listan en el resumen."),
+  ("oncomplete.none", "No hay acciones al completar configuradas."),
+  ("oncomplete.usage", "Uso: /oncomplete <categoría> move <ruta> | tag <etiqueta> | pause | notify [id de chat|here] | upload <remoto de rclone> | clear\nAñade off para quitar una acción, p. ej. /oncomplete tv pause off"),
+  ("oncomplete.cleared", "Se han eliminado las acciones al completar de {category}."),
+  ("oncomplete.move", "mover a {path}"),
+  ("oncomplete.tag", "etiqueta {tag}"),
+  ("oncomplete.pause", "pausar"),
+  ("oncomplete.notify", "avisar a {chat}"),
+  ("delete.not_found", "Ningún torrent coincide con ese hash."),
+  ("delete.prompt", "¿Borrar {name} junto con sus archivos? No se puede deshacer."),
+  ("delete.button", "🗑 Borrar con datos"),
+  ("delete.type_name", "Para borrar {name} y sus archivos, escribe su nombre o los primeros 8 caracteres de su hash ({short}). Cualquier otra cosa lo cancela."),
+  ("delete.mismatch", "No coincide, no se ha borrado nada."),
+  ("delete.done", "🗑 Se han borrado {name} y sus archivos. Deshacer vuelve a añadir el torrent, los archivos se descargan de nuevo."),
+  ("undo.button", "↩️ Deshacer"),
+  ("undo.expired", "Ya es tarde para deshacerlo."),
+  ("undo.restored", "↩️ Añadidos de nuevo: {names}"),
+  ("undo.failed", "No se han podido añadir de nuevo:"),
+  ("help.title.basics", "Básico"),
+  ("help.title.control", "Torrents"),
+  ("help.title.streaming", "Streaming"),
+  ("help.title.config", "Ajustes"),
+  ("help.title.admin", "Admin"),
+  ("help.aliases", "Alias:"),
+  ("help.basics", "📖 Básico\n\n/magnet — añade un enlace magnet, luego envíalo\n/addwithcookie — añade una URL .torrent que requiere sesión en el tracker\n/template — guarda categoría, ruta y etiquetas para elegirlas al añadir, p. ej. /template peliculas category:Movies\n/addlater <hora> <magnet> — lo añade más tarde, p. ej. fuera de hora punta con /addlater 02:00 magnet:?xt=...\n/status — qBittorrent, número de torrents y tiempo activo del bot\n/cancel — cancela lo que empezaste\n\nSi envías un archivo .torrent (o un álbum) te ofrezco añadirlo.\nResponde a uno de mis mensajes sobre un torrent con pause, resume, delete o files para actuar sobre él.\nBúsqueda inline: @YourBot ubuntu"),
+  ("help.control", "🎛 Torrents\n\n/watch <hash> — sigue el progreso en directo, p. ej. /watch 3f2a\n/compare <hash1> <hash2> — compara dos versiones, p. ej. /compare 3f2a 9b1c\n/recategorize <filtros> <destino> [move] — p. ej. /recategorize tracker:example.org TV move\n/deletedata [hash] — borra un torrent y sus archivos, p. ej. /deletedata 3f2a\n/list [filtro|grouped] [sort:<clave>] — tus torrents, p. ej. /list downloading sort:size\n/bycategory, /bytag — torrents agrupados con totales y tamaños\n/queue — la cola de descargas en orden (#1 empieza antes) con botones para moverlos\n/files [hash] — los archivos de un torrent, página a página, p. ej. /files 3f2a\n/recent [24h|7d] — torrents añadidos hace poco y cómo van, p. ej. /recent 7d\n/find <texto> — busca por nombre, p. ej. /find breaking bad\n/search <texto> — busca torrents nuevos en los indexadores, p. ej. /search ubuntu\n/sonarr, /radarr <magnet|número> — deja que Sonarr o Radarr descarguen y organicen una versión; responde a los resultados de /search con un número\n/top speed|size|ratio|peers — los torrents más pesados, p. ej. /top ratio\n/transfer — estadísticas globales de transferencia\n/ratio — ratio global, por categoría y los mejores y peores torrents\n/policies — qué borrarían ahora las reglas de eliminación automática"),
+  ("help.streaming", "🎬 Streaming\n\n/sequential — añade un magnet para streaming, o envía un hash para un torrent existente\n/send <hash> — sube aquí los archivos terminados, p. ej. /send 3f2a\nEl botón \"Enviar al terminar\" hace lo mismo cuando acaba la descarga.\n/subtitles <hash> — descarga subtítulos de OpenSubtitles junto a los vídeos"),
+  ("help.config", "⚙️ Ajustes\n\n/settings — tamaños de página, refresco, resultados inline y estilo de listas\n/settings timezone <zona> — muestra las horas en tu zona, p. ej. /settings timezone Europe/Madrid\n/language — el idioma en el que respondo\n/notify — qué notificaciones recibes\n/unlock <pin> — levanta la restricción del chat durante 30 minutos, p. ej. /unlock 4321"),
+  ("help.admin", "🛡 Admin\n\n/features — activa o desactiva funciones del bot\n/parental on|off — restringe lo que ve este chat\n/maintenance on|off — pausa el bot para los demás\n/guest <horas> — enlace de un solo uso con acceso de solo lectura; /guest lista los invitados\n/quiethours <desde>-<hasta> [altspeed|pause] — p. ej. /quiethours 23:00-07:00\n/cleanup — borra torrents terminados; /cleanup now purga registros antiguos\n/oncomplete <categoría> <acción> — p. ej. /oncomplete tv move /media/tv\n/history — quién hizo qué recientemente\n/qbrestart — reinicia qBittorrent"),
+  ("top.usage", "Uso: /top speed|size|ratio|peers"),
+  ("top.empty", "Todavía no hay torrents."),
+  ("top.title", "🏆 Torrents principales por {metric}:"),
+  ("settings.list_style", "📋 Listas de torrents: {value}"),
+  ("settings.compact", "compactas"),
+  ("settings.detailed", "detalladas"),
+  ("list.usage", "Uso: /list [{filters}] [sort:{sorts}] o /list grouped"),
+  ("list.empty", "Todavía no hay torrents."),
+  ("list.title", "📋 {count} torrent(s):"),
+  ("list.more", "…y {count} más."),
+  ("state.downloading", "Descargando"),
+  ("state.stalled", "Atascados"),
+  ("state.seeding", "Compartiendo"),
+  ("state.paused", "En pausa"),
+  ("state.errored", "Con errores"),
+  ("state.other", "Otros"),
+  ("list.page_title", "📋 Torrents ({filter}, por {sort}), página {page}:"),
+  ("list.previous", "◀️ Anterior"),
+  ("list.next", "Siguiente ▶️"),
+  ("find.usage", "Uso: /find <parte del nombre>"),
+  ("find.none", "Ningún nombre de torrent coincide."),
+  ("find.more", "…y {count} más, prueba una búsqueda más larga."),
+  ("find.pause", "⏸ Pausar"),
+  ("find.resume", "▶️ Reanudar"),
+  ("find.paused", "En pausa."),
+  ("find.resumed", "Reanudado."),
+  ("list.sort_by", "🔃 Ordenar por…"),
+  ("labels.header", "📁 {name} — {count} torrent(s), {size}"),
+  ("labels.uncategorized", "Sin categoría"),
+  ("labels.untagged", "Sin etiqueta"),
+  ("queue.empty", "No hay nada en cola. Las posiciones solo existen con la cola de torrents activada en qBittorrent."),
+  ("queue.title", "🚦 Cola de descargas, #1 empieza primero (⏫ arriba del todo, 🔼 subir, 🔽 bajar, ⏬ al final):"),
+  ("callback.expired", "Este botón ha caducado, vuelve a ejecutar el comando."),
+  ("files.title", "📂 {name}\n{count} archivo(s), página {page}/{pages} (⏫ prioridad alta, ⏭ omitido):"),
+  ("files.not_found", "Ningún torrent coincide con ese hash."),
+  ("settings.files_page_size", "📂 Archivos por página: {value}"),
+  ("select.empty", "No hay torrents entre los que elegir."),
+  ("select.files", "¿Los archivos de qué torrent?"),
+  ("select.deletedata", "¿Qué torrent quieres borrar junto con sus archivos?"),
+  ("files.priorities", "🎚 Cambiar prioridades"),
+  ("status.torrents", "📦 {total} torrent(s), {active} activo(s)"),
+  ("status.uptime", "⏱ Bot activo desde hace {uptime}"),
+  ("status.last_error", "⚠️ Último error hace {ago}: {error}"),
+  ("status.no_errors", "👌 Ningún error desde que arrancó el bot"),
+  ("qbit.offline", "⏳ qBittorrent no está accesible ahora mismo, estoy reconectando. Inténtalo en un momento o consulta /status."),
+  ("reply.not_found", "No sé a qué torrent se refiere ese mensaje."),
+  ("reply.ambiguous", "Ese mensaje menciona varios torrents, añade parte del nombre, p. ej. \"pause ubuntu\":"),
+  ("start.invalid", "Ese enlace no contiene un magnet ni un hash que pueda leer."),
+  ("subtitles.disabled", "Los subtítulos necesitan una cuenta de OpenSubtitles en la configuración `opensubtitles`."),
+  ("subtitles.usage", "Ningún torrent coincide con ese hash. Uso: /subtitles <hash o prefijo del hash>"),
+  ("subtitles.none", "No se han encontrado subtítulos para los vídeos de este torrent."),
+  ("subtitles.found", "📝 Subtítulos de {name}, guardados junto a los vídeos:"),
+  ("search.disabled", "No hay indexadores configurados, añade Jackett o Prowlarr en `search` en la configuración."),
+  ("search.usage", "Uso: /search <texto>"),
+  ("search.none", "Los indexadores no han encontrado nada."),
+  ("search.title", "🔎 Resultados de {query}, pulsa un número para añadirlo:"),
+  ("search.expired", "Estos resultados son demasiado antiguos, busca de nuevo."),
+  ("search.added", "Añadido {name}"),
+  ("arr.disabled", "{app} no está configurado."),
+  ("arr.usage", "Uso: /{command} <magnet>, o responde a los resultados de /search con /{command} <número>"),
+  ("arr.sent", "{title} enviado a {app}."),
+  ("arr.rejected", "{app} ha rechazado la versión: {reasons}"),
+  ("arr.failed", "No se ha podido contactar con {app}: {error}"),
+  ("oncomplete.upload", "subir a {remote}"),
+  ("quiet.usage", "Uso: /quiethours <HH:MM>-<HH:MM> [altspeed|pause] (UTC), o /quiethours off"),
+  ("quiet.off", "Las horas de silencio están desactivadas."),
+  ("quiet.on", "Horas de silencio: {start}-{end} UTC, {mode}."),
+  ("quiet.active", "Están en vigor ahora mismo."),
+  ("quiet.mode_altspeed", "límites de velocidad alternativos"),
+  ("quiet.mode_pause", "torrents activos en pausa"),
+  ("duplicate.same_hash", "Ya está: {name}"),
+  ("duplicate.same_content", "Parece {name}, que ya está (mismo nombre y tamaño, quizá de otro tracker)."),
+  ("duplicate.add_anyway", "➕ Añadir igualmente"),
+  ("milestones.button", "🔔 Avisos de progreso"),
+  ("milestones.on", "Te avisaré aquí al 25, 50, 75, 90 y 100%."),
+  ("milestones.off", "No habrá más avisos de progreso para este torrent."),
+  ("cleanup.dead", "muerto {hours}h"),
+  ("cleanup.dead_found", "💀 {count} descarga(s) llevan un tiempo sin semillas ni progreso. ¿Eliminarlas?"),
+  ("ratio.global", "⚖️ Ratio global: {ratio} ({uploaded} subidos, {downloaded} bajados)"),
+  ("ratio.by_category", "Por categoría:"),
+  ("ratio.best", "Mejor ratio:"),
+  ("ratio.worst", "Peor ratio:"),
+  ("recent.usage", "Uso: /recent [24h|7d]"),
+  ("recent.empty", "No se añadió nada en las últimas {period}."),
+  ("recent.title", "🆕 {count} torrent(s) añadidos en las últimas {period}:"),
+  ("recent.added", "añadido el {at}, hace {ago}"),
+  ("settings.timezone", "🕒 Zona horaria: {value}"),
+  ("settings.usage", "Elige tu zona horaria con /settings timezone <zona>, p. ej. /settings timezone Europe/Madrid"),
+  ("settings.timezone_invalid", "No conozco esa zona horaria. Usa un nombre como Europe/Madrid, America/New_York o UTC."),
+  ("settings.timezone_set", "Las horas se muestran ahora en {zone}."),
+  ("settings.units", "📏 Unidades: {value}"),
+  ("limit.button", "🐢 Límite de velocidad"),
+  ("limit.prompt", "Elige un límite de descarga (⬇️) o de subida (⬆️) para este torrent:"),
+  ("limit.unlimited", "Sin límite"),
+  ("limit.custom", "✏️ Otro"),
+  ("limit.ask_dl", "Envíame el límite de descarga, p. ej. 2.5 (MB/s), 800k o unlimited. /cancel mantiene el actual."),
+  ("limit.ask_up", "Envíame el límite de subida, p. ej. 2.5 (MB/s), 800k o unlimited. /cancel mantiene el actual."),
+  ("limit.invalid", "No entiendo esa velocidad. Prueba con 2.5, 800k, 10 MB/s o unlimited."),
+  ("limit.set_dl", "⬇️ Límite de descarga: {limit}"),
+  ("limit.set_up", "⬆️ Límite de subida: {limit}"),
+  ("template.usage", "Uso: /template <nombre> [category:<categoría>] [path:<ruta>] [tags:<a,b>] [paused] [sequential], o /template <nombre> delete\nLos nombres usan letras, dígitos, - y _, p. ej. /template peliculas category:Movies path:/data/movies"),
+  ("template.none", "Todavía no tienes plantillas para añadir."),
+  ("template.saved", "📐 Guardada {template}"),
+  ("template.deleted", "Plantilla {name} eliminada."),
+  ("template.unknown", "No tienes ninguna plantilla llamada {name}."),
+  ("template.category", "categoría {category}"),
+  ("template.path", "guardado en {path}"),
+  ("template.tags", "etiquetas {tags}"),
+  ("template.paused", "en pausa"),
+  ("template.sequential", "secuencial"),
+  ("template.defaults", "valores por defecto de qBittorrent"),
+  ("template.pick", "¿Con qué plantilla lo añado?"),
+  ("template.without", "➕ Sin plantilla"),
+  ("later.usage", "Uso: /addlater <HH:MM|30m|2h|1d> <magnet o hash>, p. ej. /addlater 02:00 magnet:?xt=... Las horas van en tu zona horaria de /settings."),
+  ("later.empty", "No hay nada programado."),
+  ("later.title", "⏰ Añadidos programados, pulsa uno para cancelarlo:"),
+  ("later.scheduled", "⏰ Añadiré {name} el {at}."),
+  ("later.cancelled", "Cancelado."),
+  ("later.gone", "Eso ya se añadió o se canceló."),
+  ("later.added", "⏰ {name} añadido según lo programado"),
+  ("later.failed", "⏰ No se pudo añadir {name}: {error}"),
+  ("watch_folder.added", "📂 {name} añadido desde la carpeta vigilada"),
+  ("watch_folder.failed", "⚠️ No se pudo añadir desde la carpeta vigilada: {name}: {error}"),
+  (
+    "guard.too_large",
+    "🛡 No se añadió: ocupa {size} y el límite es {max}.",
+  ),
+  ("guard.extension", "🛡 No se añadió: no se permiten archivos .{extension}."),
+  ("guard.tracker", "🛡 No se añadió: el tracker {tracker} está prohibido."),
+  (
+    "guard.downloads",
+    "🛡 No se añadió: ya hay {max} descargas en curso, que es el límite.",
+  ),
+  ("guard.override", "🛡 Añadir igualmente (admins)"),
+  (
+    "guard.removed",
+    "🛡 {name} se quitó de nuevo, sus metadatos incumplían la política de añadidos:\n{reason}",
+  ),
+  ("guest.usage", "Uso: /guest <horas>, p. ej. /guest 48 para dos días de acceso de solo lectura. Sin horas lista los invitados."),
+  ("guest.link", "🎟 Acceso de solo lectura durante {hours} h, el enlace funciona una vez en el próximo día:\n{link}"),
+  ("guest.none", "Ahora mismo no hay invitados."),
+  ("guest.title", "🎟 Invitados:"),
+  ("guest.until", "hasta {until}"),
+  ("guest.revoke", "✖️ Retirar a {name}"),
+  ("guest.revoked", "Acceso de invitado retirado."),
+  ("guest.gone", "El acceso de ese invitado ya había terminado."),
+  ("guest.invalid", "Este enlace de invitado no es válido, ya se usó o caducó."),
+  ("guest.member", "Ya tienes acceso completo, el enlace queda para otra persona."),
+  ("guest.welcome", "👋 Eres invitado hasta {until}. Puedes usar /list, /files, /recent, /find, /top, /ratio, /transfer, /watch, /send y /status, pero no añadir ni cambiar torrents."),
+  ("guest.read_only", "🔒 Los invitados solo pueden mirar, pídeselo a un administrador."),
+];
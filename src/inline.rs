@@ -0,0 +1,91 @@
+use std::cmp::Reverse;
+use teloxide::{
+  prelude::*,
+  types::{
+    InlineQueryResult, InlineQueryResultArticle, InputMessageContent, InputMessageContentText,
+  },
+};
+
+use crate::{
+  db::Db,
+  features::{Feature, Features},
+  format::Units,
+  parental::Parental,
+  settings,
+  torrent::events::{TorrentEvents, TorrentSnapshot},
+  HandlerResult,
+};
+
+// Telegram accepts at most 50 results per answer
+const MAX_RESULTS: usize = 50;
+
+fn summary(torrent: &TorrentSnapshot, units: Units) -> String {
+  format!(
+    "{:.1}% • {} • {} • ⬇️ {}",
+    torrent.progress * 100.0,
+    torrent.state,
+    units.bytes(torrent.size),
+    units.speed(torrent.dlspeed),
+  )
+}
+
+/// Answers `@bot <text>` with the torrents whose names contain every word of the query.
+pub async fn inline_query(
+  bot: Bot,
+  q: InlineQuery,
+  events: TorrentEvents,
+  features: Features,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  if !features.enabled(Feature::InlineSearch) {
+    bot
+      .answer_inline_query(q.id, Vec::<InlineQueryResult>::new())
+      .await?;
+    return Ok(());
+  }
+  let words: Vec<String> = q.query.split_whitespace().map(str::to_lowercase).collect();
+
+  let mut matches: Vec<TorrentSnapshot> = events
+    .torrents()
+    .borrow()
+    .values()
+    // inline queries have no chat, so the user's private chat decides
+    .filter(|torrent| !parental.hides(ChatId(q.from.id.0 as i64), torrent))
+    .filter(|torrent| {
+      let name = torrent.name.to_lowercase();
+      words.iter().all(|word| name.contains(word.as_str()))
+    })
+    .cloned()
+    .collect();
+  matches.sort_by_key(|torrent| Reverse(torrent.added_on));
+
+  let settings = settings::of(&db, Some(&q.from));
+  let units = settings.units();
+  let results = matches
+    .iter()
+    .take(settings.inline_results.min(MAX_RESULTS))
+    .map(|torrent| {
+      let text = format!(
+        "{}\n{}\nHash: {}",
+        torrent.name,
+        summary(torrent, units),
+        torrent.hash
+      );
+      InlineQueryResult::Article(
+        InlineQueryResultArticle::new(
+          &torrent.hash,
+          &torrent.name,
+          InputMessageContent::Text(InputMessageContentText::new(text)),
+        )
+        .description(summary(torrent, units)),
+      )
+    });
+
+  bot
+    .answer_inline_query(q.id, results)
+    .cache_time(5)
+    .is_personal(true)
+    .await?;
+  Ok(())
+}
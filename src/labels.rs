@@ -0,0 +1,132 @@
+use teloxide::prelude::*;
+
+use crate::{
+  db::{Db, UserSettings},
+  format::split_message,
+  i18n::{self, Lang},
+  parental::Parental,
+  settings,
+  torrent::{ApiResult, ListQuery, TorrentApi},
+  HandlerResult,
+};
+
+#[derive(Debug, Clone, Copy)]
+enum Label {
+  Category,
+  Tag,
+}
+
+impl Label {
+  fn query(self, name: &str) -> ListQuery {
+    let mut query = ListQuery {
+      sort: Some("name".to_string()),
+      ..Default::default()
+    };
+    match self {
+      Label::Category => query.category = Some(name.to_string()),
+      Label::Tag => query.tag = Some(name.to_string()),
+    }
+    query
+  }
+}
+
+/// One section per category or tag with its torrent count and total size, then up to a
+/// page of names. The empty name collects torrents without one.
+async fn render(
+  torrent: &TorrentApi,
+  parental: &Parental,
+  chat: ChatId,
+  label: Label,
+  settings: &UserSettings,
+  lang: Lang,
+) -> ApiResult<String> {
+  let per_group = settings.page_size as usize;
+  let mut names = match label {
+    Label::Category => torrent.categories().await?,
+    Label::Tag => torrent.tags().await?,
+  };
+  names.push(String::new());
+
+  let mut sections = Vec::new();
+  for name in names {
+    let torrents: Vec<_> = torrent
+      .list_with(&label.query(&name))
+      .await?
+      .into_iter()
+      .filter(|torrent| !parental.hides_labelled(chat, &torrent.category, &torrent.tags))
+      .collect();
+    if torrents.is_empty() {
+      continue;
+    }
+
+    let size: u64 = torrents.iter().map(|torrent| torrent.size).sum();
+    let title = if name.is_empty() {
+      match label {
+        Label::Category => lang.t("labels.uncategorized").to_string(),
+        Label::Tag => lang.t("labels.untagged").to_string(),
+      }
+    } else {
+      name
+    };
+    let mut section = lang.tf(
+      "labels.header",
+      &[
+        ("name", &title),
+        ("count", &torrents.len()),
+        ("size", &settings.units().bytes(size)),
+      ],
+    );
+    for torrent in torrents.iter().take(per_group) {
+      section.push_str(&format!("\n• {}", torrent.name));
+    }
+    if torrents.len() > per_group {
+      section.push_str(&format!(
+        "\n{}",
+        lang.tf("list.more", &[("count", &(torrents.len() - per_group))])
+      ));
+    }
+    sections.push(section);
+  }
+
+  if sections.is_empty() {
+    return Ok(lang.t("list.empty").to_string());
+  }
+  Ok(sections.join("\n\n"))
+}
+
+async fn reply(
+  bot: Bot,
+  msg: Message,
+  torrent: TorrentApi,
+  parental: Parental,
+  db: Db,
+  label: Label,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let settings = settings::of(&db, msg.from());
+  let text = render(&torrent, &parental, msg.chat.id, label, &settings, lang).await?;
+  for piece in split_message(&text) {
+    bot.send_message(msg.chat.id, piece).await?;
+  }
+  Ok(())
+}
+
+pub async fn bycategory(
+  bot: Bot,
+  msg: Message,
+  torrent: TorrentApi,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  reply(bot, msg, torrent, parental, db, Label::Category).await
+}
+
+pub async fn bytag(
+  bot: Bot,
+  msg: Message,
+  torrent: TorrentApi,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  reply(bot, msg, torrent, parental, db, Label::Tag).await
+}
@@ -0,0 +1,310 @@
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode},
+};
+
+use crate::{
+  callback_data,
+  db::{Db, UserSettings},
+  format::StateGroup,
+  i18n::{self, Lang},
+  parental::Parental,
+  render, settings,
+  torrent::{ApiResult, ListQuery, TorrentApi},
+  HandlerResult,
+};
+
+/// `filter` values of `torrents/info` offered by /list.
+const FILTERS: [&str; 9] = [
+  "all",
+  "downloading",
+  "seeding",
+  "completed",
+  "paused",
+  "active",
+  "inactive",
+  "stalled",
+  "errored",
+];
+
+/// `/list sort:<key>` orders and the `torrents/info` fields behind them, all descending.
+const SORTS: [(&str, &str); 5] = [
+  ("added", "added_on"),
+  ("size", "size"),
+  ("progress", "progress"),
+  ("speed", "dlspeed"),
+  ("ratio", "ratio"),
+];
+
+fn sort_field(sort: &str) -> Option<&'static str> {
+  SORTS
+    .iter()
+    .find(|(key, _)| *key == sort)
+    .map(|(_, field)| *field)
+}
+
+/// Previous/next buttons for paged messages, `data` builds the callback data of a page.
+pub fn page_row(
+  page: usize,
+  has_next: bool,
+  lang: Lang,
+  data: impl Fn(usize) -> String,
+) -> Vec<InlineKeyboardButton> {
+  let mut row = Vec::new();
+  if page > 0 {
+    row.push(callback_data::button(
+      lang.t("list.previous"),
+      data(page - 1),
+    ));
+  }
+  if has_next {
+    row.push(callback_data::button(lang.t("list.next"), data(page + 1)));
+  }
+  row
+}
+
+/// What a /list message shows, round-tripped through the callback data as
+/// `list:<filter>:<sort>:<page>`.
+struct View {
+  filter: String,
+  sort: String,
+  page: usize,
+}
+
+impl View {
+  fn data(&self, page: usize) -> String {
+    format!("list:{}:{}:{page}", self.filter, self.sort)
+  }
+
+  fn parse(data: &str) -> Option<Self> {
+    let mut parts = data.strip_prefix("list:")?.split(':');
+    let filter = parts.next().filter(|filter| FILTERS.contains(filter))?;
+    let sort = parts.next().filter(|sort| sort_field(sort).is_some())?;
+    let page = parts.next()?.parse().ok()?;
+    Some(View {
+      filter: filter.to_string(),
+      sort: sort.to_string(),
+      page,
+    })
+  }
+}
+
+/// Fetches one extra torrent to know whether there is a next page.
+async fn page(
+  torrent: &TorrentApi,
+  parental: &Parental,
+  chat: ChatId,
+  view: &View,
+  settings: &UserSettings,
+  lang: Lang,
+) -> ApiResult<(String, InlineKeyboardMarkup)> {
+  let page_size = settings.page_size as usize;
+  let query = ListQuery {
+    sort: sort_field(&view.sort).map(str::to_string),
+    reverse: true,
+    limit: Some(page_size + 1),
+    offset: Some(view.page * page_size),
+    filter: Some(view.filter.clone()),
+    ..Default::default()
+  };
+  let mut torrents = torrent.list_with(&query).await?;
+  let has_next = torrents.len() > page_size;
+  torrents.truncate(page_size);
+
+  let mut text = lang.tf(
+    "list.page_title",
+    &[
+      ("filter", &view.filter),
+      ("sort", &view.sort),
+      ("page", &(view.page + 1)),
+    ],
+  );
+  let visible: Vec<_> = torrents
+    .iter()
+    .filter(|torrent| !parental.hides_labelled(chat, &torrent.category, &torrent.tags))
+    .collect();
+  if visible.is_empty() {
+    text.push_str(&format!("\n\n{}", lang.t("list.empty")));
+  }
+  for torrent in visible {
+    text.push_str(&format!(
+      "\n\n{}",
+      render::torrent_item(torrent, settings.detailed_list, settings.units())
+    ));
+  }
+
+  let row = page_row(view.page, has_next, lang, |page| view.data(page));
+  let sort = vec![callback_data::button(
+    lang.t("list.sort_by"),
+    format!("list:sort:{}", view.filter),
+  )];
+  Ok((text, InlineKeyboardMarkup::new([row, sort])))
+}
+
+fn sort_keyboard(filter: &str, current: &str) -> InlineKeyboardMarkup {
+  let buttons: Vec<_> = SORTS
+    .iter()
+    .map(|(key, _)| {
+      let label = if *key == current {
+        format!("• {key} •")
+      } else {
+        key.to_string()
+      };
+      callback_data::button(label, format!("list:{filter}:{key}:0"))
+    })
+    .collect();
+  InlineKeyboardMarkup::new(buttons.chunks(3).map(<[_]>::to_vec))
+}
+
+/// Buckets every torrent by state with counts, showing at most a page of them.
+async fn grouped(
+  torrent: &TorrentApi,
+  parental: &Parental,
+  chat: ChatId,
+  settings: &UserSettings,
+  lang: Lang,
+) -> ApiResult<String> {
+  let page_size = settings.page_size as usize;
+  let mut torrents: Vec<_> = torrent
+    .list()
+    .await?
+    .into_iter()
+    .filter(|torrent| !parental.hides_labelled(chat, &torrent.category, &torrent.tags))
+    .collect();
+  if torrents.is_empty() {
+    return Ok(lang.t("list.empty").to_string());
+  }
+  torrents.sort_by_key(|torrent| std::cmp::Reverse(torrent.added_on));
+
+  let mut text = lang.tf("list.title", &[("count", &torrents.len())]);
+  let mut shown = 0;
+  for group in StateGroup::ALL {
+    let members: Vec<_> = torrents
+      .iter()
+      .filter(|torrent| StateGroup::of(&torrent.state) == group)
+      .collect();
+    if members.is_empty() {
+      continue;
+    }
+    text.push_str(&format!(
+      "\n\n{} {} ({})",
+      group.emoji(),
+      lang.t(group.key()),
+      members.len()
+    ));
+    for torrent in members.into_iter().take(page_size.saturating_sub(shown)) {
+      text.push_str(&format!(
+        "\n{}",
+        render::torrent_item(torrent, settings.detailed_list, settings.units())
+      ));
+      shown += 1;
+    }
+  }
+  if shown < torrents.len() {
+    text.push_str(&format!(
+      "\n\n{}",
+      lang.tf("list.more", &[("count", &(torrents.len() - shown))])
+    ));
+  }
+  Ok(text)
+}
+
+/// `/list [filter] [sort:<key>]` pages through the torrents matching a state filter,
+/// `/list grouped` buckets all of them by state. A sort given here becomes the user's default.
+pub async fn list(
+  bot: Bot,
+  msg: Message,
+  args: String,
+  torrent: TorrentApi,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let mut settings = settings::of(&db, msg.from());
+  let args = args.trim().to_lowercase();
+
+  if args == "grouped" {
+    let text = grouped(&torrent, &parental, msg.chat.id, &settings, lang).await?;
+    bot
+      .send_message(msg.chat.id, text)
+      .parse_mode(ParseMode::Html)
+      .await?;
+    return Ok(());
+  }
+  let mut view = View {
+    filter: "all".to_string(),
+    sort: settings.list_sort.clone(),
+    page: 0,
+  };
+  for arg in args.split_whitespace() {
+    match arg.strip_prefix("sort:") {
+      Some(sort) if sort_field(sort).is_some() => view.sort = sort.to_string(),
+      None if FILTERS.contains(&arg) => view.filter = arg.to_string(),
+      _ => {
+        let usage = lang.tf(
+          "list.usage",
+          &[
+            ("filters", &FILTERS.join("|")),
+            ("sorts", &SORTS.map(|(key, _)| key).join("|")),
+          ],
+        );
+        bot.send_message(msg.chat.id, usage).await?;
+        return Ok(());
+      }
+    }
+  }
+  if let Some(user) = msg.from().filter(|_| view.sort != settings.list_sort) {
+    settings.list_sort = view.sort.clone();
+    db.set_settings(user.id.0, &settings)?;
+  }
+
+  let (text, keyboard) = page(&torrent, &parental, msg.chat.id, &view, &settings, lang).await?;
+  bot
+    .send_message(msg.chat.id, text)
+    .parse_mode(ParseMode::Html)
+    .reply_markup(keyboard)
+    .await?;
+  Ok(())
+}
+
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  torrent: TorrentApi,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  bot.answer_callback_query(q.id).await?;
+  let (Some(data), Some(msg)) = (q.data.as_deref(), q.message.as_ref()) else {
+    return Ok(());
+  };
+  let mut settings = settings::of(&db, Some(&q.from));
+
+  if let Some(filter) = data
+    .strip_prefix("list:sort:")
+    .filter(|filter| FILTERS.contains(filter))
+  {
+    bot
+      .edit_message_reply_markup(msg.chat.id, msg.id)
+      .reply_markup(sort_keyboard(filter, &settings.list_sort))
+      .await?;
+    return Ok(());
+  }
+  let Some(view) = View::parse(data) else {
+    return Ok(());
+  };
+  // picking from the sort menu remembers the choice
+  if view.sort != settings.list_sort {
+    settings.list_sort = view.sort.clone();
+    db.set_settings(q.from.id.0, &settings)?;
+  }
+
+  let lang = i18n::lang(&db, Some(&q.from));
+  let (text, keyboard) = page(&torrent, &parental, msg.chat.id, &view, &settings, lang).await?;
+  bot
+    .edit_message_text(msg.chat.id, msg.id, text)
+    .parse_mode(ParseMode::Html)
+    .reply_markup(keyboard)
+    .await?;
+  Ok(())
+}
@@ -0,0 +1,49 @@
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+};
+use teloxide::types::{ChatId, InlineKeyboardMarkup, MessageId};
+use tokio::sync::watch;
+
+use crate::callback_data;
+
+// each live message and the switch that stops its refresh task
+type Views = HashMap<(ChatId, MessageId), watch::Sender<bool>>;
+
+/// Messages that are being edited in place by a background task, so a button tap can stop them.
+#[derive(Clone, Default)]
+pub struct LiveViews {
+  views: Arc<Mutex<Views>>,
+}
+
+impl LiveViews {
+  /// The returned receiver flips to `true` once the view should stop refreshing.
+  pub fn register(&self, chat_id: ChatId, message_id: MessageId) -> watch::Receiver<bool> {
+    let (tx, rx) = watch::channel(false);
+    self.lock().insert((chat_id, message_id), tx);
+    rx
+  }
+
+  pub fn stop(&self, chat_id: ChatId, message_id: MessageId) -> bool {
+    match self.lock().remove(&(chat_id, message_id)) {
+      Some(tx) => tx.send(true).is_ok(),
+      None => false,
+    }
+  }
+
+  /// Called by the refreshing task when it ends on its own.
+  pub fn finish(&self, chat_id: ChatId, message_id: MessageId) {
+    self.lock().remove(&(chat_id, message_id));
+  }
+
+  fn lock(&self) -> std::sync::MutexGuard<'_, Views> {
+    self
+      .views
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+pub fn stop_button() -> InlineKeyboardMarkup {
+  InlineKeyboardMarkup::new(vec![vec![callback_data::button("⏹ Stop", "live:stop")]])
+}
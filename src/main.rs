@@ -1,20 +1,113 @@
+use config::Config;
+use db::Db;
+use features::Features;
+use format::format_bytes;
+use metadata::Tmdb;
+use parental::Parental;
+use quota::Quotas;
+use std::{sync::Arc, time::Duration};
 use teloxide::{
   dispatching::{dialogue, dialogue::InMemStorage, UpdateHandler},
   prelude::*,
+  types::{InlineKeyboardMarkup, Me},
   utils::command::BotCommands,
 };
-use torrent::TorrentApi;
+use torrent::{events::TorrentEvents, AddTorrentArg, TorrentApi};
 
 type MyDialogue = Dialogue<State, InMemStorage<State>>;
 type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
+// generous for a .torrent, anything bigger is something else
+const MAX_TORRENT_FILE: usize = 10 * 1024 * 1024;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+mod alert;
+mod alias;
+mod announce;
+mod arr;
+mod audit;
+mod auth;
+mod callback_data;
+mod cleanup;
+mod compare;
+mod completion;
+mod config;
+mod db;
+mod deeplink;
+mod delete;
+mod detect;
+mod digest;
+mod duplicates;
+mod features;
+mod files;
+mod find;
+mod format;
+mod guard;
+mod guest;
+mod help;
+mod i18n;
+mod inline;
+mod labels;
+mod list;
+mod live;
+mod maintenance;
+mod media_group;
+mod metadata;
+mod milestones;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod notify;
+mod parental;
+mod picker;
+mod policy;
+mod profile;
+mod queue;
+mod quiet_hours;
+mod quota;
+mod ratio;
+mod recategorize;
+mod recent;
+mod recovery;
+mod render;
+mod reply;
+mod restart;
+mod schedule;
+mod search;
+mod select;
+mod send;
+mod settings;
+mod speed_limit;
+mod status;
+mod subtitles;
+mod supervisor;
+mod template;
+mod top;
 mod torrent;
+mod transfer;
+mod undo;
+mod upload;
+mod watch;
+mod watch_folder;
+mod webhook;
 
 #[derive(Clone, Default)]
 pub enum State {
   #[default]
   Start,
   GetMagnet,
+  GetSequential,
+  GetCookie,
+  GetCookieUrl {
+    cookie: String,
+  },
+  ConfirmDeleteData {
+    hash: String,
+    name: String,
+  },
+  GetSpeedLimit {
+    hash: String,
+    upload: bool,
+  },
 }
 
 #[derive(BotCommands, Clone)]
@@ -25,28 +118,312 @@ pub enum State {
 enum Command {
   #[command(description = "display this text.")]
   Help,
+  #[command(description = "start the bot, deep links add the magnet they carry")]
+  Start(String),
   #[command(description = "start the torrent download")]
-  // Start,
-  // #[command(description = "start the torrent download")]
   Magnet,
+  #[command(
+    description = "add a torrent for streaming, or enable sequential mode on an existing hash"
+  )]
+  Sequential,
+  #[command(description = "add a .torrent URL that needs a tracker login cookie")]
+  AddWithCookie,
+  #[command(
+    description = "save options to add with: /template <name> category:<c> path:<p> tags:<t> paused sequential"
+  )]
+  Template(String),
+  #[command(
+    description = "add a magnet later: /addlater <HH:MM|30m|2h|1d> <magnet>, alone lists them"
+  )]
+  AddLater(String),
+  #[command(description = "move matching torrents to another category, see /recategorize")]
+  Recategorize(String),
+  #[command(description = "follow a torrent's progress live: /watch <hash>")]
+  Watch(String),
+  #[command(description = "compare two releases side by side: /compare <hash1> <hash2>")]
+  Compare(String),
+  #[command(description = "upload the files of a finished torrent here: /send <hash>")]
+  Send(String),
+  #[command(description = "fetch subtitles for a torrent's videos: /subtitles <hash>")]
+  Subtitles(String),
+  #[command(description = "delete a torrent and its files: /deletedata [hash]")]
+  DeleteData(String),
+  #[command(description = "preview what the auto-removal policies would delete")]
+  Policies,
+  #[command(description = "act on finished torrents per category, see /oncomplete (admins)")]
+  OnComplete(String),
+  #[command(
+    description = "list torrents: /list [downloading|seeding|...] [sort:size], /list grouped"
+  )]
+  List(String),
+  #[command(description = "torrents grouped by category, with counts and sizes")]
+  ByCategory,
+  #[command(description = "torrents grouped by tag, with counts and sizes")]
+  ByTag,
+  #[command(description = "show and reorder the download queue")]
+  Queue,
+  #[command(description = "list a torrent's files page by page: /files [hash]")]
+  Files(String),
+  #[command(description = "torrents added lately: /recent [24h|7d]")]
+  Recent(String),
+  #[command(description = "search torrents by name: /find <text>")]
+  Find(String),
+  #[command(description = "search the configured indexers for new torrents: /search <text>")]
+  Search(String),
+  #[command(description = "hand a magnet or /search result to Sonarr: /sonarr <magnet|number>")]
+  Sonarr(String),
+  #[command(description = "hand a magnet or /search result to Radarr: /radarr <magnet|number>")]
+  Radarr(String),
+  #[command(description = "show the heaviest torrents: /top speed|size|ratio|peers")]
+  Top(String),
+  #[command(description = "show the global ratio, per category and the best and worst torrents")]
+  Ratio,
+  #[command(description = "show global transfer statistics")]
+  Transfer,
+  #[command(description = "choose which notifications you receive")]
+  Notify,
+  #[command(description = "show who did what recently")]
+  History,
+  #[command(description = "change your personal settings, /settings timezone <zone>")]
+  Settings(String),
+  #[command(description = "choose the language I answer in")]
+  Language,
+  #[command(description = "switch bot features on or off (admins)")]
+  Features,
+  #[command(description = "restrict what this chat can see and add: /parental on|off (admins)")]
+  Parental(String),
+  #[command(description = "lift the chat restriction for a while: /unlock <pin>")]
+  Unlock(String),
+  #[command(description = "invite someone read-only: /guest <hours>, alone lists guests (admins)")]
+  Guest(String),
+  #[command(description = "turn maintenance mode on or off (admins)")]
+  Maintenance(String),
+  #[command(description = "limit or pause torrents nightly: /quiethours 23:00-07:00 (admins)")]
+  QuietHours(String),
+  #[command(description = "delete finished torrents, /cleanup now prunes records (admins)")]
+  Cleanup(String),
+  #[command(description = "shut qBittorrent down and start it again (admins)")]
+  QbRestart,
+  #[command(description = "check qBittorrent and the bot's health")]
+  Status,
   #[command(description = "cancel the purchase procedure.")]
   Cancel,
 }
 
+impl Command {
+  /// Commands that still work while qBittorrent is unreachable.
+  fn needs_qbittorrent(&self) -> bool {
+    !matches!(
+      self,
+      Command::Help
+        | Command::Notify
+        | Command::History
+        | Command::Settings(_)
+        | Command::Template(_)
+        // only stored until it's due, the scheduler adds it once qBittorrent is back
+        | Command::AddLater(_)
+        | Command::Language
+        | Command::Features
+        | Command::Parental(_)
+        | Command::Unlock(_)
+        | Command::Guest(_)
+        | Command::Maintenance(_)
+        // the release goes to Sonarr/Radarr, which queue it even while qBittorrent is down
+        | Command::Sonarr(_)
+        | Command::Radarr(_)
+        // restarting is how an admin gets qBittorrent back
+        | Command::QbRestart
+        | Command::Status
+        | Command::Cancel
+    )
+  }
+
+  /// Commands guests may use, they look at torrents but don't change them.
+  fn is_read_only(&self) -> bool {
+    match self {
+      // a payload is a magnet to add
+      Command::Start(payload) => payload.trim().is_empty(),
+      Command::Help
+      | Command::List(_)
+      | Command::ByCategory
+      | Command::ByTag
+      | Command::Files(_)
+      | Command::Recent(_)
+      | Command::Find(_)
+      | Command::Top(_)
+      | Command::Ratio
+      | Command::Transfer
+      | Command::Watch(_)
+      | Command::Send(_)
+      | Command::Language
+      | Command::Status
+      | Command::Cancel => true,
+      _ => false,
+    }
+  }
+}
+
 #[tokio::main]
 async fn main() {
+  pretty_env_logger::init();
+  status::mark_started();
   let bot = Bot::from_env();
 
+  let config = match Config::load() {
+    Ok(config) => Arc::new(config),
+    Err(err) => {
+      log::error!("{err}");
+      std::process::exit(1);
+    }
+  };
+  alert::spawn(bot.clone(), config.admin_chat_id);
+  let db = Db::open().expect("could not open the database");
+  callback_data::init(db.clone());
+  let features = Features::load(db.clone());
+  let parental = Parental::load(config.parental.clone(), db.clone());
+  let access = auth::Access::from_env(&db);
+
   // initialize client with given username and password
-  let client = torrent::TorrentApi::new();
+  let client = match torrent::TorrentApi::new() {
+    Ok(client) => client,
+    Err(err) => {
+      log::error!("Invalid qBittorrent connection settings: {err}");
+      std::process::exit(1);
+    }
+  };
 
   // login first
   let _ = client.login().await;
+  match client.ping().await {
+    Ok(version) => log::info!("Connected to qBittorrent {version}"),
+    Err(err) => log::error!("qBittorrent is not reachable: {err}"),
+  }
+
+  let connection = supervisor::Connection::default();
+  supervisor::spawn(client.clone(), connection.clone());
+  let events = TorrentEvents::spawn(client.clone(), config.poll_interval());
+  let quotas = Quotas::new(db.clone(), config.clone(), features.clone(), &events);
+  quotas.spawn(bot.clone(), client.clone(), events.clone());
+  let tmdb = metadata::Tmdb::new(config.tmdb_api_key.clone());
+  let indexers = search::SearchProviders::from_config(&config);
+  let notifier =
+    notify::Notifiers::from_config(bot.clone(), config.clone(), db.clone(), features.clone());
+  notify::spawn_completion_notifier(
+    notifier.clone(),
+    config.clone(),
+    events.clone(),
+    tmdb.clone(),
+  );
+  notify::spawn_problem_watcher(notifier.clone(), config.clone(), events.clone());
+  recovery::spawn(
+    client.clone(),
+    notifier.clone(),
+    config.clone(),
+    features.clone(),
+    events.clone(),
+  );
+  let dry_runs = digest::DryRuns::default();
+  if config.dry_run && config.digest.time.is_none() {
+    log::warn!("dry_run is on without a digest, what would be deleted is only logged");
+  }
+  digest::spawn(notifier, &config.digest, events.clone(), dry_runs.clone());
+  cleanup::spawn(
+    bot.clone(),
+    db.clone(),
+    config.clone(),
+    events.clone(),
+    dry_runs.clone(),
+  );
+  send::spawn_auto_send(
+    bot.clone(),
+    client.clone(),
+    config.clone(),
+    db.clone(),
+    features.clone(),
+    events.clone(),
+  );
+  completion::spawn(
+    bot.clone(),
+    client.clone(),
+    db.clone(),
+    features.clone(),
+    events.clone(),
+  );
+  watch_folder::spawn(
+    bot.clone(),
+    client.clone(),
+    config.clone(),
+    db.clone(),
+    features.clone(),
+  );
+  policy::spawn(
+    client.clone(),
+    config.clone(),
+    features.clone(),
+    events.clone(),
+    dry_runs,
+  );
+  guard::spawn(
+    bot.clone(),
+    client.clone(),
+    config.clone(),
+    db.clone(),
+    parental.clone(),
+    events.clone(),
+  );
+  webhook::spawn(config.webhooks.clone(), features.clone(), events.clone());
+  quiet_hours::spawn(client.clone(), db.clone(), events.clone());
+  schedule::spawn(
+    bot.clone(),
+    client.clone(),
+    config.clone(),
+    db.clone(),
+    quotas.clone(),
+  );
+  milestones::spawn(bot.clone(), db.clone(), features.clone(), events.clone());
+  announce::spawn(
+    bot.clone(),
+    config.announce.clone(),
+    features.clone(),
+    parental.clone(),
+    events.clone(),
+  );
+  if let Some(mqtt) = config.mqtt.clone() {
+    #[cfg(feature = "mqtt")]
+    mqtt::spawn(mqtt, client.clone(), events.clone());
+    #[cfg(not(feature = "mqtt"))]
+    log::warn!(
+      "MQTT is configured for {}, but the bot was built without the mqtt feature",
+      mqtt.host
+    );
+  }
 
   println!("The bot is now started...");
 
   Dispatcher::builder(bot, schema())
-    .dependencies(dptree::deps![InMemStorage::<State>::new(), client])
+    .dependencies(dptree::deps![
+      InMemStorage::<State>::new(),
+      client,
+      connection,
+      config,
+      events,
+      db,
+      live::LiveViews::default(),
+      media_group::MediaGroups::default(),
+      undo::Undos::default(),
+      access,
+      quotas,
+      features,
+      parental,
+      tmdb,
+      indexers
+    ])
+    .error_handler(Arc::new(
+      |err: Box<dyn std::error::Error + Send + Sync>| async move {
+        log::error!("Error from a handler: {err}");
+        status::record_error(err);
+      },
+    ))
     .enable_ctrlc_handler()
     .build()
     .dispatch()
@@ -56,81 +433,616 @@ async fn main() {
 fn schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
   use dptree::case;
 
-  let command_handler = teloxide::filter_command::<Command, _>()
+  let command_handler = dptree::filter_map(parse_command)
+    .branch(
+      dptree::filter(|cmd: Command, connection: supervisor::Connection| {
+        cmd.needs_qbittorrent() && !connection.is_online()
+      })
+      .endpoint(supervisor::offline),
+    )
     .branch(
       case![State::Start]
-        .branch(case![Command::Help].endpoint(help))
-        // .branch(case![Command::Start].endpoint(start))
-        .branch(case![Command::Magnet].endpoint(get_magnet)),
+        .branch(case![Command::Help].endpoint(help::help))
+        .branch(case![Command::Start(payload)].endpoint(start))
+        .branch(case![Command::Magnet].endpoint(get_magnet))
+        .branch(case![Command::Sequential].endpoint(get_sequential))
+        .branch(case![Command::AddWithCookie].endpoint(get_cookie))
+        .branch(case![Command::Template(args)].endpoint(template::template))
+        .branch(case![Command::AddLater(args)].endpoint(schedule::addlater))
+        .branch(case![Command::Recategorize(args)].endpoint(recategorize::recategorize))
+        .branch(case![Command::Watch(hash)].endpoint(watch::watch))
+        .branch(case![Command::Compare(args)].endpoint(compare::compare))
+        .branch(case![Command::Send(hash)].endpoint(send::send))
+        .branch(case![Command::Subtitles(hash)].endpoint(subtitles::subtitles))
+        .branch(case![Command::DeleteData(hash)].endpoint(delete::deletedata))
+        .branch(case![Command::Policies].endpoint(policy::policies))
+        .branch(case![Command::OnComplete(args)].endpoint(completion::oncomplete))
+        .branch(case![Command::List(arg)].endpoint(list::list))
+        .branch(case![Command::ByCategory].endpoint(labels::bycategory))
+        .branch(case![Command::ByTag].endpoint(labels::bytag))
+        .branch(case![Command::Queue].endpoint(queue::queue))
+        .branch(case![Command::Files(hash)].endpoint(files::files))
+        .branch(case![Command::Find(query)].endpoint(find::find))
+        .branch(case![Command::Search(query)].endpoint(search::search))
+        .branch(case![Command::Sonarr(text)].endpoint(arr::sonarr))
+        .branch(case![Command::Radarr(text)].endpoint(arr::radarr))
+        .branch(case![Command::Recent(arg)].endpoint(recent::recent))
+        .branch(case![Command::Top(metric)].endpoint(top::top))
+        .branch(case![Command::Ratio].endpoint(ratio::ratio))
+        .branch(case![Command::Transfer].endpoint(transfer::transfer))
+        .branch(case![Command::Notify].endpoint(notify::notify))
+        .branch(case![Command::History].endpoint(audit::history))
+        .branch(case![Command::Settings(args)].endpoint(settings::settings))
+        .branch(case![Command::Language].endpoint(i18n::language))
+        .branch(case![Command::Features].endpoint(features::features))
+        .branch(case![Command::Parental(arg)].endpoint(parental::parental))
+        .branch(case![Command::Unlock(pin)].endpoint(parental::unlock))
+        .branch(case![Command::Guest(arg)].endpoint(guest::guest))
+        .branch(case![Command::Maintenance(arg)].endpoint(maintenance::maintenance))
+        .branch(case![Command::QuietHours(args)].endpoint(quiet_hours::quiethours))
+        .branch(case![Command::Cleanup(arg)].endpoint(cleanup::cleanup))
+        .branch(case![Command::QbRestart].endpoint(restart::qbrestart))
+        .branch(case![Command::Status].endpoint(status::status)),
     )
     .branch(case![Command::Cancel].endpoint(cancel));
 
   let message_handler = Update::filter_message()
     .branch(command_handler)
     .branch(case![State::GetMagnet].endpoint(magnet))
+    .branch(case![State::GetSequential].endpoint(sequential))
+    .branch(case![State::GetCookie].endpoint(cookie))
+    .branch(case![State::GetCookieUrl { cookie }].endpoint(cookie_url))
+    .branch(case![State::ConfirmDeleteData { hash, name }].endpoint(delete::confirm))
+    .branch(case![State::GetSpeedLimit { hash, upload }].endpoint(speed_limit::custom))
+    .branch(
+      case![State::Start]
+        .filter(reply::is_action)
+        .endpoint(reply::act),
+    )
+    .branch(
+      case![State::Start]
+        .filter(media_group::is_torrent_album)
+        .endpoint(media_group::collect),
+    )
+    .branch(
+      case![State::Start]
+        .filter(detect::has_torrent)
+        .endpoint(detect::offer),
+    )
     .branch(dptree::endpoint(invalid_state));
 
-  dialogue::enter::<Update, InMemStorage<State>, State, _>().branch(message_handler)
+  let callback_handler = Update::filter_callback_query()
+    .map(callback_data::expand)
+    .branch(callback_prefix("profile:").endpoint(profile::callback))
+    .branch(callback_prefix("notify:").endpoint(notify::callback))
+    .branch(callback_prefix("live:").endpoint(watch::callback))
+    .branch(callback_prefix("find:").endpoint(find::callback))
+    .branch(callback_prefix("search:").endpoint(search::callback))
+    .branch(callback_prefix("queue:").endpoint(queue::callback))
+    .branch(callback_prefix("fl:").endpoint(files::callback))
+    .branch(callback_prefix("select:").endpoint(select::callback))
+    .branch(callback_prefix("list:").endpoint(list::callback))
+    .branch(callback_prefix("transfer:").endpoint(transfer::callback))
+    .branch(callback_prefix("history:").endpoint(audit::callback))
+    .branch(callback_prefix("help:").endpoint(help::callback))
+    .branch(callback_prefix("add:").endpoint(detect::callback))
+    .branch(callback_prefix("dup:").endpoint(duplicates::callback))
+    .branch(callback_prefix("guard:").endpoint(guard::callback))
+    .branch(callback_prefix("tpl:").endpoint(template::callback))
+    .branch(callback_prefix("later:").endpoint(schedule::callback))
+    .branch(callback_prefix("autosend:").endpoint(send::callback))
+    .branch(callback_prefix("milestones:").endpoint(milestones::callback))
+    .branch(callback_prefix("limit:").endpoint(speed_limit::callback))
+    .branch(callback_prefix("cl:").endpoint(cleanup::callback))
+    .branch(callback_prefix("cmp:").endpoint(compare::callback))
+    .branch(callback_prefix("deldata:").endpoint(delete::callback))
+    .branch(callback_prefix("undo:").endpoint(undo::callback))
+    .branch(callback_prefix("settings:").endpoint(settings::callback))
+    .branch(callback_prefix("lang:").endpoint(i18n::callback))
+    .branch(callback_prefix("feature:").endpoint(features::callback))
+    .branch(callback_prefix("guest:").endpoint(guest::callback))
+    .branch(callback_prefix(callback_data::TOKEN_PREFIX).endpoint(callback_data::expired))
+    .branch(dptree::endpoint(picker::callback));
+
+  // inline queries have no chat, so they can't take part in the dialogue
+  let inline_handler = Update::filter_inline_query().endpoint(inline::inline_query);
+
+  // checked before access, the link is how a guest gets in
+  let invite_handler = Update::filter_message()
+    .filter_map(guest::invite)
+    .endpoint(guest::redeem);
+
+  let deny_handler =
+    dptree::filter(|upd: Update, access: auth::Access| !access.allows(&upd)).endpoint(auth::deny);
+
+  let maintenance_handler =
+    dptree::filter(|upd: Update, access: auth::Access, features: Features| {
+      features.in_maintenance() && !upd.user().is_some_and(|user| access.is_admin(user.id))
+    })
+    .endpoint(maintenance::busy);
+
+  let guest_handler = dptree::filter(
+    |upd: Update, access: auth::Access, me: Me, config: Arc<Config>| {
+      access.is_read_only(&upd) && !guest::may(&upd, me, config)
+    },
+  )
+  .endpoint(guest::read_only);
+
+  dptree::entry()
+    .branch(invite_handler)
+    .branch(deny_handler)
+    .branch(maintenance_handler)
+    .branch(guest_handler)
+    .branch(inline_handler)
+    .branch(
+      dialogue::enter::<Update, InMemStorage<State>, State, _>()
+        .branch(message_handler)
+        .branch(callback_handler),
+    )
 }
 
-// async fn start(bot: Bot, msg: Message) -> HandlerResult {
-//   bot.send_message(msg.chat.id, "Let's start!").await?;
-//   Ok(())
-// }
+/// Like `teloxide::filter_command`, with configured aliases resolved first.
+fn parse_command(msg: Message, me: Me, config: Arc<Config>) -> Option<Command> {
+  let text = alias::resolve(msg.text()?, &config.aliases);
+  Command::parse(&text, me.username()).ok()
+}
 
-async fn help(bot: Bot, msg: Message) -> HandlerResult {
-  bot
-    .send_message(msg.chat.id, Command::descriptions().to_string())
-    .await?;
-  Ok(())
+fn callback_prefix(
+  prefix: &'static str,
+) -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+  dptree::filter(move |q: CallbackQuery| {
+    q.data
+      .as_deref()
+      .is_some_and(|data| data.starts_with(prefix))
+  })
+}
+
+/// `/start` greets, `t.me/<bot>?start=<payload>` links hand over a magnet or info hash to add.
+#[allow(clippy::too_many_arguments)]
+async fn start(
+  bot: Bot,
+  msg: Message,
+  payload: String,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+  tmdb: Tmdb,
+) -> HandlerResult {
+  if payload.trim().is_empty() {
+    return help::help(bot, msg, config, db).await;
+  }
+  let Some(magnet) = deeplink::decode(&payload) else {
+    let lang = i18n::lang(&db, msg.from());
+    bot
+      .send_message(msg.chat.id, lang.t("start.invalid"))
+      .await?;
+    return Ok(());
+  };
+  add_magnet(
+    bot, msg, magnet, torrent, config, quotas, parental, db, tmdb,
+  )
+  .await
 }
 
-async fn cancel(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
+async fn cancel(bot: Bot, dialogue: MyDialogue, msg: Message, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
   bot
-    .send_message(msg.chat.id, "Cancelling the dialogue.")
+    .send_message(msg.chat.id, lang.t("dialogue.cancel"))
     .await?;
   dialogue.exit().await?;
   Ok(())
 }
 
-async fn get_magnet(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
-  bot
-    .send_message(msg.chat.id, "Send me the magnet link")
-    .await?;
+async fn get_magnet(bot: Bot, dialogue: MyDialogue, msg: Message, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  bot.send_message(msg.chat.id, lang.t("magnet.ask")).await?;
   dialogue.update(State::GetMagnet).await?;
   Ok(())
 }
 
-async fn magnet(bot: Bot, msg: Message, torrent: TorrentApi) -> HandlerResult {
+#[allow(clippy::too_many_arguments)]
+async fn magnet(
+  bot: Bot,
+  msg: Message,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+  tmdb: Tmdb,
+) -> HandlerResult {
   match msg.text().map(ToOwned::to_owned) {
-    Some(text) => {
-      let urls: [String; 1] = [text];
-      match torrent.client.torrents_add_by_url(&urls).await {
-        Ok(_) => {
-          bot
-            .send_message(msg.chat.id, "Torrent has been added to download queue")
-            .await?;
+    Some(text) => add_magnet(bot, msg, text, torrent, config, quotas, parental, db, tmdb).await,
+    None => {
+      let lang = i18n::lang(&db, msg.from());
+      bot
+        .send_message(msg.chat.id, lang.t("magnet.missing"))
+        .await?;
+      Ok(())
+    }
+  }
+}
+
+/// Adds `text`, a magnet or bare info hash, after the parental, quota and add policy checks,
+/// then offers the follow-up actions.
+#[allow(clippy::too_many_arguments)]
+async fn add_magnet(
+  bot: Bot,
+  msg: Message,
+  text: String,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+  tmdb: Tmdb,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let text = torrent::hash_magnet(&text).unwrap_or(text);
+  let name = parental::magnet_name(&text);
+  if blocked(&bot, &msg, &parental, lang, name.clone()).await? {
+    return Ok(());
+  }
+  let candidate = guard::Candidate::from_link(&text);
+  if !within_quota(&bot, &msg, &quotas, lang, candidate.size).await? {
+    return Ok(());
+  }
+  if let Some(rejection) = guard::check(&torrent, &config.guard, &candidate).await? {
+    return guard::reject(&bot, msg.chat.id, &rejection, Some(&text), lang).await;
+  }
+  let hash = torrent::extract_info_hash(&text);
+  let size = candidate.size;
+  if let Some(duplicate) =
+    duplicates::check_duplicates(&torrent, hash.as_deref(), name.as_deref(), size).await?
+  {
+    return duplicates::warn(&bot, msg.chat.id, &duplicate, &text, lang).await;
+  }
+  if let Some(user) = msg.from() {
+    let templates = db.templates(user.id.0)?;
+    if let Some(keyboard) = template::keyboard(&templates, &text, lang) {
+      bot
+        .send_message(msg.chat.id, lang.t("template.pick"))
+        .reply_markup(keyboard)
+        .await?;
+      return Ok(());
+    }
+  }
+  let arg = AddTorrentArg {
+    urls: vec![text],
+    ..Default::default()
+  };
+  match torrent.add_torrent(&arg).await {
+    Ok(()) => {
+      record_add(&msg, &quotas, hash.as_deref(), size);
+      if let Some(hash) = &hash {
+        guard::recheck(hash, msg.chat.id);
+      }
+      if let Some(user) = msg.from() {
+        audit::record(&db, user, "added a magnet", hash.as_deref());
+      }
+      let reply = bot.send_message(msg.chat.id, lang.t("torrent.added"));
+      match hash {
+        Some(hash) => {
+          reply
+            .reply_markup(add_keyboard(&hash, &config, lang))
+            .await?
         }
-        Err(err) => {
-          bot.send_message(msg.chat.id, err.to_string()).await?;
+        None => reply.await?,
+      };
+      if let Some(name) = name {
+        if let Some(found) = tmdb.lookup(&name).await {
+          metadata::send(&bot, msg.chat.id, &found).await?;
         }
       }
     }
+    Err(err) => {
+      bot.send_message(msg.chat.id, err.to_string()).await?;
+    }
+  }
+  Ok(())
+}
+
+/// Tells the user and returns `false` when `size` more bytes (or any, if unknown) don't fit in
+/// their quotas for today/this month.
+async fn within_quota(
+  bot: &Bot,
+  msg: &Message,
+  quotas: &Quotas,
+  lang: i18n::Lang,
+  size: Option<u64>,
+) -> Result<bool, teloxide::RequestError> {
+  let Some(user) = msg.from() else {
+    return Ok(true);
+  };
+  match quotas.check(user.id.0, size, lang) {
+    Ok(()) => Ok(true),
+    Err(reason) => {
+      bot.send_message(msg.chat.id, reason).await?;
+      Ok(false)
+    }
+  }
+}
+
+/// Tells the user and returns `true` when the chat's restricted profile denies `name`.
+async fn blocked(
+  bot: &Bot,
+  msg: &Message,
+  parental: &Parental,
+  lang: i18n::Lang,
+  name: Option<String>,
+) -> Result<bool, teloxide::RequestError> {
+  match name {
+    Some(name) if parental.blocks(msg.chat.id, &name) => {
+      bot
+        .send_message(msg.chat.id, lang.t("parental.blocked"))
+        .await?;
+      Ok(true)
+    }
+    _ => Ok(false),
+  }
+}
+
+fn record_add(msg: &Message, quotas: &Quotas, hash: Option<&str>, size: Option<u64>) {
+  if let (Some(user), Some(hash)) = (msg.from(), hash) {
+    quotas.record(user.id.0, hash, size);
+  }
+}
+
+/// Follow-up actions offered once a torrent has been added.
+fn add_keyboard(hash: &str, config: &Config, lang: i18n::Lang) -> InlineKeyboardMarkup {
+  let mut rows = picker::select_files_button(hash, lang).inline_keyboard;
+  rows.push(vec![
+    send::auto_send_button(hash, lang),
+    milestones::button(hash, lang),
+  ]);
+  rows.push(vec![speed_limit::button(hash, lang)]);
+  rows.extend(profile::buttons(hash, &config.profiles));
+  InlineKeyboardMarkup::new(rows)
+}
+
+async fn get_sequential(bot: Bot, dialogue: MyDialogue, msg: Message, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  bot
+    .send_message(msg.chat.id, lang.t("sequential.ask"))
+    .await?;
+  dialogue.update(State::GetSequential).await?;
+  Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn sequential(
+  bot: Bot,
+  dialogue: MyDialogue,
+  msg: Message,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(text) = msg.text().map(str::trim) else {
+    bot
+      .send_message(msg.chat.id, lang.t("sequential.missing"))
+      .await?;
+    return Ok(());
+  };
+
+  let hash = torrent::normalize_info_hash(text).or_else(|| torrent::extract_info_hash(text));
+  let existing = match &hash {
+    Some(hash) => torrent.info(hash).await?.map(|info| info.hash),
+    None => None,
+  };
+
+  let result = match existing {
+    // already known to qBittorrent, so only make sure the flags are on
+    Some(hash) => {
+      let res = torrent.set_sequential_download(&hash, true).await;
+      if let (Ok(()), Some(user)) = (&res, msg.from()) {
+        audit::record(&db, user, "enabled sequential download", Some(&hash));
+      }
+      res.map(|_| lang.t("sequential.enabled"))
+    }
     None => {
+      // a hash qBittorrent doesn't know yet is added like a magnet
+      let link = torrent::hash_magnet(text).unwrap_or_else(|| text.to_string());
+      if blocked(&bot, &msg, &parental, lang, parental::magnet_name(&link)).await? {
+        return Ok(());
+      }
+      let candidate = guard::Candidate::from_link(&link);
+      if !within_quota(&bot, &msg, &quotas, lang, candidate.size).await? {
+        return Ok(());
+      }
+      if let Some(rejection) = guard::check(&torrent, &config.guard, &candidate).await? {
+        // the override adds it without sequential mode, which /sequential on the hash turns on
+        dialogue.exit().await?;
+        return guard::reject(&bot, msg.chat.id, &rejection, Some(&link), lang).await;
+      }
+      let arg = AddTorrentArg {
+        urls: vec![link],
+        sequential_download: true,
+        first_last_piece_prio: true,
+        ..Default::default()
+      };
+      let res = torrent.add_torrent(&arg).await;
+      if res.is_ok() {
+        record_add(&msg, &quotas, hash.as_deref(), candidate.size);
+        if let Some(hash) = &hash {
+          guard::recheck(hash, msg.chat.id);
+        }
+        if let Some(user) = msg.from() {
+          audit::record(
+            &db,
+            user,
+            "added a torrent in sequential mode",
+            hash.as_deref(),
+          );
+        }
+      }
+      res.map(|_| lang.t("sequential.added"))
+    }
+  };
+
+  match result {
+    Ok(reply) => {
+      bot.send_message(msg.chat.id, reply).await?;
+      dialogue.exit().await?;
+    }
+    Err(err) => {
+      bot.send_message(msg.chat.id, err.to_string()).await?;
+    }
+  }
+  Ok(())
+}
+
+async fn get_cookie(bot: Bot, dialogue: MyDialogue, msg: Message, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  bot.send_message(msg.chat.id, lang.t("cookie.ask")).await?;
+  dialogue.update(State::GetCookie).await?;
+  Ok(())
+}
+
+async fn cookie(bot: Bot, dialogue: MyDialogue, msg: Message, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(cookie) = msg.text().map(str::trim) else {
+    bot
+      .send_message(msg.chat.id, lang.t("cookie.missing"))
+      .await?;
+    return Ok(());
+  };
+  dialogue
+    .update(State::GetCookieUrl {
+      cookie: cookie.to_string(),
+    })
+    .await?;
+
+  // the cookie is a tracker credential, don't leave it lying around in the chat
+  let _ = bot.delete_message(msg.chat.id, msg.id).await;
+  bot
+    .send_message(msg.chat.id, lang.t("cookie.ask_url"))
+    .await?;
+  Ok(())
+}
+
+/// Downloads a .torrent with the tracker's login cookie, so it's checked like an uploaded file
+/// before qBittorrent gets it.
+async fn fetch_torrent(url: &str, cookie: &str) -> Result<Vec<u8>, String> {
+  let http = reqwest::Client::builder()
+    .timeout(FETCH_TIMEOUT)
+    .build()
+    .map_err(|err| err.to_string())?;
+  let res = http
+    .get(url)
+    .header(reqwest::header::COOKIE, cookie)
+    .send()
+    .await
+    .and_then(reqwest::Response::error_for_status)
+    .map_err(|err| err.to_string())?;
+  if res
+    .content_length()
+    .is_some_and(|len| len > MAX_TORRENT_FILE as u64)
+  {
+    return Err(format!(
+      "larger than {}",
+      format_bytes(MAX_TORRENT_FILE as u64)
+    ));
+  }
+  let bytes = res.bytes().await.map_err(|err| err.to_string())?;
+  if bytes.len() > MAX_TORRENT_FILE {
+    return Err(format!(
+      "larger than {}",
+      format_bytes(MAX_TORRENT_FILE as u64)
+    ));
+  }
+  Ok(bytes.to_vec())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn cookie_url(
+  bot: Bot,
+  dialogue: MyDialogue,
+  cookie: String,
+  msg: Message,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(url) = msg.text().map(str::trim) else {
+    bot
+      .send_message(msg.chat.id, lang.t("cookie.missing_url"))
+      .await?;
+    return Ok(());
+  };
+
+  if !within_quota(&bot, &msg, &quotas, lang, None).await? {
+    return Ok(());
+  }
+  let contents = match fetch_torrent(url, &cookie).await {
+    Ok(contents) => contents,
+    Err(error) => {
+      let text = lang.tf("cookie.fetch_failed", &[("error", &error)]);
+      bot.send_message(msg.chat.id, text).await?;
+      return Ok(());
+    }
+  };
+  let Some(metainfo) = torrent::metainfo::parse(&contents) else {
+    bot
+      .send_message(msg.chat.id, lang.t("cookie.not_torrent"))
+      .await?;
+    return Ok(());
+  };
+  if blocked(&bot, &msg, &parental, lang, Some(metainfo.name.clone())).await? {
+    dialogue.exit().await?;
+    return Ok(());
+  }
+  if !within_quota(&bot, &msg, &quotas, lang, Some(metainfo.size)).await? {
+    dialogue.exit().await?;
+    return Ok(());
+  }
+  let candidate = guard::Candidate::from_metainfo(&metainfo);
+  if let Some(rejection) = guard::check(&torrent, &config.guard, &candidate).await? {
+    // the cookie can't ride along in a button, so there's no override
+    dialogue.exit().await?;
+    return guard::reject(&bot, msg.chat.id, &rejection, None, lang).await;
+  }
+
+  let arg = AddTorrentArg {
+    files: vec![(format!("{}.torrent", metainfo.name), contents)],
+    ..Default::default()
+  };
+  match torrent.add_torrent(&arg).await {
+    Ok(()) => {
+      record_add(&msg, &quotas, Some(&metainfo.hash), Some(metainfo.size));
+      if let Some(user) = msg.from() {
+        audit::record(
+          &db,
+          user,
+          "added a torrent URL with a cookie",
+          Some(&metainfo.hash),
+        );
+      }
       bot
-        .send_message(msg.chat.id, "Please, send me your magnet link.")
+        .send_message(msg.chat.id, lang.t("torrent.added"))
+        .reply_markup(add_keyboard(&metainfo.hash, &config, lang))
         .await?;
+      dialogue.exit().await?;
+    }
+    Err(err) => {
+      bot.send_message(msg.chat.id, err.to_string()).await?;
     }
   }
   Ok(())
 }
 
-async fn invalid_state(bot: Bot, msg: Message) -> HandlerResult {
+async fn invalid_state(bot: Bot, msg: Message, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
   bot
-    .send_message(
-      msg.chat.id,
-      "Unable to handle the message. Type /help to see the usage.",
-    )
+    .send_message(msg.chat.id, lang.t("dialogue.invalid"))
     .await?;
   Ok(())
 }
@@ -0,0 +1,71 @@
+use teloxide::{
+  prelude::*,
+  types::{InlineQueryResult, UpdateKind},
+};
+
+use crate::{audit, auth::Access, db::Db, features::Features, i18n, HandlerResult};
+
+/// `/maintenance on|off`, without an argument it tells whether maintenance mode is on.
+pub async fn maintenance(
+  bot: Bot,
+  msg: Message,
+  arg: String,
+  access: Access,
+  features: Features,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(user) = msg.from().filter(|user| access.is_admin(user.id)) else {
+    bot.send_message(msg.chat.id, lang.t("admin.only")).await?;
+    return Ok(());
+  };
+
+  let on = match arg.trim().to_lowercase().as_str() {
+    "on" => true,
+    "off" => false,
+    _ => {
+      let key = if features.in_maintenance() {
+        "maintenance.is_on"
+      } else {
+        "maintenance.is_off"
+      };
+      bot.send_message(msg.chat.id, lang.t(key)).await?;
+      return Ok(());
+    }
+  };
+  features.set_maintenance(on)?;
+  let action = if on {
+    "turned maintenance mode on"
+  } else {
+    "turned maintenance mode off"
+  };
+  audit::record(&db, user, action, None);
+
+  let key = if on {
+    "maintenance.started"
+  } else {
+    "maintenance.ended"
+  };
+  bot.send_message(msg.chat.id, lang.t(key)).await?;
+  Ok(())
+}
+
+/// Answers everyone but admins while maintenance mode is on.
+pub async fn busy(bot: Bot, upd: Update, db: Db) -> HandlerResult {
+  let busy = i18n::lang(&db, upd.user()).t("maintenance.busy");
+  match upd.kind {
+    UpdateKind::Message(msg) => {
+      bot.send_message(msg.chat.id, busy).await?;
+    }
+    UpdateKind::CallbackQuery(q) => {
+      bot.answer_callback_query(q.id).text(busy).await?;
+    }
+    UpdateKind::InlineQuery(q) => {
+      bot
+        .answer_inline_query(q.id, Vec::<InlineQueryResult>::new())
+        .await?;
+    }
+    _ => {}
+  }
+  Ok(())
+}
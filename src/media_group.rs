@@ -0,0 +1,207 @@
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+use teloxide::{net::Download, prelude::*};
+
+use crate::{
+  audit,
+  config::Config,
+  db::Db,
+  detect,
+  guard::{self, Candidate},
+  i18n,
+  parental::Parental,
+  quota::Quotas,
+  torrent::{metainfo, AddTorrentArg, TorrentApi},
+  HandlerResult,
+};
+
+// Telegram delivers an album as separate messages in quick succession
+const COLLECT_FOR: Duration = Duration::from_secs(2);
+
+/// .torrent files of albums that are still arriving, by media group id.
+#[derive(Clone, Default)]
+pub struct MediaGroups {
+  pending: Arc<Mutex<HashMap<String, Vec<Message>>>>,
+}
+
+impl MediaGroups {
+  /// Returns `true` for the first message of a group, whose handler then collects the rest.
+  fn push(&self, group: &str, msg: Message) -> bool {
+    let mut pending = self.lock();
+    let messages = pending.entry(group.to_string()).or_default();
+    messages.push(msg);
+    messages.len() == 1
+  }
+
+  fn take(&self, group: &str) -> Vec<Message> {
+    self.lock().remove(group).unwrap_or_default()
+  }
+
+  fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Vec<Message>>> {
+    self
+      .pending
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+pub fn is_torrent_album(msg: Message) -> bool {
+  msg.media_group_id().is_some() && detect::torrent_file(&msg).is_some()
+}
+
+/// Adds every .torrent file of an album at once and answers with a single summary.
+#[allow(clippy::too_many_arguments)]
+pub async fn collect(
+  bot: Bot,
+  msg: Message,
+  groups: MediaGroups,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let Some(group) = msg.media_group_id().map(str::to_string) else {
+    return Ok(());
+  };
+  if !groups.push(&group, msg.clone()) {
+    return Ok(());
+  }
+  // updates from one chat are handled in order, so wait for the rest outside the handler
+  tokio::spawn(async move {
+    tokio::time::sleep(COLLECT_FOR).await;
+    let messages = groups.take(&group);
+    let res = add_album(
+      &bot, &msg, &messages, &torrent, &config, &quotas, &parental, &db,
+    )
+    .await;
+    if let Err(err) = res {
+      log::error!("Could not add album {group}: {err}");
+    }
+  });
+  Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn add_album(
+  bot: &Bot,
+  msg: &Message,
+  messages: &[Message],
+  torrent: &TorrentApi,
+  config: &Config,
+  quotas: &Quotas,
+  parental: &Parental,
+  db: &Db,
+) -> HandlerResult {
+  let lang = i18n::lang(db, msg.from());
+  if let Some(user) = msg.from() {
+    if let Err(reason) = quotas.check(user.id.0, None, lang) {
+      bot.send_message(msg.chat.id, reason).await?;
+      return Ok(());
+    }
+  }
+
+  let mut files = Vec::new();
+  let mut failed = Vec::new();
+  let mut blocked = Vec::new();
+  let mut rejected = Vec::new();
+  // hash and size of each file that goes in, charged together against the quotas
+  let mut charged: Vec<(String, u64)> = Vec::new();
+  for (id, name) in messages.iter().filter_map(detect::torrent_file) {
+    if parental.blocks(msg.chat.id, &name) {
+      blocked.push(name);
+      continue;
+    }
+    let mut contents = Vec::new();
+    let downloaded = match bot.get_file(id).await {
+      Ok(file) => bot.download_file(&file.path, &mut contents).await.is_ok(),
+      Err(_) => false,
+    };
+    if !downloaded {
+      failed.push(name);
+      continue;
+    }
+    let metainfo = metainfo::parse(&contents);
+    let candidate = match &metainfo {
+      Some(metainfo) if parental.blocks(msg.chat.id, &metainfo.name) => {
+        blocked.push(name);
+        continue;
+      }
+      Some(metainfo) => Candidate::from_metainfo(metainfo),
+      None => Candidate::default(),
+    };
+    if let (Some(user), Some(metainfo)) = (msg.from(), &metainfo) {
+      let total = charged.iter().map(|(_, size)| size).sum::<u64>() + metainfo.size;
+      if let Err(reason) = quotas.check(user.id.0, Some(total), lang) {
+        rejected.push(format!("{name}: {reason}"));
+        continue;
+      }
+    }
+    if let Some(rejection) = guard::check(torrent, &config.guard, &candidate).await? {
+      rejected.push(format!("{name}: {}", rejection.message(lang)));
+      continue;
+    }
+    if let Some(metainfo) = metainfo {
+      charged.push((metainfo.hash, metainfo.size));
+    }
+    files.push((name, contents));
+  }
+
+  let names: Vec<String> = files.iter().map(|(name, _)| name.clone()).collect();
+  let mut summary = if files.is_empty() {
+    String::new()
+  } else {
+    let arg = AddTorrentArg {
+      files,
+      ..Default::default()
+    };
+    match torrent.add_torrent(&arg).await {
+      Ok(()) => {
+        if let Some(user) = msg.from() {
+          for (hash, size) in &charged {
+            quotas.record(user.id.0, hash, Some(*size));
+          }
+          let action = format!("added {} .torrent files", names.len());
+          audit::record(db, user, action, None);
+        }
+        format!(
+          "{}\n• {}",
+          lang.tf("album.added", &[("count", &names.len())]),
+          names.join("\n• ")
+        )
+      }
+      Err(err) => err.to_string(),
+    }
+  };
+  if !failed.is_empty() {
+    if !summary.is_empty() {
+      summary.push_str("\n\n");
+    }
+    summary.push_str(&format!(
+      "{}\n• {}",
+      lang.t("album.failed"),
+      failed.join("\n• ")
+    ));
+  }
+  if !blocked.is_empty() {
+    if !summary.is_empty() {
+      summary.push_str("\n\n");
+    }
+    summary.push_str(&format!(
+      "{}\n• {}",
+      lang.t("parental.blocked"),
+      blocked.join("\n• ")
+    ));
+  }
+  if !rejected.is_empty() {
+    if !summary.is_empty() {
+      summary.push_str("\n\n");
+    }
+    summary.push_str(&format!("• {}", rejected.join("\n• ")));
+  }
+  bot.send_message(msg.chat.id, summary).await?;
+  Ok(())
+}
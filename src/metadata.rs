@@ -0,0 +1,205 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex, MutexGuard, OnceLock},
+};
+use teloxide::{
+  prelude::*,
+  types::{InputFile, ParseMode},
+};
+
+use crate::{render, torrent::ApiResult, HandlerResult};
+
+const SEARCH_URL: &str = "https://api.themoviedb.org/3/search";
+const POSTER_URL: &str = "https://image.tmdb.org/t/p/w342";
+
+// `Title.Name.2019.1080p...`, the title is everything before the year
+fn movie_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| {
+    Regex::new(r"^(.+?)[ ._\-\[(]+((?:19|20)\d{2})\b").expect("movie pattern is valid")
+  })
+}
+
+// `Show Name S02E05 ...` or a whole season as `S02`
+fn show_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| {
+    Regex::new(r"(?i)^(.+?)[ ._\-]+S\d{1,2}(?:E\d{1,3})?\b").expect("show pattern is valid")
+  })
+}
+
+/// What a torrent name looks like it is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Guess {
+  Movie { title: String, year: u16 },
+  Show { title: String },
+}
+
+fn clean(title: &str) -> String {
+  title
+    .replace(['.', '_'], " ")
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Shows are checked first, since their names often carry a year too.
+fn guess(name: &str) -> Option<Guess> {
+  if let Some(caps) = show_re().captures(name) {
+    return Some(Guess::Show {
+      title: clean(&caps[1]),
+    });
+  }
+  let caps = movie_re().captures(name)?;
+  Some(Guess::Movie {
+    title: clean(&caps[1]),
+    year: caps[2].parse().ok()?,
+  })
+}
+
+/// Poster, year and rating of the movie or show a torrent is.
+#[derive(Debug, Clone)]
+pub struct Metadata {
+  pub title: String,
+  pub year: Option<String>,
+  pub rating: Option<f64>,
+  pub poster_url: Option<String>,
+}
+
+impl Metadata {
+  /// `🎬 <b>Title</b> (2019) ⭐ 7.8`, in HTML.
+  pub fn caption(&self) -> String {
+    let mut caption = format!("🎬 {}", render::bold(&self.title));
+    if let Some(year) = &self.year {
+      caption.push_str(&format!(" ({year})"));
+    }
+    if let Some(rating) = self.rating.filter(|rating| *rating > 0.0) {
+      caption.push_str(&format!(" ⭐ {rating:.1}"));
+    }
+    caption
+  }
+}
+
+#[derive(Deserialize)]
+struct SearchResults {
+  results: Vec<SearchResult>,
+}
+
+#[derive(Deserialize)]
+struct SearchResult {
+  // movies have a title and release date, shows a name and first air date
+  title: Option<String>,
+  name: Option<String>,
+  release_date: Option<String>,
+  first_air_date: Option<String>,
+  vote_average: Option<f64>,
+  poster_path: Option<String>,
+}
+
+struct Inner {
+  http: reqwest::Client,
+  api_key: String,
+  cache: Mutex<HashMap<Guess, Option<Metadata>>>,
+}
+
+impl Inner {
+  fn cache(&self) -> MutexGuard<'_, HashMap<Guess, Option<Metadata>>> {
+    self
+      .cache
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+/// TMDB lookups, a no-op unless `tmdb_api_key` is configured.
+#[derive(Clone)]
+pub struct Tmdb(Option<Arc<Inner>>);
+
+impl Tmdb {
+  pub fn new(api_key: Option<String>) -> Self {
+    Tmdb(api_key.filter(|key| !key.is_empty()).map(|api_key| {
+      Arc::new(Inner {
+        http: reqwest::Client::new(),
+        api_key,
+        cache: Mutex::new(HashMap::new()),
+      })
+    }))
+  }
+
+  /// The best TMDB match for a torrent `name`, `None` when it doesn't look like a movie or show
+  /// or nothing was found. Failed requests are logged and not cached.
+  pub async fn lookup(&self, name: &str) -> Option<Metadata> {
+    let inner = self.0.as_ref()?;
+    let guess = guess(name)?;
+    if let Some(cached) = inner.cache().get(&guess) {
+      return cached.clone();
+    }
+    match search(inner, &guess).await {
+      Ok(found) => {
+        inner.cache().insert(guess, found.clone());
+        found
+      }
+      Err(err) => {
+        log::warn!("TMDB lookup for {name} failed: {err}");
+        None
+      }
+    }
+  }
+}
+
+async fn search(inner: &Inner, guess: &Guess) -> ApiResult<Option<Metadata>> {
+  let (kind, title, year) = match guess {
+    Guess::Movie { title, year } => ("movie", title, Some(year.to_string())),
+    Guess::Show { title } => ("tv", title, None),
+  };
+  let mut query = vec![("api_key", inner.api_key.clone()), ("query", title.clone())];
+  if let Some(year) = year {
+    query.push(("year", year));
+  }
+  let results: SearchResults = inner
+    .http
+    .get(format!("{SEARCH_URL}/{kind}"))
+    .query(&query)
+    .send()
+    .await?
+    .error_for_status()?
+    .json()
+    .await?;
+
+  Ok(results.results.into_iter().next().map(|result| {
+    let date = result.release_date.or(result.first_air_date);
+    Metadata {
+      title: result
+        .title
+        .or(result.name)
+        .unwrap_or_else(|| title.clone()),
+      year: date
+        .and_then(|date| date.get(..4).map(str::to_string))
+        .filter(|year| !year.is_empty()),
+      rating: result.vote_average,
+      poster_url: result.poster_path.map(|path| format!("{POSTER_URL}{path}")),
+    }
+  }))
+}
+
+/// Posts the poster with the caption under it, or just the caption when TMDB has no poster.
+pub async fn send(bot: &Bot, chat_id: ChatId, found: &Metadata) -> HandlerResult {
+  match found.poster_url.as_deref().and_then(|url| url.parse().ok()) {
+    Some(url) => {
+      bot
+        .send_photo(chat_id, InputFile::url(url))
+        .caption(found.caption())
+        .parse_mode(ParseMode::Html)
+        .await?;
+    }
+    None => {
+      bot
+        .send_message(chat_id, found.caption())
+        .parse_mode(ParseMode::Html)
+        .await?;
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,87 @@
+use teloxide::{prelude::*, types::InlineKeyboardButton};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+  callback_data,
+  db::Db,
+  features::{Feature, Features},
+  i18n::{self, Lang},
+  torrent::events::{TorrentEvent, TorrentEvents},
+  HandlerResult,
+};
+
+pub fn button(hash: &str, lang: Lang) -> InlineKeyboardButton {
+  callback_data::button(lang.t("milestones.button"), format!("milestones:{hash}"))
+}
+
+/// Subscribes the chat to the torrent's progress milestones, a second tap unsubscribes.
+pub async fn callback(bot: Bot, q: CallbackQuery, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  let (Some(hash), Some(msg)) = (
+    q.data
+      .as_deref()
+      .and_then(|data| data.strip_prefix("milestones:")),
+    q.message.as_ref(),
+  ) else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+
+  let subscribed = db.toggle_milestones(hash, msg.chat.id.0)?;
+  bot
+    .answer_callback_query(q.id)
+    .text(lang.t(if subscribed {
+      "milestones.on"
+    } else {
+      "milestones.off"
+    }))
+    .await?;
+  Ok(())
+}
+
+/// Tells subscribed chats once per milestone the poller reports (25, 50, 75, 90 and 100%).
+/// Subscriptions end with the download, or when the torrent is removed.
+pub fn spawn(bot: Bot, db: Db, features: Features, events: TorrentEvents) {
+  let mut events = events.subscribe();
+  tokio::spawn(async move {
+    loop {
+      let (torrent, percent) = match events.recv().await {
+        Ok(TorrentEvent::Milestone { torrent, percent }) => (torrent, percent),
+        Ok(TorrentEvent::Removed { hash, .. }) => {
+          if let Err(err) = db.clear_milestones(&hash) {
+            log::error!("Could not clear the milestones of {hash}: {err}");
+          }
+          continue;
+        }
+        Ok(_) => continue,
+        Err(RecvError::Lagged(skipped)) => {
+          log::warn!("Milestones skipped {skipped} events");
+          continue;
+        }
+        Err(RecvError::Closed) => break,
+      };
+      let chats = match db.milestone_chats(&torrent.hash) {
+        Ok(chats) => chats,
+        Err(err) => {
+          log::error!("Could not look up milestones for {}: {err}", torrent.hash);
+          continue;
+        }
+      };
+      if torrent.is_complete() {
+        if let Err(err) = db.clear_milestones(&torrent.hash) {
+          log::error!("Could not clear the milestones of {}: {err}", torrent.hash);
+        }
+      }
+      if !features.enabled(Feature::Notifications) || features.in_maintenance() {
+        continue;
+      }
+
+      let text = format!("📶 {} is {percent}% done", torrent.name);
+      for chat_id in chats.into_iter().map(ChatId) {
+        if let Err(err) = bot.send_message(chat_id, &text).await {
+          log::warn!("Could not send a milestone to {chat_id}: {err}");
+        }
+      }
+    }
+  });
+}
@@ -0,0 +1,148 @@
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use serde_json::json;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+  config::MqttConfig,
+  torrent::{
+    events::{TorrentEvent, TorrentEvents},
+    TorrentApi,
+  },
+};
+
+const PUBLISH_INTERVAL: Duration = Duration::from_secs(30);
+
+// (object id, name, unit, device class) of every sensor announced to Home Assistant
+const SENSORS: [(&str, &str, Option<&str>, Option<&str>); 5] = [
+  ("torrents", "Torrents", None, None),
+  ("downloading", "Downloading", None, None),
+  ("seeding", "Seeding", None, None),
+  (
+    "download_speed",
+    "Download speed",
+    Some("B/s"),
+    Some("data_rate"),
+  ),
+  (
+    "upload_speed",
+    "Upload speed",
+    Some("B/s"),
+    Some("data_rate"),
+  ),
+];
+
+/// Publishes torrent counts, speeds and completions to MQTT, with Home Assistant discovery,
+/// and runs `pause_all` / `resume_all` commands from `<base_topic>/command` if enabled.
+pub fn spawn(config: MqttConfig, torrent: TorrentApi, events: TorrentEvents) {
+  let mut options = MqttOptions::new("chatqbit", &config.host, config.port);
+  options.set_keep_alive(Duration::from_secs(30));
+  if let (Some(username), Some(password)) = (&config.username, &config.password) {
+    options.set_credentials(username, password);
+  }
+  let (client, mut eventloop) = AsyncClient::new(options, 32);
+  let base = config.base_topic.clone();
+
+  // the event loop has to be polled for anything to go out, and carries incoming commands
+  let command_topic = format!("{base}/command");
+  let commands = config.commands;
+  tokio::spawn(async move {
+    loop {
+      match eventloop.poll().await {
+        Ok(Event::Incoming(Packet::Publish(publish))) if publish.topic == command_topic => {
+          let command = String::from_utf8_lossy(&publish.payload).trim().to_string();
+          let res = match command.as_str() {
+            "pause_all" => torrent.pause("all").await,
+            "resume_all" => torrent.resume("all").await,
+            _ => Err(format!("unknown command {command}").into()),
+          };
+          if let Err(err) = res {
+            log::warn!("MQTT command failed: {err}");
+          }
+        }
+        Ok(_) => {}
+        Err(err) => {
+          log::warn!("MQTT connection error: {err}");
+          tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+      }
+    }
+  });
+
+  tokio::spawn(async move {
+    if let Err(err) = announce(&client, &config).await {
+      log::warn!("MQTT discovery failed: {err}");
+    }
+    if commands {
+      if let Err(err) = client
+        .subscribe(format!("{base}/command"), QoS::AtLeastOnce)
+        .await
+      {
+        log::warn!("MQTT subscribe failed: {err}");
+      }
+    }
+
+    let mut torrent_events = events.subscribe();
+    let torrents = events.torrents();
+    let server_state = events.server_state();
+    let mut ticker = tokio::time::interval(PUBLISH_INTERVAL);
+    loop {
+      tokio::select! {
+        _ = ticker.tick() => {
+          let state = {
+            let torrents = torrents.borrow();
+            let server = server_state.borrow();
+            json!({
+              "torrents": torrents.len(),
+              "downloading": torrents.values().filter(|t| !t.is_complete()).count(),
+              "seeding": torrents.values().filter(|t| t.state.ends_with("UP")).count(),
+              "download_speed": server.dl_info_speed,
+              "upload_speed": server.up_info_speed,
+            })
+          };
+          let _ = client
+            .publish(format!("{base}/state"), QoS::AtMostOnce, true, state.to_string())
+            .await;
+        }
+        event = torrent_events.recv() => match event {
+          Ok(TorrentEvent::Completed(torrent)) => {
+            let payload = json!({ "event": "completed", "hash": torrent.hash, "name": torrent.name });
+            let _ = client
+              .publish(format!("{base}/event"), QoS::AtLeastOnce, false, payload.to_string())
+              .await;
+          }
+          Ok(_) | Err(RecvError::Lagged(_)) => {}
+          Err(RecvError::Closed) => break,
+        },
+      }
+    }
+  });
+}
+
+async fn announce(client: &AsyncClient, config: &MqttConfig) -> Result<(), rumqttc::ClientError> {
+  let base = &config.base_topic;
+  for (id, name, unit, class) in SENSORS {
+    let mut discovery = json!({
+      "name": name,
+      "unique_id": format!("chatqbit_{id}"),
+      "state_topic": format!("{base}/state"),
+      "value_template": format!("{{{{ value_json.{id} }}}}"),
+      "device": { "identifiers": ["chatqbit"], "name": "ChatQBit" },
+    });
+    if let Some(unit) = unit {
+      discovery["unit_of_measurement"] = json!(unit);
+    }
+    if let Some(class) = class {
+      discovery["device_class"] = json!(class);
+    }
+    client
+      .publish(
+        format!("{}/sensor/chatqbit/{id}/config", config.discovery_prefix),
+        QoS::AtLeastOnce,
+        true,
+        discovery.to_string(),
+      )
+      .await?;
+  }
+  Ok(())
+}
@@ -0,0 +1,507 @@
+use async_trait::async_trait;
+use std::{
+  collections::HashMap,
+  sync::Arc,
+  time::{Duration, Instant},
+};
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardMarkup, InputFile, ParseMode},
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{
+  callback_data,
+  config::{CompletionMedia, Config},
+  db::{Db, NotifyPrefs},
+  features::{Feature, Features},
+  i18n,
+  metadata::Tmdb,
+  render,
+  torrent::events::{TorrentEvent, TorrentEvents, TorrentSnapshot},
+  HandlerResult,
+};
+
+mod discord;
+mod matrix;
+
+use discord::DiscordNotifier;
+use matrix::MatrixNotifier;
+
+// how often torrents in a bad state are re-checked against the threshold
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyKind {
+  Completed,
+  Errors,
+  Digest,
+}
+
+impl NotifyKind {
+  const ALL: [NotifyKind; 3] = [
+    NotifyKind::Completed,
+    NotifyKind::Errors,
+    NotifyKind::Digest,
+  ];
+
+  fn key(self) -> &'static str {
+    match self {
+      NotifyKind::Completed => "completed",
+      NotifyKind::Errors => "errors",
+      NotifyKind::Digest => "digest",
+    }
+  }
+
+  fn label(self) -> &'static str {
+    match self {
+      NotifyKind::Completed => "Completed downloads",
+      NotifyKind::Errors => "Errors and stalls",
+      NotifyKind::Digest => "Summary digest",
+    }
+  }
+
+  fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|kind| kind.key() == key)
+  }
+
+  fn flag(self, prefs: &mut NotifyPrefs) -> &mut bool {
+    match self {
+      NotifyKind::Completed => &mut prefs.completed,
+      NotifyKind::Errors => &mut prefs.errors,
+      NotifyKind::Digest => &mut prefs.digest,
+    }
+  }
+
+  fn wanted_by(self) -> fn(&NotifyPrefs) -> bool {
+    match self {
+      NotifyKind::Completed => |prefs: &NotifyPrefs| prefs.completed,
+      NotifyKind::Errors => |prefs: &NotifyPrefs| prefs.errors,
+      NotifyKind::Digest => |prefs: &NotifyPrefs| prefs.digest,
+    }
+  }
+}
+
+pub type NotifyResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+/// A place notifications can be delivered to.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+  fn name(&self) -> &str;
+
+  async fn notify(&self, kind: NotifyKind, text: &str) -> NotifyResult;
+
+  /// A title followed by a list, e.g. a batch of completions. Plain lines unless overridden.
+  async fn notify_list(&self, kind: NotifyKind, title: &str, items: &[String]) -> NotifyResult {
+    let text = format!("{title}\n• {}", items.join("\n• "));
+    self.notify(kind, &text).await
+  }
+
+  /// `text` with a `caption` line in HTML and a poster image, backends that can't show
+  /// images send the plain text.
+  async fn notify_with_poster(
+    &self,
+    kind: NotifyKind,
+    text: &str,
+    _caption: &str,
+    _poster_url: Option<&str>,
+  ) -> NotifyResult {
+    self.notify(kind, text).await
+  }
+
+  /// A sticker or GIF following a notification, skipped by backends that can't show one.
+  async fn notify_media(&self, _kind: NotifyKind, _media: &CompletionMedia) -> NotifyResult {
+    Ok(())
+  }
+}
+
+/// Sends to the configured chats and to users who opted in with /notify.
+pub struct TelegramNotifier {
+  bot: Bot,
+  config: Arc<Config>,
+  db: Db,
+}
+
+impl TelegramNotifier {
+  fn recipients(&self, kind: NotifyKind) -> Vec<ChatId> {
+    let mut chats: Vec<ChatId> = self
+      .config
+      .notify
+      .chat_ids
+      .iter()
+      .map(|id| ChatId(*id))
+      .collect();
+    match self.db.notify_subscribers(kind.wanted_by()) {
+      Ok(users) => chats.extend(users.into_iter().map(|id| ChatId(id as i64))),
+      Err(err) => log::warn!("Could not load notification subscribers: {err}"),
+    }
+    chats.sort_by_key(|chat| chat.0);
+    chats.dedup();
+    chats
+  }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+  fn name(&self) -> &str {
+    "telegram"
+  }
+
+  async fn notify(&self, kind: NotifyKind, text: &str) -> NotifyResult {
+    for chat_id in self.recipients(kind) {
+      // one blocked chat shouldn't keep the others from hearing about it
+      if let Err(err) = self.bot.send_message(chat_id, text).await {
+        log::warn!("Could not notify chat {chat_id}: {err}");
+      }
+    }
+    Ok(())
+  }
+
+  async fn notify_list(&self, kind: NotifyKind, title: &str, items: &[String]) -> NotifyResult {
+    // long lists collapse into an expandable quote instead of flooding the chat
+    let items: Vec<String> = items.iter().map(|item| render::escape(item)).collect();
+    let html = format!(
+      "{}\n<blockquote expandable>{}</blockquote>",
+      render::bold(title),
+      items.join("\n")
+    );
+    for chat_id in self.recipients(kind) {
+      let res = self
+        .bot
+        .send_message(chat_id, &html)
+        .parse_mode(ParseMode::Html)
+        .await;
+      if let Err(err) = res {
+        log::warn!("Could not notify chat {chat_id}: {err}");
+      }
+    }
+    Ok(())
+  }
+
+  async fn notify_with_poster(
+    &self,
+    kind: NotifyKind,
+    text: &str,
+    caption: &str,
+    poster_url: Option<&str>,
+  ) -> NotifyResult {
+    let html = format!("{}\n{caption}", render::escape(text));
+    for chat_id in self.recipients(kind) {
+      let res = match poster_url.and_then(|url| url.parse().ok()) {
+        Some(url) => self
+          .bot
+          .send_photo(chat_id, InputFile::url(url))
+          .caption(&html)
+          .parse_mode(ParseMode::Html)
+          .await
+          .map(drop),
+        None => self
+          .bot
+          .send_message(chat_id, &html)
+          .parse_mode(ParseMode::Html)
+          .await
+          .map(drop),
+      };
+      if let Err(err) = res {
+        log::warn!("Could not notify chat {chat_id}: {err}");
+      }
+    }
+    Ok(())
+  }
+
+  async fn notify_media(&self, kind: NotifyKind, media: &CompletionMedia) -> NotifyResult {
+    let file = |source: &str| match source.parse() {
+      Ok(url) if source.starts_with("http") => InputFile::url(url),
+      _ => InputFile::file_id(source),
+    };
+    for chat_id in self.recipients(kind) {
+      let res = match media {
+        CompletionMedia::Sticker(source) => {
+          self.bot.send_sticker(chat_id, file(source)).await.map(drop)
+        }
+        CompletionMedia::Animation(source) => self
+          .bot
+          .send_animation(chat_id, file(source))
+          .await
+          .map(drop),
+      };
+      if let Err(err) = res {
+        log::warn!("Could not send the completion media to chat {chat_id}: {err}");
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Every configured notifier, Telegram first.
+#[derive(Clone)]
+pub struct Notifiers {
+  backends: Arc<Vec<Box<dyn Notifier>>>,
+  features: Features,
+}
+
+impl Notifiers {
+  pub fn from_config(bot: Bot, config: Arc<Config>, db: Db, features: Features) -> Self {
+    let http = reqwest::Client::new();
+    let mut backends: Vec<Box<dyn Notifier>> = Vec::new();
+    for url in &config.notify.discord_webhooks {
+      backends.push(Box::new(DiscordNotifier::new(http.clone(), url.clone())));
+    }
+    if let Some(matrix) = &config.notify.matrix {
+      backends.push(Box::new(MatrixNotifier::new(http.clone(), matrix.clone())));
+    }
+    backends.insert(0, Box::new(TelegramNotifier { bot, config, db }));
+
+    Notifiers {
+      backends: Arc::new(backends),
+      features,
+    }
+  }
+
+  fn enabled(&self, kind: NotifyKind) -> bool {
+    if self.features.in_maintenance() {
+      return false;
+    }
+    match kind {
+      NotifyKind::Digest => self.features.enabled(Feature::Digest),
+      _ => self.features.enabled(Feature::Notifications),
+    }
+  }
+
+  pub async fn send(&self, kind: NotifyKind, text: &str) {
+    if !self.enabled(kind) {
+      return;
+    }
+    for backend in self.backends.iter() {
+      if let Err(err) = backend.notify(kind, text).await {
+        log::warn!("{} notification failed: {err}", backend.name());
+      }
+    }
+  }
+
+  pub async fn send_list(&self, kind: NotifyKind, title: &str, items: &[String]) {
+    if !self.enabled(kind) {
+      return;
+    }
+    for backend in self.backends.iter() {
+      if let Err(err) = backend.notify_list(kind, title, items).await {
+        log::warn!("{} notification failed: {err}", backend.name());
+      }
+    }
+  }
+
+  pub async fn send_with_poster(
+    &self,
+    kind: NotifyKind,
+    text: &str,
+    caption: &str,
+    poster_url: Option<&str>,
+  ) {
+    if !self.enabled(kind) {
+      return;
+    }
+    for backend in self.backends.iter() {
+      if let Err(err) = backend
+        .notify_with_poster(kind, text, caption, poster_url)
+        .await
+      {
+        log::warn!("{} notification failed: {err}", backend.name());
+      }
+    }
+  }
+
+  pub async fn send_media(&self, kind: NotifyKind, media: &CompletionMedia) {
+    if !self.enabled(kind) {
+      return;
+    }
+    for backend in self.backends.iter() {
+      if let Err(err) = backend.notify_media(kind, media).await {
+        log::warn!("{} notification failed: {err}", backend.name());
+      }
+    }
+  }
+}
+
+/// Announces finished torrents. Completions arriving within `notify.batch_window_secs` of
+/// each other (RSS bursts) are sent as one list instead of a message each; a single movie or
+/// show gets its TMDB poster.
+pub fn spawn_completion_notifier(
+  notifier: Notifiers,
+  config: Arc<Config>,
+  events: TorrentEvents,
+  tmdb: Tmdb,
+) {
+  let window = Duration::from_secs(config.notify.batch_window_secs);
+  let mut events = events.subscribe();
+  tokio::spawn(async move {
+    while let Some(first) = next_completed(&mut events).await {
+      let mut batch = vec![first];
+      if !window.is_zero() {
+        let deadline = tokio::time::sleep(window);
+        tokio::pin!(deadline);
+        loop {
+          tokio::select! {
+            _ = &mut deadline => break,
+            torrent = next_completed(&mut events) => match torrent {
+              Some(torrent) => batch.push(torrent),
+              None => break,
+            },
+          }
+        }
+      }
+
+      let names: Vec<String> = batch.iter().map(|torrent| torrent.name.clone()).collect();
+      match names.as_slice() {
+        [name] => {
+          let text = format!("✅ {name} has finished downloading");
+          match tmdb.lookup(name).await {
+            Some(found) => {
+              notifier
+                .send_with_poster(
+                  NotifyKind::Completed,
+                  &text,
+                  &found.caption(),
+                  found.poster_url.as_deref(),
+                )
+                .await
+            }
+            None => notifier.send(NotifyKind::Completed, &text).await,
+          }
+        }
+        names => {
+          let title = format!("✅ {} downloads have finished", names.len());
+          notifier
+            .send_list(NotifyKind::Completed, &title, names)
+            .await;
+        }
+      }
+
+      // once per category, a burst of episodes gets one confetti rather than ten
+      let mut categories: Vec<&str> = batch
+        .iter()
+        .map(|torrent| torrent.category.as_str())
+        .collect();
+      categories.sort_unstable();
+      categories.dedup();
+      for category in categories {
+        if let Some(media) = config.notify.completion_media.get(category) {
+          notifier.send_media(NotifyKind::Completed, media).await;
+        }
+      }
+    }
+  });
+}
+
+async fn next_completed(events: &mut broadcast::Receiver<TorrentEvent>) -> Option<TorrentSnapshot> {
+  loop {
+    match events.recv().await {
+      Ok(TorrentEvent::Completed(torrent)) => return Some(torrent),
+      Ok(_) => {}
+      Err(RecvError::Lagged(skipped)) => log::warn!("Notifier skipped {skipped} events"),
+      Err(RecvError::Closed) => return None,
+    }
+  }
+}
+
+/// Watches the torrent list and warns about torrents that stay errored, missing files
+/// or stalled for longer than `notify.problem_after_secs`.
+pub fn spawn_problem_watcher(notifier: Notifiers, config: Arc<Config>, events: TorrentEvents) {
+  tokio::spawn(async move {
+    let threshold = Duration::from_secs(config.notify.problem_after_secs);
+    let torrents = events.torrents();
+    // hash -> (problem, first seen, already reported)
+    let mut problems: HashMap<String, (&'static str, Instant, bool)> = HashMap::new();
+    let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+
+    loop {
+      ticker.tick().await;
+      let current = torrents.borrow().clone();
+
+      problems.retain(|hash, (problem, ..)| {
+        current
+          .get(hash)
+          .and_then(|torrent| problem_of(&torrent.state))
+          == Some(*problem)
+      });
+
+      for torrent in current.values() {
+        let Some(problem) = problem_of(&torrent.state) else {
+          continue;
+        };
+        let (_, since, reported) =
+          problems
+            .entry(torrent.hash.clone())
+            .or_insert((problem, Instant::now(), false));
+        if *reported || since.elapsed() < threshold {
+          continue;
+        }
+        *reported = true;
+
+        let text = format!(
+          "⚠️ {} has been {problem} for over {} min",
+          torrent.name,
+          threshold.as_secs() / 60
+        );
+        notifier.send(NotifyKind::Errors, &text).await;
+      }
+    }
+  });
+}
+
+fn problem_of(state: &str) -> Option<&'static str> {
+  match state {
+    "error" => Some("errored"),
+    "missingFiles" => Some("missing files"),
+    "stalledDL" => Some("stalled"),
+    _ => None,
+  }
+}
+
+fn prefs_keyboard(prefs: &NotifyPrefs) -> InlineKeyboardMarkup {
+  let mut prefs = *prefs;
+  InlineKeyboardMarkup::new(NotifyKind::ALL.map(|kind| {
+    let mark = if *kind.flag(&mut prefs) { "✅" } else { "⬜" };
+    vec![callback_data::button(
+      format!("{mark} {}", kind.label()),
+      format!("notify:{}", kind.key()),
+    )]
+  }))
+}
+
+pub async fn notify(bot: Bot, msg: Message, db: Db) -> HandlerResult {
+  let Some(user) = msg.from() else {
+    return Ok(());
+  };
+  let prefs = db.notify_prefs(user.id.0)?;
+  bot
+    .send_message(msg.chat.id, i18n::lang(&db, Some(user)).t("notify.prompt"))
+    .reply_markup(prefs_keyboard(&prefs))
+    .await?;
+  Ok(())
+}
+
+pub async fn callback(bot: Bot, q: CallbackQuery, db: Db) -> HandlerResult {
+  let Some(kind) = q
+    .data
+    .as_deref()
+    .and_then(|data| data.strip_prefix("notify:"))
+    .and_then(NotifyKind::from_key)
+  else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+
+  let mut prefs = db.notify_prefs(q.from.id.0)?;
+  let flag = kind.flag(&mut prefs);
+  *flag = !*flag;
+  db.set_notify_prefs(q.from.id.0, prefs)?;
+
+  bot.answer_callback_query(q.id).await?;
+  if let Some(msg) = q.message {
+    bot
+      .edit_message_reply_markup(msg.chat.id, msg.id)
+      .reply_markup(prefs_keyboard(&prefs))
+      .await?;
+  }
+  Ok(())
+}
@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{Notifier, NotifyKind, NotifyResult};
+
+pub struct DiscordNotifier {
+  http: reqwest::Client,
+  url: String,
+}
+
+impl DiscordNotifier {
+  pub fn new(http: reqwest::Client, url: String) -> Self {
+    DiscordNotifier { http, url }
+  }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+  fn name(&self) -> &str {
+    "discord"
+  }
+
+  async fn notify(&self, _kind: NotifyKind, text: &str) -> NotifyResult {
+    self
+      .http
+      .post(&self.url)
+      .json(&json!({ "content": text }))
+      .send()
+      .await?
+      .error_for_status()?;
+    Ok(())
+  }
+}
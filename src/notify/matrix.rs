@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::{Notifier, NotifyKind, NotifyResult};
+use crate::config::MatrixConfig;
+
+pub struct MatrixNotifier {
+  http: reqwest::Client,
+  config: MatrixConfig,
+  // Matrix deduplicates sends by transaction id, so every message needs a fresh one
+  txn: AtomicU64,
+}
+
+impl MatrixNotifier {
+  pub fn new(http: reqwest::Client, config: MatrixConfig) -> Self {
+    let start = chrono::Utc::now().timestamp_millis() as u64;
+    MatrixNotifier {
+      http,
+      config,
+      txn: AtomicU64::new(start),
+    }
+  }
+}
+
+#[async_trait]
+impl Notifier for MatrixNotifier {
+  fn name(&self) -> &str {
+    "matrix"
+  }
+
+  async fn notify(&self, _kind: NotifyKind, text: &str) -> NotifyResult {
+    let txn = self.txn.fetch_add(1, Ordering::Relaxed).to_string();
+    let mut url = reqwest::Url::parse(&self.config.homeserver)?;
+    url
+      .path_segments_mut()
+      .map_err(|_| "invalid Matrix homeserver URL")?
+      .pop_if_empty()
+      .extend([
+        "_matrix",
+        "client",
+        "v3",
+        "rooms",
+        &self.config.room_id,
+        "send",
+        "m.room.message",
+        &txn,
+      ]);
+
+    self
+      .http
+      .put(url)
+      .bearer_auth(&self.config.access_token)
+      .json(&json!({ "msgtype": "m.text", "body": text }))
+      .send()
+      .await?
+      .error_for_status()?;
+    Ok(())
+  }
+}
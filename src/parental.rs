@@ -0,0 +1,319 @@
+use std::{
+  collections::{HashMap, HashSet},
+  sync::{Arc, Mutex, RwLock},
+  time::{Duration, Instant},
+};
+use teloxide::prelude::*;
+
+use crate::{
+  audit, auth::Access, config::ParentalConfig, db::Db, i18n, torrent::events::TorrentSnapshot,
+  HandlerResult,
+};
+
+const UNLOCK_FOR: Duration = Duration::from_secs(30 * 60);
+
+// wrong PINs a chat gets before /unlock stops listening to it for a while
+const MAX_PIN_ATTEMPTS: u32 = 5;
+const LOCKOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Chats switched to the restricted profile with /parental, and the ones temporarily
+/// unlocked with the admin PIN.
+#[derive(Clone)]
+pub struct Parental {
+  config: ParentalConfig,
+  db: Db,
+  restricted: Arc<RwLock<HashSet<i64>>>,
+  unlocked: Arc<Mutex<HashMap<i64, Instant>>>,
+  // wrong PINs per chat, with when the last one was tried
+  failures: Arc<Mutex<HashMap<i64, (u32, Instant)>>>,
+}
+
+impl Parental {
+  pub fn load(config: ParentalConfig, db: Db) -> Self {
+    let restricted = db.restricted_chats().unwrap_or_else(|err| {
+      log::error!("Could not load restricted chats: {err}");
+      Vec::new()
+    });
+    Parental {
+      config,
+      db,
+      restricted: Arc::new(RwLock::new(restricted.into_iter().collect())),
+      unlocked: Arc::default(),
+      failures: Arc::default(),
+    }
+  }
+
+  pub fn is_restricted(&self, chat: ChatId) -> bool {
+    let restricted = self
+      .restricted
+      .read()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+      .contains(&chat.0);
+    if !restricted {
+      return false;
+    }
+    let mut unlocked = self.unlocked();
+    match unlocked.get(&chat.0) {
+      Some(since) if since.elapsed() < UNLOCK_FOR => false,
+      Some(_) => {
+        unlocked.remove(&chat.0);
+        true
+      }
+      None => true,
+    }
+  }
+
+  /// Whether `torrent` is kept out of sight in `chat`.
+  pub fn hides(&self, chat: ChatId, torrent: &TorrentSnapshot) -> bool {
+    self.hides_labelled(chat, &torrent.category, &torrent.tags)
+  }
+
+  /// [`Parental::hides`] for torrents that aren't snapshots, by category and comma-separated tags.
+  pub fn hides_labelled(&self, chat: ChatId, category: &str, tags: &str) -> bool {
+    if !self.is_restricted(chat) {
+      return false;
+    }
+    let hidden_category = self
+      .config
+      .hidden_categories
+      .iter()
+      .any(|hidden| hidden.eq_ignore_ascii_case(category));
+    let hidden_tag = tags.split(',').map(str::trim).any(|tag| {
+      self
+        .config
+        .hidden_tags
+        .iter()
+        .any(|hidden| hidden.eq_ignore_ascii_case(tag))
+    });
+    hidden_category || hidden_tag
+  }
+
+  /// Whether a torrent called `name` may not be added from `chat`.
+  pub fn blocks(&self, chat: ChatId, name: &str) -> bool {
+    if !self.is_restricted(chat) {
+      return false;
+    }
+    let name = name.to_lowercase();
+    self
+      .config
+      .denylist
+      .iter()
+      .any(|word| name.contains(&word.to_lowercase()))
+  }
+
+  fn set_restricted(&self, chat: ChatId, on: bool) -> rusqlite::Result<()> {
+    self.db.set_restricted_chat(chat.0, on)?;
+    let mut restricted = self
+      .restricted
+      .write()
+      .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if on {
+      restricted.insert(chat.0);
+    } else {
+      restricted.remove(&chat.0);
+    }
+    self.unlocked().remove(&chat.0);
+    Ok(())
+  }
+
+  fn unlocked(&self) -> std::sync::MutexGuard<'_, HashMap<i64, Instant>> {
+    self
+      .unlocked
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  fn failures(&self) -> std::sync::MutexGuard<'_, HashMap<i64, (u32, Instant)>> {
+    self
+      .failures
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  /// How long `chat` still has to wait before trying another PIN.
+  fn locked_out(&self, chat: ChatId) -> Option<Duration> {
+    let mut failures = self.failures();
+    let (count, last) = *failures.get(&chat.0)?;
+    let waited = last.elapsed();
+    if waited >= LOCKOUT {
+      failures.remove(&chat.0);
+      return None;
+    }
+    (count >= MAX_PIN_ATTEMPTS).then(|| LOCKOUT - waited)
+  }
+
+  fn record_failure(&self, chat: ChatId) {
+    let mut failures = self.failures();
+    let entry = failures.entry(chat.0).or_insert((0, Instant::now()));
+    *entry = (entry.0 + 1, Instant::now());
+  }
+}
+
+/// Compares PINs in time that doesn't depend on where they differ.
+fn pin_matches(expected: &str, given: &str) -> bool {
+  let (expected, given) = (expected.as_bytes(), given.as_bytes());
+  expected.len() == given.len()
+    && expected
+      .iter()
+      .zip(given)
+      .fold(0, |diff, (a, b)| diff | (a ^ b))
+      == 0
+}
+
+/// Decodes the `%XX` escapes and `+` spaces of a magnet parameter.
+pub fn percent_decode(raw: &str) -> String {
+  let raw = raw.replace('+', " ");
+  let bytes = raw.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    let hex = bytes
+      .get(i + 1..i + 3)
+      .and_then(|hex| std::str::from_utf8(hex).ok())
+      .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+    match (bytes[i], hex) {
+      (b'%', Some(byte)) => {
+        decoded.push(byte);
+        i += 3;
+      }
+      (byte, _) => {
+        decoded.push(byte);
+        i += 1;
+      }
+    }
+  }
+  String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The `dn=` display name of a magnet link, if it has one.
+pub fn magnet_name(magnet: &str) -> Option<String> {
+  magnet
+    .split(['?', '&'])
+    .find_map(|param| param.strip_prefix("dn="))
+    .map(percent_decode)
+}
+
+/// `/parental on|off` switches the restricted profile for the current chat. Turning it off takes
+/// an admin listed in `ADMIN_USER_IDS`, since every allowed user is an admin without that list.
+pub async fn parental(
+  bot: Bot,
+  msg: Message,
+  arg: String,
+  access: Access,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(user) = msg.from().filter(|user| access.is_admin(user.id)) else {
+    bot.send_message(msg.chat.id, lang.t("admin.only")).await?;
+    return Ok(());
+  };
+
+  let on = match arg.trim().to_lowercase().as_str() {
+    "on" => true,
+    "off" => false,
+    _ => {
+      bot
+        .send_message(msg.chat.id, lang.t("parental.usage"))
+        .await?;
+      return Ok(());
+    }
+  };
+  if !on && !access.is_listed_admin(user.id) {
+    bot
+      .send_message(msg.chat.id, lang.t("parental.off_admin_only"))
+      .await?;
+    return Ok(());
+  }
+  parental.set_restricted(msg.chat.id, on)?;
+  let action = if on {
+    "restricted a chat"
+  } else {
+    "lifted a chat restriction"
+  };
+  audit::record(&db, user, action, None);
+
+  let key = if on { "parental.on" } else { "parental.off" };
+  bot.send_message(msg.chat.id, lang.t(key)).await?;
+  Ok(())
+}
+
+/// `/unlock <pin>` lifts the restriction of the current chat for a while. Too many wrong PINs
+/// lock the chat out of trying for a while.
+pub async fn unlock(
+  bot: Bot,
+  msg: Message,
+  pin: String,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  // the PIN shouldn't stay readable for whoever picks up the phone next
+  let _ = bot.delete_message(msg.chat.id, msg.id).await;
+
+  if let Some(wait) = parental.locked_out(msg.chat.id) {
+    let mins = wait.as_secs().div_ceil(60);
+    bot
+      .send_message(
+        msg.chat.id,
+        lang.tf("parental.locked_out", &[("mins", &mins)]),
+      )
+      .await?;
+    return Ok(());
+  }
+  let matches = parental
+    .config
+    .pin
+    .as_deref()
+    .is_some_and(|expected| !expected.is_empty() && pin_matches(expected, pin.trim()));
+  if !matches {
+    log::warn!("Wrong parental PIN in chat {}", msg.chat.id);
+    parental.record_failure(msg.chat.id);
+    bot
+      .send_message(msg.chat.id, lang.t("parental.wrong_pin"))
+      .await?;
+    return Ok(());
+  }
+  parental.failures().remove(&msg.chat.id.0);
+  parental.unlocked().insert(msg.chat.id.0, Instant::now());
+  bot
+    .send_message(
+      msg.chat.id,
+      lang.tf(
+        "parental.unlocked",
+        &[("mins", &(UNLOCK_FOR.as_secs() / 60))],
+      ),
+    )
+    .await?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decodes_magnet_parameters() {
+    assert_eq!(percent_decode("The+Movie%20%282024%29"), "The Movie (2024)");
+    assert_eq!(percent_decode("caf%C3%A9"), "café");
+    assert_eq!(percent_decode("100%"), "100%");
+    assert_eq!(percent_decode("50%zz"), "50%zz");
+  }
+
+  #[test]
+  fn reads_the_display_name() {
+    assert_eq!(
+      magnet_name("magnet:?xt=urn:btih:abc&dn=Some+Show.S01&tr=udp%3A%2F%2Fa").as_deref(),
+      Some("Some Show.S01")
+    );
+    assert_eq!(magnet_name("magnet:?xt=urn:btih:abc"), None);
+  }
+
+  #[test]
+  fn compares_pins_exactly() {
+    assert!(pin_matches("4321", "4321"));
+    assert!(!pin_matches("4321", "4320"));
+    assert!(!pin_matches("4321", "432"));
+    assert!(!pin_matches("4321", "43210"));
+  }
+}
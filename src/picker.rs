@@ -0,0 +1,203 @@
+use std::{
+  collections::HashMap,
+  sync::{Mutex, MutexGuard, OnceLock},
+};
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardButton, InlineKeyboardMarkup, MessageId},
+};
+
+use crate::{
+  audit, callback_data,
+  db::Db,
+  i18n::{self, Lang},
+  list::page_row,
+  settings,
+  torrent::{TorrentApi, TorrentFile, PRIORITY_HIGH, PRIORITY_NORMAL, PRIORITY_SKIP},
+  HandlerResult,
+};
+
+/// The mark shown for a file priority, `7` (maximal) counts as high.
+fn mark(priority: u8) -> &'static str {
+  match priority {
+    PRIORITY_SKIP => "⏭",
+    PRIORITY_NORMAL => "⬇️",
+    _ => "⏫",
+  }
+}
+
+/// Tapping a file cycles Download → High → Skip.
+fn next_priority(priority: u8) -> u8 {
+  match priority {
+    PRIORITY_SKIP => PRIORITY_NORMAL,
+    PRIORITY_NORMAL => PRIORITY_HIGH,
+    _ => PRIORITY_SKIP,
+  }
+}
+
+// choices not applied yet, per picker message: file index to the priority picked for it
+type Choices = HashMap<(ChatId, MessageId), HashMap<usize, u8>>;
+
+fn choices() -> MutexGuard<'static, Choices> {
+  static CHOICES: OnceLock<Mutex<Choices>> = OnceLock::new();
+  CHOICES
+    .get_or_init(Default::default)
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The files as they will be once the picks made in `picker` are applied.
+fn with_choices(mut files: Vec<TorrentFile>, picker: (ChatId, MessageId)) -> Vec<TorrentFile> {
+  if let Some(picked) = choices().get(&picker) {
+    for file in &mut files {
+      if let Some(priority) = picked.get(&file.index) {
+        file.priority = *priority;
+      }
+    }
+  }
+  files
+}
+
+/// Button offered after adding a torrent, opening the file picker once metadata is in.
+pub fn select_files_button(hash: &str, lang: Lang) -> InlineKeyboardMarkup {
+  InlineKeyboardMarkup::new(vec![vec![callback_data::button(
+    lang.t("picker.button"),
+    format!("files:{hash}"),
+  )]])
+}
+
+fn file_button(hash: &str, file: &TorrentFile, page: usize) -> InlineKeyboardButton {
+  callback_data::button(
+    format!("{} {}", mark(file.priority), file.name),
+    format!("ft:{hash}:{}:{page}", file.index),
+  )
+}
+
+fn controls(hash: &str) -> Vec<InlineKeyboardButton> {
+  vec![
+    callback_data::button("✔️ Done", format!("fa:{hash}")),
+    callback_data::button("✖️ Close", "fc"),
+  ]
+}
+
+/// One page of files. Picks are kept per picker until Done, so they survive changing pages.
+fn keyboard(
+  hash: &str,
+  files: &[TorrentFile],
+  page: usize,
+  page_size: usize,
+  lang: Lang,
+) -> InlineKeyboardMarkup {
+  let pages = files.len().div_ceil(page_size);
+  let page = page.min(pages.saturating_sub(1));
+  let mut rows: Vec<_> = files
+    .iter()
+    .skip(page * page_size)
+    .take(page_size)
+    .map(|file| vec![file_button(hash, file, page)])
+    .collect();
+  rows.push(page_row(page, page + 1 < pages, lang, |page| {
+    format!("files:{hash}:{page}")
+  }));
+  rows.push(controls(hash));
+  InlineKeyboardMarkup::new(rows)
+}
+
+pub async fn callback(bot: Bot, q: CallbackQuery, torrent: TorrentApi, db: Db) -> HandlerResult {
+  let (Some(data), Some(msg)) = (q.data.as_deref(), q.message.as_ref()) else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+  let lang = i18n::lang(&db, Some(&q.from));
+  let page_size = settings::of(&db, Some(&q.from)).files_page_size as usize;
+  let picker = (msg.chat.id, msg.id);
+  let mut parts = data.split(':');
+
+  match (parts.next(), parts.next(), parts.next(), parts.next()) {
+    (Some("files"), Some(hash), page, None) => {
+      let files = with_choices(torrent.files(hash).await?, picker);
+      if files.is_empty() {
+        bot
+          .answer_callback_query(q.id)
+          .text(lang.t("picker.no_metadata"))
+          .await?;
+        return Ok(());
+      }
+      let page = page.and_then(|page| page.parse().ok()).unwrap_or(0);
+
+      bot.answer_callback_query(q.id).await?;
+      bot
+        .edit_message_text(msg.chat.id, msg.id, lang.t("picker.prompt"))
+        .reply_markup(keyboard(hash, &files, page, page_size, lang))
+        .await?;
+    }
+    (Some("ft"), Some(hash), Some(index), Some(page)) => {
+      let index: usize = index.parse()?;
+      let files = with_choices(torrent.files(hash).await?, picker);
+      let Some(file) = files.iter().find(|file| file.index == index) else {
+        bot.answer_callback_query(q.id).await?;
+        return Ok(());
+      };
+      choices()
+        .entry(picker)
+        .or_default()
+        .insert(index, next_priority(file.priority));
+      let files = with_choices(files, picker);
+
+      bot.answer_callback_query(q.id).await?;
+      bot
+        .edit_message_reply_markup(msg.chat.id, msg.id)
+        .reply_markup(keyboard(hash, &files, page.parse()?, page_size, lang))
+        .await?;
+    }
+    (Some("fa"), Some(hash), None, None) => {
+      let files = torrent.files(hash).await?;
+      let picked = choices().get(&picker).cloned().unwrap_or_default();
+      let chosen: Vec<usize> = with_choices(files.clone(), picker)
+        .iter()
+        .filter(|file| file.priority > PRIORITY_SKIP)
+        .map(|file| file.index)
+        .collect();
+      if chosen.is_empty() {
+        bot
+          .answer_callback_query(q.id)
+          .text(lang.t("picker.empty"))
+          .await?;
+        return Ok(());
+      }
+
+      torrent.download_only_files(hash, &chosen).await?;
+      // a fresh torrent downloads everything normally, so only other picks need a call
+      for priority in [PRIORITY_NORMAL, PRIORITY_HIGH] {
+        let changed: Vec<usize> = files
+          .iter()
+          .filter(|file| file.priority != priority && picked.get(&file.index) == Some(&priority))
+          .map(|file| file.index)
+          .collect();
+        torrent.set_file_priority(hash, &changed, priority).await?;
+      }
+      choices().remove(&picker);
+      let chosen = chosen.len();
+
+      let action = format!("selected {chosen} file(s)");
+      audit::record(&db, &q.from, action, Some(hash));
+      bot.answer_callback_query(q.id).await?;
+      bot
+        .edit_message_text(
+          msg.chat.id,
+          msg.id,
+          lang.tf("picker.done", &[("count", &chosen)]),
+        )
+        .await?;
+    }
+    (Some("fc"), None, None, None) => {
+      choices().remove(&picker);
+      bot.answer_callback_query(q.id).await?;
+      bot.delete_message(msg.chat.id, msg.id).await?;
+    }
+    _ => {
+      bot.answer_callback_query(q.id).await?;
+    }
+  }
+  Ok(())
+}
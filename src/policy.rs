@@ -0,0 +1,235 @@
+use serde::Deserialize;
+use std::{collections::HashSet, sync::Arc};
+use teloxide::{prelude::*, types::ParseMode};
+
+use crate::{
+  config::Config,
+  db::Db,
+  digest::DryRuns,
+  features::Features,
+  i18n,
+  parental::Parental,
+  render,
+  torrent::{
+    events::{TorrentEvents, TorrentSnapshot},
+    TorrentApi,
+  },
+  HandlerResult,
+};
+
+/// A rule deleting completed torrents once they reached a ratio or have seeded long enough.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Policy {
+  pub name: String,
+  /// Only torrents in this category, any when unset.
+  #[serde(default)]
+  pub category: Option<String>,
+  #[serde(default)]
+  pub ratio: Option<f64>,
+  #[serde(default)]
+  pub seeded_days: Option<u64>,
+  /// Deletes the downloaded data along with the torrent.
+  #[serde(default)]
+  pub delete_files: bool,
+}
+
+impl Policy {
+  /// A rule without a ratio or seeding time never matches, rather than matching everything.
+  fn matches(&self, torrent: &TorrentSnapshot) -> bool {
+    if !torrent.is_complete() {
+      return false;
+    }
+    if self
+      .category
+      .as_ref()
+      .is_some_and(|category| *category != torrent.category)
+    {
+      return false;
+    }
+    let ratio = self.ratio.is_some_and(|ratio| torrent.ratio >= ratio);
+    let seeded = self
+      .seeded_days
+      .is_some_and(|days| torrent.seeding_time >= days as i64 * 24 * 60 * 60);
+    ratio || seeded
+  }
+}
+
+/// The first policy matching each torrent, sorted by name.
+fn due<'a>(
+  policies: &'a [Policy],
+  torrents: impl Iterator<Item = &'a TorrentSnapshot>,
+) -> Vec<(&'a TorrentSnapshot, &'a Policy)> {
+  let mut due: Vec<_> = torrents
+    .filter_map(|torrent| {
+      policies
+        .iter()
+        .find(|policy| policy.matches(torrent))
+        .map(|policy| (torrent, policy))
+    })
+    .collect();
+  due.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+  due
+}
+
+/// Applies the policies whenever the poller publishes a new torrent list. With `dry_run` on, the
+/// torrents they would remove go to the digest instead.
+pub fn spawn(
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  features: Features,
+  events: TorrentEvents,
+  dry_runs: DryRuns,
+) {
+  if config.policies.is_empty() {
+    return;
+  }
+  let mut torrents = events.torrents();
+  tokio::spawn(async move {
+    // deletions take a poll or two to show up, don't send them twice
+    let mut removed = HashSet::new();
+    while torrents.changed().await.is_ok() {
+      if features.in_maintenance() {
+        continue;
+      }
+      let snapshot = torrents.borrow_and_update().clone();
+      removed.retain(|hash| snapshot.contains_key(hash));
+      for (due, policy) in due(&config.policies, snapshot.values()) {
+        if removed.contains(&due.hash) {
+          continue;
+        }
+        if config.dry_run {
+          let with_data = if policy.delete_files {
+            " with data"
+          } else {
+            ""
+          };
+          dry_runs.torrent(format!("{} ({}{with_data})", due.name, policy.name));
+          removed.insert(due.hash.clone());
+          continue;
+        }
+        match torrent.delete(&due.hash, policy.delete_files).await {
+          Ok(()) => {
+            log::info!("Policy {} removed {}", policy.name, due.name);
+            removed.insert(due.hash.clone());
+          }
+          Err(err) => log::warn!(
+            "Policy {} could not remove {}: {err}",
+            policy.name,
+            due.name
+          ),
+        }
+      }
+    }
+  });
+}
+
+/// Dry run: lists the torrents the policies would remove right now.
+pub async fn policies(
+  bot: Bot,
+  msg: Message,
+  config: Arc<Config>,
+  db: Db,
+  events: TorrentEvents,
+  parental: Parental,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  if config.policies.is_empty() {
+    bot
+      .send_message(msg.chat.id, lang.t("policies.none"))
+      .await?;
+    return Ok(());
+  }
+
+  let torrents = events.torrents().borrow().clone();
+  let visible = torrents
+    .values()
+    .filter(|torrent| !parental.hides(msg.chat.id, torrent));
+  let due = due(&config.policies, visible);
+  if due.is_empty() {
+    bot
+      .send_message(msg.chat.id, lang.t("policies.nothing"))
+      .await?;
+    return Ok(());
+  }
+
+  let mut text = lang.t("policies.title").to_string();
+  if config.dry_run {
+    text = format!("{text}\n{}", lang.t("policies.dry_run"));
+  }
+  for (torrent, policy) in due {
+    let with_data = if policy.delete_files { " 🗑" } else { "" };
+    text.push_str(&format!(
+      "\n• {} — {}{with_data}",
+      render::bold(&torrent.name),
+      render::italic(&policy.name)
+    ));
+  }
+  bot
+    .send_message(msg.chat.id, text)
+    .parse_mode(ParseMode::Html)
+    .await?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn policy() -> Policy {
+    Policy {
+      name: "test".to_string(),
+      category: None,
+      ratio: None,
+      seeded_days: None,
+      delete_files: false,
+    }
+  }
+
+  fn torrent(progress: f64, ratio: f64, seeded_days: i64) -> TorrentSnapshot {
+    TorrentSnapshot {
+      progress,
+      ratio,
+      seeding_time: seeded_days * 24 * 60 * 60,
+      category: "tv".to_string(),
+      ..TorrentSnapshot::default()
+    }
+  }
+
+  #[test]
+  fn matches_ratio_or_seeding_time() {
+    let by_ratio = Policy {
+      ratio: Some(2.0),
+      ..policy()
+    };
+    assert!(by_ratio.matches(&torrent(1.0, 2.0, 0)));
+    assert!(!by_ratio.matches(&torrent(1.0, 1.9, 30)));
+
+    let by_time = Policy {
+      seeded_days: Some(7),
+      ..policy()
+    };
+    assert!(by_time.matches(&torrent(1.0, 0.0, 7)));
+    assert!(!by_time.matches(&torrent(1.0, 0.0, 6)));
+  }
+
+  #[test]
+  fn needs_a_complete_torrent_in_its_category() {
+    let rule = Policy {
+      ratio: Some(1.0),
+      category: Some("movies".to_string()),
+      ..policy()
+    };
+    assert!(!rule.matches(&torrent(1.0, 5.0, 0)));
+    let rule = Policy {
+      category: Some("tv".to_string()),
+      ..rule
+    };
+    assert!(rule.matches(&torrent(1.0, 5.0, 0)));
+    assert!(!rule.matches(&torrent(0.9, 5.0, 0)));
+  }
+
+  #[test]
+  fn never_matches_without_a_limit() {
+    assert!(!policy().matches(&torrent(1.0, 100.0, 365)));
+  }
+}
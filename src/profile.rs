@@ -0,0 +1,152 @@
+use serde::Deserialize;
+use std::sync::Arc;
+use teloxide::{prelude::*, types::InlineKeyboardButton};
+
+use crate::{
+  audit, callback_data,
+  config::Config,
+  db::Db,
+  i18n,
+  parental::Parental,
+  torrent::{ApiResult, TorrentApi},
+  HandlerResult,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueuePosition {
+  Top,
+  Bottom,
+}
+
+/// A named bundle of settings applied to a torrent right after it was added.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+  pub name: String,
+  pub sequential: bool,
+  pub queue: Option<QueuePosition>,
+  /// Bytes per second.
+  pub upload_limit: Option<u64>,
+  /// Bytes per second.
+  pub download_limit: Option<u64>,
+}
+
+impl Profile {
+  pub fn defaults() -> Vec<Profile> {
+    vec![
+      Profile {
+        name: "Media streaming".to_string(),
+        sequential: true,
+        queue: Some(QueuePosition::Top),
+        ..Default::default()
+      },
+      Profile {
+        name: "Archive".to_string(),
+        queue: Some(QueuePosition::Bottom),
+        upload_limit: Some(100 * 1024),
+        ..Default::default()
+      },
+    ]
+  }
+
+  pub async fn apply(&self, torrent: &TorrentApi, hash: &str) -> ApiResult<()> {
+    if self.sequential {
+      torrent.set_sequential_download(hash, true).await?;
+    }
+    match self.queue {
+      Some(QueuePosition::Top) => torrent.set_queue_position(hash, true).await?,
+      Some(QueuePosition::Bottom) => torrent.set_queue_position(hash, false).await?,
+      None => {}
+    }
+    if let Some(limit) = self.upload_limit {
+      torrent.set_upload_limit(hash, limit).await?;
+    }
+    if let Some(limit) = self.download_limit {
+      torrent.set_download_limit(hash, limit).await?;
+    }
+    Ok(())
+  }
+}
+
+/// One button per configured profile, referenced by position to stay within callback limits.
+pub fn buttons(hash: &str, profiles: &[Profile]) -> Vec<Vec<InlineKeyboardButton>> {
+  profiles
+    .chunks(2)
+    .enumerate()
+    .map(|(row, chunk)| {
+      chunk
+        .iter()
+        .enumerate()
+        .map(|(col, profile)| {
+          callback_data::button(
+            format!("⚙️ {}", profile.name),
+            format!("profile:{}:{hash}", row * 2 + col),
+          )
+        })
+        .collect()
+    })
+    .collect()
+}
+
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  db: Db,
+  parental: Parental,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  let Some((index, hash)) = q
+    .data
+    .as_deref()
+    .and_then(|data| data.strip_prefix("profile:"))
+    .and_then(|data| data.split_once(':'))
+  else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+  let Some(profile) = index
+    .parse::<usize>()
+    .ok()
+    .and_then(|index| config.profiles.get(index))
+  else {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("profile.gone"))
+      .await?;
+    return Ok(());
+  };
+
+  // restricted chats can't reach hidden torrents through buttons sent before the restriction
+  let chat = q
+    .message
+    .as_ref()
+    .map_or(ChatId(q.from.id.0 as i64), |msg| msg.chat.id);
+  let visible = torrent
+    .info(hash)
+    .await?
+    .is_some_and(|info| !parental.hides_labelled(chat, &info.category, &info.tags));
+  if !visible {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("profile.not_found"))
+      .await?;
+    return Ok(());
+  }
+
+  let reply = match profile.apply(&torrent, hash).await {
+    Ok(()) => {
+      let action = format!("applied the {} profile", profile.name);
+      audit::record(&db, &q.from, action, Some(hash));
+      lang.tf("profile.applied", &[("name", &profile.name)])
+    }
+    Err(err) => lang.tf(
+      "profile.failed",
+      &[("name", &profile.name), ("error", &err)],
+    ),
+  };
+  bot.answer_callback_query(q.id).text(reply).await?;
+  Ok(())
+}
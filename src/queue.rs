@@ -0,0 +1,114 @@
+use teloxide::{prelude::*, types::InlineKeyboardMarkup};
+
+use crate::{
+  audit, callback_data,
+  db::Db,
+  i18n::{self, Lang},
+  parental::Parental,
+  settings,
+  torrent::{ApiResult, ListQuery, TorrentApi},
+  HandlerResult,
+};
+
+/// The front of the download queue with buttons to move each torrent, `#1` starts next.
+async fn render(
+  torrent: &TorrentApi,
+  parental: &Parental,
+  chat: ChatId,
+  count: usize,
+  lang: Lang,
+) -> ApiResult<(String, InlineKeyboardMarkup)> {
+  let query = ListQuery {
+    sort: Some("priority".to_string()),
+    ..Default::default()
+  };
+  let queued: Vec<_> = torrent
+    .list_with(&query)
+    .await?
+    .into_iter()
+    .filter(|torrent| torrent.priority > 0)
+    .filter(|torrent| !parental.hides_labelled(chat, &torrent.category, &torrent.tags))
+    .take(count)
+    .collect();
+  if queued.is_empty() {
+    return Ok((
+      lang.t("queue.empty").to_string(),
+      InlineKeyboardMarkup::default(),
+    ));
+  }
+
+  let mut text = lang.t("queue.title").to_string();
+  let mut rows = Vec::new();
+  for torrent in &queued {
+    text.push_str(&format!(
+      "\n#{} {} ({:.0}%)",
+      torrent.priority,
+      torrent.name,
+      torrent.progress * 100.0
+    ));
+    let hash = &torrent.hash;
+    rows.push(vec![
+      callback_data::button(format!("#{}", torrent.priority), "queue:noop"),
+      callback_data::button("⏫", format!("queue:top:{hash}")),
+      callback_data::button("🔼", format!("queue:up:{hash}")),
+      callback_data::button("🔽", format!("queue:down:{hash}")),
+      callback_data::button("⏬", format!("queue:bottom:{hash}")),
+    ]);
+  }
+  Ok((text, InlineKeyboardMarkup::new(rows)))
+}
+
+/// `/queue` shows the queue order, which is what the top/up/down/bottom buttons change.
+pub async fn queue(
+  bot: Bot,
+  msg: Message,
+  torrent: TorrentApi,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let count = settings::of(&db, msg.from()).page_size as usize;
+  let (text, keyboard) = render(&torrent, &parental, msg.chat.id, count, lang).await?;
+  bot
+    .send_message(msg.chat.id, text)
+    .reply_markup(keyboard)
+    .await?;
+  Ok(())
+}
+
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  torrent: TorrentApi,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  bot.answer_callback_query(q.id).await?;
+  let mut parts = q.data.as_deref().unwrap_or_default().splitn(3, ':').skip(1);
+  let (Some(action), Some(hash), Some(msg)) = (parts.next(), parts.next(), q.message.as_ref())
+  else {
+    return Ok(());
+  };
+  match action {
+    "top" => torrent.set_queue_position(hash, true).await?,
+    "bottom" => torrent.set_queue_position(hash, false).await?,
+    "up" => torrent.step_queue_position(hash, true).await?,
+    "down" => torrent.step_queue_position(hash, false).await?,
+    _ => return Ok(()),
+  }
+  audit::record(
+    &db,
+    &q.from,
+    format!("moved a torrent {action} the queue"),
+    Some(hash),
+  );
+
+  let lang = i18n::lang(&db, Some(&q.from));
+  let count = settings::of(&db, Some(&q.from)).page_size as usize;
+  let (text, keyboard) = render(&torrent, &parental, msg.chat.id, count, lang).await?;
+  bot
+    .edit_message_text(msg.chat.id, msg.id, text)
+    .reply_markup(keyboard)
+    .await?;
+  Ok(())
+}
@@ -0,0 +1,194 @@
+use chrono::{NaiveTime, Utc};
+use std::time::Duration;
+use teloxide::prelude::*;
+
+use crate::{
+  audit,
+  auth::Access,
+  db::{Db, QuietHours},
+  format::StateGroup,
+  i18n::{self, Lang},
+  torrent::{events::TorrentEvents, ApiResult, TorrentApi},
+  HandlerResult,
+};
+
+// windows are set to the minute, so checking more often wouldn't start them any sooner
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+fn parse_time(text: &str) -> Option<NaiveTime> {
+  NaiveTime::parse_from_str(text, "%H:%M").ok()
+}
+
+/// Whether `now` falls in the window, which may span midnight.
+fn within(hours: &QuietHours, now: NaiveTime) -> bool {
+  let (Some(start), Some(end)) = (parse_time(&hours.start), parse_time(&hours.end)) else {
+    return false;
+  };
+  if start <= end {
+    start <= now && now < end
+  } else {
+    now >= start || now < end
+  }
+}
+
+/// Switches to the alternative limits, or pauses whatever is running, and notes what it did.
+async fn begin(
+  torrent: &TorrentApi,
+  events: &TorrentEvents,
+  hours: &mut QuietHours,
+) -> ApiResult<()> {
+  if hours.pause {
+    let running: Vec<String> = events
+      .torrents()
+      .borrow()
+      .values()
+      .filter(|torrent| StateGroup::of(&torrent.state) != StateGroup::Paused)
+      .map(|torrent| torrent.hash.clone())
+      .collect();
+    if !running.is_empty() {
+      torrent.pause(&running.join("|")).await?;
+    }
+    hours.paused = running;
+  } else if !torrent.alt_speed_enabled().await? {
+    torrent.toggle_alt_speed().await?;
+    hours.toggled_alt_speed = true;
+  }
+  hours.active = true;
+  Ok(())
+}
+
+/// Undoes [`begin`], leaving alone what was already paused or limited before the window.
+async fn finish(torrent: &TorrentApi, hours: &mut QuietHours) -> ApiResult<()> {
+  if !hours.paused.is_empty() {
+    torrent.resume(&hours.paused.join("|")).await?;
+  }
+  if hours.toggled_alt_speed && torrent.alt_speed_enabled().await? {
+    torrent.toggle_alt_speed().await?;
+  }
+  hours.active = false;
+  hours.toggled_alt_speed = false;
+  hours.paused.clear();
+  Ok(())
+}
+
+/// Starts and ends the quiet hours window. Progress is saved after each step, so a restart in
+/// the middle still restores everything afterwards.
+pub fn spawn(torrent: TorrentApi, db: Db, events: TorrentEvents) {
+  tokio::spawn(async move {
+    let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+      ticker.tick().await;
+      let mut hours = match db.quiet_hours() {
+        Ok(Some(hours)) => hours,
+        Ok(None) => continue,
+        Err(err) => {
+          log::error!("Could not load quiet hours: {err}");
+          continue;
+        }
+      };
+      let inside = within(&hours, Utc::now().time());
+      let res = match (inside, hours.active) {
+        (true, false) => begin(&torrent, &events, &mut hours).await,
+        (false, true) => finish(&torrent, &mut hours).await,
+        _ => continue,
+      };
+      if let Err(err) = res {
+        log::warn!("Could not switch quiet hours: {err}");
+        continue;
+      }
+      if let Err(err) = db.set_quiet_hours(&hours) {
+        log::error!("Could not save quiet hours: {err}");
+      }
+    }
+  });
+}
+
+fn describe(hours: Option<&QuietHours>, lang: Lang) -> String {
+  let Some(hours) = hours else {
+    return lang.t("quiet.off").to_string();
+  };
+  let mode = lang.t(if hours.pause {
+    "quiet.mode_pause"
+  } else {
+    "quiet.mode_altspeed"
+  });
+  let mut text = lang.tf(
+    "quiet.on",
+    &[
+      ("start", &hours.start),
+      ("end", &hours.end),
+      ("mode", &mode),
+    ],
+  );
+  if hours.active {
+    text.push_str(&format!("\n{}", lang.t("quiet.active")));
+  }
+  text
+}
+
+/// `/quiethours <HH:MM>-<HH:MM> [altspeed|pause]` sets the nightly window (UTC), `off` removes it
+/// and restores right away if it was in effect.
+pub async fn quiethours(
+  bot: Bot,
+  msg: Message,
+  args: String,
+  torrent: TorrentApi,
+  access: Access,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(user) = msg.from().filter(|user| access.is_admin(user.id)) else {
+    bot.send_message(msg.chat.id, lang.t("admin.only")).await?;
+    return Ok(());
+  };
+  let current = db.quiet_hours()?;
+  let mut words = args.split_whitespace();
+  let Some(window) = words.next() else {
+    let text = format!(
+      "{}\n\n{}",
+      describe(current.as_ref(), lang),
+      lang.t("quiet.usage")
+    );
+    bot.send_message(msg.chat.id, text).await?;
+    return Ok(());
+  };
+
+  if window == "off" {
+    if let Some(mut hours) = current.filter(|hours| hours.active) {
+      finish(&torrent, &mut hours).await?;
+    }
+    db.clear_quiet_hours()?;
+    audit::record(&db, user, "turned quiet hours off", None);
+    bot.send_message(msg.chat.id, describe(None, lang)).await?;
+    return Ok(());
+  }
+
+  let pause = match words.next() {
+    None | Some("altspeed") => false,
+    Some("pause") => true,
+    Some(_) => {
+      bot.send_message(msg.chat.id, lang.t("quiet.usage")).await?;
+      return Ok(());
+    }
+  };
+  let Some((start, end)) = window
+    .split_once('-')
+    .filter(|(start, end)| parse_time(start).is_some() && parse_time(end).is_some())
+  else {
+    bot.send_message(msg.chat.id, lang.t("quiet.usage")).await?;
+    return Ok(());
+  };
+  // keep what an active window changed, the scheduler undoes it with the new settings
+  let hours = QuietHours {
+    start: start.to_string(),
+    end: end.to_string(),
+    pause,
+    ..current.unwrap_or_default()
+  };
+  db.set_quiet_hours(&hours)?;
+  audit::record(&db, user, format!("set quiet hours to {start}-{end}"), None);
+  bot
+    .send_message(msg.chat.id, describe(Some(&hours), lang))
+    .await?;
+  Ok(())
+}
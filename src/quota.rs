@@ -0,0 +1,292 @@
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use std::sync::Arc;
+use teloxide::prelude::*;
+use tokio::sync::{broadcast::error::RecvError, watch};
+
+use crate::{
+  config::Config,
+  db::Db,
+  features::{Feature, Features},
+  format::format_bytes,
+  i18n::{self, Lang},
+  torrent::{
+    events::{TorrentEvent, TorrentEvents, TorrentSnapshot, Torrents},
+    TorrentApi,
+  },
+};
+
+pub const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Unix times the current day and month started at, in UTC.
+fn period_starts(now: DateTime<Utc>) -> (i64, i64) {
+  let day = Utc
+    .from_utc_datetime(&now.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default())
+    .timestamp();
+  let month = Utc
+    .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+    .single()
+    .map_or(day, |start| start.timestamp());
+  (day, month)
+}
+
+/// Whether `incoming` more bytes don't fit next to `used`, or the limit is used up already.
+fn goes_past(used: u64, incoming: u64, limit: u64) -> bool {
+  used >= limit || used.saturating_add(incoming) > limit
+}
+
+/// Per-user daily and monthly limits on the size of added torrents.
+///
+/// An add whose size is known up front (.torrent files, magnets with `xl=`) is turned down if
+/// it doesn't fit. Other adds are charged once qBittorrent has their metadata, and removed then
+/// if they don't fit after all. Adds without a known hash (plain .torrent URLs) are not tracked.
+#[derive(Clone)]
+pub struct Quotas {
+  db: Db,
+  config: Arc<Config>,
+  features: Features,
+  torrents: watch::Receiver<Torrents>,
+}
+
+/// A limit an add goes past.
+struct Exceeded {
+  key: &'static str,
+  limit: u64,
+  used: u64,
+}
+
+impl Quotas {
+  pub fn new(db: Db, config: Arc<Config>, features: Features, events: &TorrentEvents) -> Self {
+    Quotas {
+      db,
+      config,
+      features,
+      torrents: events.torrents(),
+    }
+  }
+
+  fn usage_since(&self, user_id: u64, since: i64) -> rusqlite::Result<u64> {
+    let torrents = self.torrents.borrow().clone();
+    let mut total = 0;
+    for (hash, bytes) in self.db.adds_since(user_id, since)? {
+      let bytes = match bytes {
+        Some(bytes) => bytes,
+        // missed while the bot was down, the torrent list still knows
+        None => match torrents.get(&hash).filter(|torrent| torrent.size > 0) {
+          Some(torrent) => {
+            self.db.set_added_size(&hash, torrent.size)?;
+            torrent.size
+          }
+          None => 0,
+        },
+      };
+      total += bytes;
+    }
+    Ok(total)
+  }
+
+  /// The first limit that `incoming` more bytes would go past, or that is used up already.
+  fn exceeded(&self, user_id: u64, incoming: u64) -> rusqlite::Result<Option<Exceeded>> {
+    let (day, month) = period_starts(Utc::now());
+    let limits = [
+      (self.config.quota.daily_gib, day, "quota.daily"),
+      (self.config.quota.monthly_gib, month, "quota.monthly"),
+    ];
+    for (limit, since, key) in limits {
+      let Some(limit) = limit.map(|gib| gib.saturating_mul(GIB)) else {
+        continue;
+      };
+      let used = self.usage_since(user_id, since)?;
+      if goes_past(used, incoming, limit) {
+        return Ok(Some(Exceeded { key, limit, used }));
+      }
+    }
+    Ok(None)
+  }
+
+  /// The limit `user_id` is already past, counting the adds just charged.
+  fn over(&self, user_id: u64) -> Option<Exceeded> {
+    match self.exceeded(user_id, 0) {
+      Ok(exceeded) => exceeded.filter(|exceeded| exceeded.used > exceeded.limit),
+      Err(err) => {
+        log::error!("Could not read quota usage: {err}");
+        None
+      }
+    }
+  }
+
+  fn explain(exceeded: &Exceeded, size: Option<u64>, lang: Lang) -> String {
+    let limit = format_bytes(exceeded.limit);
+    let used = format_bytes(exceeded.used);
+    match size {
+      Some(size) if exceeded.used < exceeded.limit => lang.tf(
+        &format!("{}_exceeds", exceeded.key),
+        &[
+          ("size", &format_bytes(size)),
+          ("limit", &limit),
+          ("used", &used),
+        ],
+      ),
+      _ => lang.tf(exceeded.key, &[("limit", &limit), ("used", &used)]),
+    }
+  }
+
+  /// `Err` carries the message for the user when `size` more bytes don't fit in a quota, or
+  /// when one is used up if the size isn't known yet.
+  pub fn check(&self, user_id: u64, size: Option<u64>, lang: Lang) -> Result<(), String> {
+    if !self.features.enabled(Feature::Quotas) {
+      return Ok(());
+    }
+    let exceeded = self.exceeded(user_id, size.unwrap_or(0)).map_err(|err| {
+      log::error!("Could not read quota usage: {err}");
+      lang.t("quota.error").to_string()
+    })?;
+    match exceeded {
+      Some(exceeded) => Err(Self::explain(&exceeded, size, lang)),
+      None => Ok(()),
+    }
+  }
+
+  pub fn record(&self, user_id: u64, hash: &str, size: Option<u64>) {
+    let size = size.filter(|size| *size > 0);
+    if let Err(err) = self
+      .db
+      .record_add(user_id, hash, Utc::now().timestamp(), size)
+    {
+      log::error!("Could not record the add for quotas: {err}");
+    }
+  }
+
+  /// Charges adds as soon as their size is known, even if the torrent is deleted right after,
+  /// and removes the ones that turn out not to fit.
+  pub fn spawn(&self, bot: Bot, torrent: TorrentApi, events: TorrentEvents) {
+    let quotas = self.clone();
+    let mut events = events.subscribe();
+    tokio::spawn(async move {
+      loop {
+        let snapshot = match events.recv().await {
+          Ok(TorrentEvent::MetadataReceived(snapshot)) => snapshot,
+          Ok(_) => continue,
+          Err(RecvError::Lagged(skipped)) => {
+            log::warn!("Quotas missed {skipped} events");
+            continue;
+          }
+          Err(RecvError::Closed) => break,
+        };
+        let users = match quotas.db.set_added_size(&snapshot.hash, snapshot.size) {
+          Ok(users) => users,
+          Err(err) => {
+            log::error!("Could not charge {} to its quota: {err}", snapshot.hash);
+            continue;
+          }
+        };
+        if !quotas.features.enabled(Feature::Quotas) {
+          continue;
+        }
+        // the add is part of the usage now, so it only has to fit
+        let over: Vec<(u64, Exceeded)> = users
+          .into_iter()
+          .filter_map(|user_id| Some((user_id, quotas.over(user_id)?)))
+          .collect();
+        if over.is_empty() {
+          continue;
+        }
+        // someone else who added it too and still fits keeps the torrent
+        let shared = match quotas.db.adders(&snapshot.hash) {
+          Ok(adders) => adders.into_iter().any(|adder| {
+            !over.iter().any(|(user_id, _)| *user_id == adder) && quotas.over(adder).is_none()
+          }),
+          Err(err) => {
+            log::error!("Could not list who added {}: {err}", snapshot.hash);
+            true
+          }
+        };
+        if !shared {
+          if let Err(err) = torrent.delete(&snapshot.hash, true).await {
+            log::error!("Could not remove {} over quota: {err}", snapshot.hash);
+            continue;
+          }
+        }
+        for (user_id, exceeded) in over {
+          quotas
+            .drop_add(&bot, user_id, &snapshot, &exceeded, shared)
+            .await;
+        }
+      }
+    });
+  }
+
+  /// Stops charging `user_id` for an add that didn't fit and tells them whether the torrent
+  /// was removed or stays for the others who added it.
+  async fn drop_add(
+    &self,
+    bot: &Bot,
+    user_id: u64,
+    snapshot: &TorrentSnapshot,
+    exceeded: &Exceeded,
+    shared: bool,
+  ) {
+    if let Err(err) = self.db.remove_add(user_id, &snapshot.hash) {
+      log::error!("Could not drop {} from the quota: {err}", snapshot.hash);
+    }
+    let lang = i18n::chat_lang(&self.db, ChatId(user_id as i64));
+    let exceeded = Exceeded {
+      used: exceeded.used.saturating_sub(snapshot.size),
+      ..*exceeded
+    };
+    let text = lang.tf(
+      if shared {
+        "quota.dropped"
+      } else {
+        "quota.removed"
+      },
+      &[
+        ("name", &snapshot.name),
+        (
+          "reason",
+          &Self::explain(&exceeded, Some(snapshot.size), lang),
+        ),
+      ],
+    );
+    // private chats share the user's id, which works once they have talked to the bot
+    if let Err(err) = bot.send_message(ChatId(user_id as i64), text).await {
+      log::warn!("Could not tell {user_id} about going over quota: {err}");
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn periods_start_at_midnight_and_the_first() {
+    let now = Utc.with_ymd_and_hms(2024, 3, 15, 13, 45, 10).unwrap();
+    let (day, month) = period_starts(now);
+    assert_eq!(
+      day,
+      Utc
+        .with_ymd_and_hms(2024, 3, 15, 0, 0, 0)
+        .unwrap()
+        .timestamp()
+    );
+    assert_eq!(
+      month,
+      Utc
+        .with_ymd_and_hms(2024, 3, 1, 0, 0, 0)
+        .unwrap()
+        .timestamp()
+    );
+
+    let first = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    assert_eq!(period_starts(first), (first.timestamp(), first.timestamp()));
+  }
+
+  #[test]
+  fn adds_must_fit() {
+    assert!(!goes_past(0, 10, 10));
+    assert!(goes_past(5, 6, 10));
+    assert!(goes_past(10, 0, 10));
+    assert!(!goes_past(9, 0, 10));
+    assert!(goes_past(1, u64::MAX, 10));
+  }
+}
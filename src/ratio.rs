@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use teloxide::prelude::*;
+
+use crate::{
+  db::Db,
+  format::split_message,
+  i18n,
+  parental::Parental,
+  settings,
+  torrent::{events::TorrentEvents, TorrentApi, TorrentInfo},
+  HandlerResult,
+};
+
+// torrents listed as the best and as the worst
+const SHOWN: usize = 5;
+
+/// Uploaded over downloaded, nothing before anything was downloaded.
+fn ratio_of(uploaded: u64, downloaded: u64) -> Option<f64> {
+  (downloaded > 0).then(|| uploaded as f64 / downloaded as f64)
+}
+
+fn format_ratio(ratio: Option<f64>) -> String {
+  match ratio {
+    Some(ratio) => format!("{ratio:.2}"),
+    None => "—".to_string(),
+  }
+}
+
+fn totals<'a>(torrents: impl IntoIterator<Item = &'a TorrentInfo>) -> (u64, u64) {
+  torrents.into_iter().fold((0, 0), |(up, down), torrent| {
+    (up + torrent.uploaded, down + torrent.downloaded)
+  })
+}
+
+/// `/ratio` sums up seeding: the global ratio, one per category and the best and worst torrents.
+pub async fn ratio(
+  bot: Bot,
+  msg: Message,
+  torrent: TorrentApi,
+  events: TorrentEvents,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let units = settings::of(&db, msg.from()).units();
+  let mut torrents: Vec<_> = torrent
+    .list()
+    .await?
+    .into_iter()
+    .filter(|torrent| !parental.hides_labelled(msg.chat.id, &torrent.category, &torrent.tags))
+    .collect();
+  if torrents.is_empty() {
+    bot.send_message(msg.chat.id, lang.t("top.empty")).await?;
+    return Ok(());
+  }
+
+  // qBittorrent's own numbers cover removed torrents too, unless the chat must not see them all
+  let state = events.server_state().borrow().clone();
+  let (ratio, uploaded, downloaded) =
+    if parental.is_restricted(msg.chat.id) || state.global_ratio.is_empty() {
+      let (up, down) = totals(&torrents);
+      (format_ratio(ratio_of(up, down)), up, down)
+    } else {
+      (
+        state.global_ratio.clone(),
+        state.alltime_ul,
+        state.alltime_dl,
+      )
+    };
+  let mut text = lang.tf(
+    "ratio.global",
+    &[
+      ("ratio", &ratio),
+      ("uploaded", &units.bytes(uploaded)),
+      ("downloaded", &units.bytes(downloaded)),
+    ],
+  );
+
+  let mut categories: BTreeMap<&str, Vec<&TorrentInfo>> = BTreeMap::new();
+  for torrent in &torrents {
+    categories
+      .entry(&torrent.category)
+      .or_default()
+      .push(torrent);
+  }
+  text.push_str(&format!("\n\n{}", lang.t("ratio.by_category")));
+  for (category, members) in &categories {
+    let (up, down) = totals(members.iter().copied());
+    let name = match *category {
+      "" => lang.t("labels.uncategorized"),
+      category => category,
+    };
+    text.push_str(&format!(
+      "\n• {name}: {} ({})",
+      format_ratio(ratio_of(up, down)),
+      members.len()
+    ));
+  }
+
+  torrents.sort_by(|a, b| b.ratio.total_cmp(&a.ratio));
+  // with few torrents the worst ones are whatever the best didn't take
+  let worst = SHOWN.min(torrents.len().saturating_sub(SHOWN));
+  text.push_str(&format!("\n\n{}", lang.t("ratio.best")));
+  for torrent in torrents.iter().take(SHOWN) {
+    text.push_str(&format!("\n• {:.2} {}", torrent.ratio, torrent.name));
+  }
+  if worst > 0 {
+    text.push_str(&format!("\n\n{}", lang.t("ratio.worst")));
+    for torrent in torrents.iter().rev().take(worst) {
+      text.push_str(&format!("\n• {:.2} {}", torrent.ratio, torrent.name));
+    }
+  }
+
+  for piece in split_message(&text) {
+    bot.send_message(msg.chat.id, piece).await?;
+  }
+  Ok(())
+}
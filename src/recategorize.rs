@@ -0,0 +1,224 @@
+use regex::Regex;
+use teloxide::prelude::*;
+
+use crate::{
+  audit,
+  db::Db,
+  i18n::{self, Lang},
+  parental::Parental,
+  torrent::{TorrentApi, TorrentInfo},
+  HandlerResult,
+};
+
+// hashes sent to qBittorrent per request, progress is reported after each batch
+const BATCH_SIZE: usize = 50;
+
+enum Filter {
+  Tracker(String),
+  Name(Regex),
+  Category(String),
+}
+
+impl Filter {
+  fn matches(&self, torrent: &TorrentInfo) -> bool {
+    match self {
+      Filter::Tracker(domain) => {
+        torrent.tracker.contains(domain.as_str()) || torrent.magnet_uri.contains(domain.as_str())
+      }
+      Filter::Name(regex) => regex.is_match(&torrent.name),
+      Filter::Category(category) => torrent.category == *category,
+    }
+  }
+}
+
+struct Request {
+  filters: Vec<Filter>,
+  target: String,
+  move_data: bool,
+}
+
+fn parse(args: &str, lang: Lang) -> Result<Request, String> {
+  let mut filters = Vec::new();
+  let mut target = None;
+  let mut move_data = false;
+
+  for token in args.split_whitespace() {
+    match token.split_once(':') {
+      Some(("tracker", domain)) => filters.push(Filter::Tracker(domain.to_lowercase())),
+      Some(("name", pattern)) => {
+        let regex = Regex::new(&format!("(?i){pattern}"))
+          .map_err(|err| lang.tf("recategorize.bad_regex", &[("error", &err)]))?;
+        filters.push(Filter::Name(regex));
+      }
+      Some(("category", category)) => filters.push(Filter::Category(category.to_string())),
+      _ if token == "move" && target.is_some() => move_data = true,
+      _ if target.is_none() => target = Some(token.to_string()),
+      _ => return Err(lang.tf("recategorize.unexpected", &[("argument", &token)])),
+    }
+  }
+
+  match (filters.is_empty(), target) {
+    (false, Some(target)) => Ok(Request {
+      filters,
+      target,
+      move_data,
+    }),
+    _ => Err(lang.t("recategorize.usage").to_string()),
+  }
+}
+
+pub async fn recategorize(
+  bot: Bot,
+  msg: Message,
+  args: String,
+  torrent: TorrentApi,
+  db: Db,
+  parental: Parental,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let request = match parse(&args, lang) {
+    Ok(request) => request,
+    Err(err) => {
+      bot.send_message(msg.chat.id, err).await?;
+      return Ok(());
+    }
+  };
+
+  let hashes: Vec<String> = torrent
+    .list()
+    .await?
+    .into_iter()
+    // hidden torrents are out of reach for restricted chats, even in bulk
+    .filter(|info| !parental.hides_labelled(msg.chat.id, &info.category, &info.tags))
+    .filter(|info| request.filters.iter().all(|filter| filter.matches(info)))
+    .map(|info| info.hash)
+    .collect();
+  if hashes.is_empty() {
+    bot
+      .send_message(msg.chat.id, lang.t("recategorize.none"))
+      .await?;
+    return Ok(());
+  }
+
+  torrent.create_category(&request.target).await?;
+  let progress = bot
+    .send_message(
+      msg.chat.id,
+      lang.tf(
+        "recategorize.moving",
+        &[("count", &hashes.len()), ("category", &request.target)],
+      ),
+    )
+    .await?;
+
+  let mut done = 0;
+  for batch in hashes.chunks(BATCH_SIZE) {
+    torrent.set_category(batch, &request.target).await?;
+    if request.move_data {
+      torrent.set_auto_management(batch, true).await?;
+    }
+    done += batch.len();
+    if done < hashes.len() {
+      bot
+        .edit_message_text(
+          msg.chat.id,
+          progress.id,
+          lang.tf(
+            "recategorize.progress",
+            &[
+              ("done", &done),
+              ("count", &hashes.len()),
+              ("category", &request.target),
+            ],
+          ),
+        )
+        .await?;
+    }
+  }
+
+  if let Some(user) = msg.from() {
+    let action = format!("moved {done} torrent(s) to {}", request.target);
+    audit::record(&db, user, action, None);
+  }
+  bot
+    .edit_message_text(
+      msg.chat.id,
+      progress.id,
+      lang.tf(
+        if request.move_data {
+          "recategorize.done_relocating"
+        } else {
+          "recategorize.done"
+        },
+        &[("count", &done), ("category", &request.target)],
+      ),
+    )
+    .await?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn torrent(name: &str, category: &str, tracker: &str) -> TorrentInfo {
+    serde_json::from_value(serde_json::json!({
+      "hash": "abc",
+      "name": name,
+      "state": "uploading",
+      "progress": 1.0,
+      "size": 0,
+      "dlspeed": 0,
+      "upspeed": 0,
+      "eta": 0,
+      "ratio": 0.0,
+      "added_on": 0,
+      "category": category,
+      "tracker": tracker,
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn parses_filters_target_and_move() {
+    let request = parse("tracker:Example.org category:tv Series move", Lang::En).unwrap();
+    assert_eq!(request.filters.len(), 2);
+    assert_eq!(request.target, "Series");
+    assert!(request.move_data);
+
+    let request = parse("name:foo Films", Lang::En).unwrap();
+    assert_eq!(request.target, "Films");
+    assert!(!request.move_data);
+  }
+
+  #[test]
+  fn rejects_bad_arguments() {
+    let err = parse("category:tv Series extra", Lang::En).err().unwrap();
+    assert_eq!(err, "Unexpected argument: extra");
+    // "move" only counts once the target is known
+    assert!(parse("category:tv move Series", Lang::En).is_err());
+    assert!(parse("Series", Lang::En).is_err());
+    assert!(parse("category:tv", Lang::En).is_err());
+    assert!(parse("name:( Series", Lang::En).is_err());
+  }
+
+  #[test]
+  fn filters_match() {
+    let info = torrent(
+      "Some.Show.S01",
+      "tv",
+      "https://tracker.example.org/announce",
+    );
+    let request = parse("tracker:EXAMPLE.org name:show category:tv Series", Lang::En).unwrap();
+    assert!(request.filters.iter().all(|filter| filter.matches(&info)));
+
+    for args in [
+      "tracker:other.org Series",
+      "name:^show Series",
+      "category:films Series",
+    ] {
+      let request = parse(args, Lang::En).unwrap();
+      assert!(!request.filters[0].matches(&info), "{args}");
+    }
+  }
+}
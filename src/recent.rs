@@ -0,0 +1,86 @@
+use chrono::Utc;
+use teloxide::{prelude::*, types::ParseMode};
+
+use crate::{
+  db::Db,
+  format::{format_duration, format_timestamp, split_message},
+  i18n,
+  parental::Parental,
+  render, settings,
+  torrent::{ListQuery, TorrentApi},
+  HandlerResult,
+};
+
+/// `/recent` periods as shown and in seconds.
+fn period(arg: &str) -> Option<(&'static str, i64)> {
+  match arg {
+    "" | "24h" | "day" => Some(("24h", 24 * 60 * 60)),
+    "7d" | "week" => Some(("7d", 7 * 24 * 60 * 60)),
+    _ => None,
+  }
+}
+
+/// `/recent [24h|7d]` lists the torrents added in that period, newest first, as they are now.
+pub async fn recent(
+  bot: Bot,
+  msg: Message,
+  arg: String,
+  torrent: TorrentApi,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let arg = arg.trim().to_lowercase();
+  let Some((period, seconds)) = period(&arg) else {
+    bot
+      .send_message(msg.chat.id, lang.t("recent.usage"))
+      .await?;
+    return Ok(());
+  };
+
+  let now = Utc::now().timestamp();
+  let query = ListQuery {
+    sort: Some("added_on".to_string()),
+    reverse: true,
+    ..Default::default()
+  };
+  let torrents: Vec<_> = torrent
+    .list_with(&query)
+    .await?
+    .into_iter()
+    .take_while(|torrent| torrent.added_on >= now - seconds)
+    .filter(|torrent| !parental.hides_labelled(msg.chat.id, &torrent.category, &torrent.tags))
+    .collect();
+  if torrents.is_empty() {
+    bot
+      .send_message(msg.chat.id, lang.tf("recent.empty", &[("period", &period)]))
+      .await?;
+    return Ok(());
+  }
+
+  let settings = settings::of(&db, msg.from());
+  let mut text = lang.tf(
+    "recent.title",
+    &[("count", &torrents.len()), ("period", &period)],
+  );
+  for torrent in &torrents {
+    text.push_str(&format!(
+      "\n\n{}\n   {}",
+      render::torrent_item(torrent, settings.detailed_list, settings.units()),
+      lang.tf(
+        "recent.added",
+        &[
+          ("at", &format_timestamp(torrent.added_on, settings.tz())),
+          ("ago", &format_duration(now - torrent.added_on))
+        ]
+      )
+    ));
+  }
+  for piece in split_message(&text) {
+    bot
+      .send_message(msg.chat.id, piece)
+      .parse_mode(ParseMode::Html)
+      .await?;
+  }
+  Ok(())
+}
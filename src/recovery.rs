@@ -0,0 +1,137 @@
+use serde::Deserialize;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::time::Instant;
+
+use crate::{
+  config::Config,
+  features::Features,
+  notify::{Notifiers, NotifyKind},
+  torrent::{
+    events::{TorrentEvents, TorrentSnapshot},
+    TorrentApi,
+  },
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How stalled downloads are brought back: reannounced, then force-resumed, then reported.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecoveryRule {
+  /// Only torrents in this category, any when unset.
+  #[serde(default)]
+  pub category: Option<String>,
+  /// Minutes before each step, counted from the previous one.
+  #[serde(default = "default_after_mins")]
+  pub after_mins: u64,
+}
+
+fn default_after_mins() -> u64 {
+  15
+}
+
+impl RecoveryRule {
+  fn step_after(&self) -> Duration {
+    Duration::from_secs(self.after_mins.max(1) * 60)
+  }
+}
+
+fn rule_for<'a>(rules: &'a [RecoveryRule], category: &str) -> Option<&'a RecoveryRule> {
+  rules
+    .iter()
+    .find(|rule| rule.category.as_deref() == Some(category))
+    .or_else(|| rules.iter().find(|rule| rule.category.is_none()))
+}
+
+/// What has been tried on a stalled torrent so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Step {
+  Waiting,
+  Reannounced,
+  ForceResumed,
+  Reported,
+}
+
+struct Stalled {
+  step: Step,
+  since: Instant,
+}
+
+/// Walks stalled downloads through the recovery steps of their rule. A torrent that moves again
+/// starts over, and one that was force-resumed goes back under the queue limits.
+pub fn spawn(
+  torrent: TorrentApi,
+  notifier: Notifiers,
+  config: Arc<Config>,
+  features: Features,
+  events: TorrentEvents,
+) {
+  if config.recovery.is_empty() {
+    return;
+  }
+  let torrents = events.torrents();
+  tokio::spawn(async move {
+    let mut stalled: HashMap<String, Stalled> = HashMap::new();
+    let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+      ticker.tick().await;
+      let current = torrents.borrow().clone();
+
+      let recovered: Vec<(String, Step)> = stalled
+        .iter()
+        .filter(|(hash, _)| !current.get(*hash).is_some_and(TorrentSnapshot::is_stalled))
+        .map(|(hash, stalled)| (hash.clone(), stalled.step))
+        .collect();
+      for (hash, step) in recovered {
+        stalled.remove(&hash);
+        if step >= Step::ForceResumed && current.contains_key(&hash) {
+          if let Err(err) = torrent.set_force_start(&hash, false).await {
+            log::warn!("Could not end the force start of {hash}: {err}");
+          }
+        }
+      }
+      if features.in_maintenance() {
+        continue;
+      }
+
+      for snapshot in current.values().filter(|torrent| torrent.is_stalled()) {
+        let Some(rule) = rule_for(&config.recovery, &snapshot.category) else {
+          continue;
+        };
+        let entry = stalled.entry(snapshot.hash.clone()).or_insert(Stalled {
+          step: Step::Waiting,
+          since: Instant::now(),
+        });
+        if entry.step == Step::Reported || entry.since.elapsed() < rule.step_after() {
+          continue;
+        }
+
+        let res = match entry.step {
+          Step::Waiting => torrent
+            .reannounce(&snapshot.hash)
+            .await
+            .map(|()| Step::Reannounced),
+          Step::Reannounced => torrent
+            .set_force_start(&snapshot.hash, true)
+            .await
+            .map(|()| Step::ForceResumed),
+          Step::ForceResumed | Step::Reported => {
+            let text = format!(
+              "🛟 {} is still stalled after a reannounce and a force resume",
+              snapshot.name
+            );
+            notifier.send(NotifyKind::Errors, &text).await;
+            Ok(Step::Reported)
+          }
+        };
+        match res {
+          Ok(step) => {
+            log::info!("Recovery of {} is at {step:?}", snapshot.name);
+            entry.step = step;
+            entry.since = Instant::now();
+          }
+          Err(err) => log::warn!("Could not recover {}: {err}", snapshot.name),
+        }
+      }
+    }
+  });
+}
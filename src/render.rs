@@ -0,0 +1,96 @@
+//! Building blocks for messages sent with `ParseMode::Html`. Anything that came from users or
+//! qBittorrent goes through [`escape`], translated templates are plain text and can be used as is.
+
+use crate::{
+  format::{format_eta, progress_bar, StateGroup, Units},
+  torrent::{TorrentFile, TorrentInfo, PRIORITY_NORMAL, PRIORITY_SKIP},
+};
+
+pub fn escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+}
+
+pub fn bold(text: &str) -> String {
+  format!("<b>{}</b>", escape(text))
+}
+
+pub fn italic(text: &str) -> String {
+  format!("<i>{}</i>", escape(text))
+}
+
+/// Monospace, so hashes can be copied with a tap.
+pub fn code(text: &str) -> String {
+  format!("<code>{}</code>", escape(text))
+}
+
+pub fn pre(text: &str) -> String {
+  format!("<pre>{}</pre>", escape(text))
+}
+
+/// The first 8 characters, enough for every command that takes a hash prefix.
+pub fn short_hash(hash: &str) -> String {
+  code(hash.get(..8).unwrap_or(hash))
+}
+
+/// One torrent: state emoji, queue position and name, then progress, speeds and ETA. `detailed`
+/// adds size, ratio, peers and the short hash.
+pub fn torrent_item(torrent: &TorrentInfo, detailed: bool, units: Units) -> String {
+  let queued = if torrent.priority > 0 {
+    format!("#{} ", torrent.priority)
+  } else {
+    String::new()
+  };
+  let mut item = format!(
+    "{} {queued}{}\n   {} {:.0}% · ↓ {} ↑ {} · ETA {}",
+    StateGroup::of(&torrent.state).emoji(),
+    bold(&torrent.name),
+    progress_bar(torrent.progress),
+    torrent.progress * 100.0,
+    units.speed(torrent.dlspeed),
+    units.speed(torrent.upspeed),
+    format_eta(torrent.eta)
+  );
+  if detailed {
+    item.push_str(&format!(
+      "\n   {} · ratio {:.2} · {} peers · {}",
+      units.bytes(torrent.size),
+      torrent.ratio,
+      torrent.num_seeds + torrent.num_leechs,
+      short_hash(&torrent.hash)
+    ));
+  }
+  item
+}
+
+// keeps a page of 50 files well under Telegram's 4096 characters
+const MAX_FILE_NAME_CHARS: usize = 40;
+
+/// One file with its download state, long paths are cut from the front.
+pub fn file_line(file: &TorrentFile, units: Units) -> String {
+  let mark = match file.priority {
+    PRIORITY_SKIP => "⏭",
+    _ if file.progress >= 1.0 => "✅",
+    PRIORITY_NORMAL => "⬇️",
+    _ => "⏫",
+  };
+  let count = file.name.chars().count();
+  let name = if count <= MAX_FILE_NAME_CHARS {
+    file.name.clone()
+  } else {
+    let tail: String = file
+      .name
+      .chars()
+      .skip(count - MAX_FILE_NAME_CHARS + 1)
+      .collect();
+    format!("…{tail}")
+  };
+  format!(
+    "{mark} {} — {} · {:.0}%",
+    escape(&name),
+    units.bytes(file.size),
+    file.progress * 100.0
+  )
+}
@@ -0,0 +1,154 @@
+use teloxide::{prelude::*, types::ParseMode};
+
+use crate::{
+  audit,
+  db::Db,
+  delete, files,
+  i18n::{self, Lang},
+  parental::Parental,
+  settings,
+  torrent::{
+    events::{TorrentEvents, TorrentSnapshot},
+    TorrentApi,
+  },
+  HandlerResult, MyDialogue,
+};
+
+/// What a one-word reply to a bot message asks for.
+#[derive(Debug, Clone, Copy)]
+enum Action {
+  Pause,
+  Resume,
+  Delete,
+  Files,
+}
+
+/// `pause`, optionally followed by part of a name when the replied message lists several torrents.
+fn parse(text: &str) -> Option<(Action, String)> {
+  let text = text.trim().to_lowercase();
+  let (word, rest) = text.split_once(' ').unwrap_or((text.as_str(), ""));
+  let action = match word {
+    "pause" | "stop" => Action::Pause,
+    "resume" | "start" => Action::Resume,
+    "delete" => Action::Delete,
+    "files" => Action::Files,
+    _ => return None,
+  };
+  Some((action, rest.trim().to_string()))
+}
+
+/// Replies to one of our messages that start with an action word.
+pub fn is_action(msg: Message) -> bool {
+  msg
+    .reply_to_message()
+    .is_some_and(|replied| replied.from().is_some_and(|user| user.is_bot))
+    && msg.text().and_then(parse).is_some()
+}
+
+/// Torrents named in `text` or with their short hash in it, narrowed down by `hint`.
+fn mentioned<'a>(
+  torrents: &[&'a TorrentSnapshot],
+  text: &str,
+  hint: &str,
+) -> Vec<&'a TorrentSnapshot> {
+  torrents
+    .iter()
+    .filter(|torrent| {
+      let short = torrent.hash.get(..8).unwrap_or(&torrent.hash);
+      text.contains(&torrent.name) || text.contains(short)
+    })
+    .filter(|torrent| hint.is_empty() || torrent.name.to_lowercase().contains(hint))
+    .copied()
+    .collect()
+}
+
+async fn ambiguous(bot: &Bot, msg: &Message, names: Vec<String>, lang: Lang) -> HandlerResult {
+  let mut text = lang.t("reply.ambiguous").to_string();
+  for name in names {
+    text.push_str(&format!("\n• {name}"));
+  }
+  bot.send_message(msg.chat.id, text).await?;
+  Ok(())
+}
+
+/// Runs the action on the torrent the replied message is about, so nobody has to copy a hash.
+pub async fn act(
+  bot: Bot,
+  msg: Message,
+  dialogue: MyDialogue,
+  torrent: TorrentApi,
+  events: TorrentEvents,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let (Some((action, hint)), Some(replied), Some(user)) = (
+    msg.text().and_then(parse),
+    msg.reply_to_message().and_then(Message::text),
+    msg.from(),
+  ) else {
+    return Ok(());
+  };
+  let targets: Vec<TorrentSnapshot> = {
+    let torrents = events.torrents();
+    let current = torrents.borrow();
+    let visible: Vec<_> = current
+      .values()
+      .filter(|torrent| !parental.hides(msg.chat.id, torrent))
+      .collect();
+    mentioned(&visible, replied, &hint)
+      .into_iter()
+      .cloned()
+      .collect()
+  };
+  let target = match targets.as_slice() {
+    [target] => target,
+    [] => {
+      bot
+        .send_message(msg.chat.id, lang.t("reply.not_found"))
+        .await?;
+      return Ok(());
+    }
+    _ => {
+      let names = targets.iter().map(|torrent| torrent.name.clone()).collect();
+      return ambiguous(&bot, &msg, names, lang).await;
+    }
+  };
+
+  match action {
+    Action::Pause => {
+      torrent.pause(&target.hash).await?;
+      audit::record(&db, user, "paused a torrent", Some(&target.hash));
+      bot.send_message(msg.chat.id, lang.t("find.paused")).await?;
+    }
+    Action::Resume => {
+      torrent.resume(&target.hash).await?;
+      audit::record(&db, user, "resumed a torrent", Some(&target.hash));
+      bot
+        .send_message(msg.chat.id, lang.t("find.resumed"))
+        .await?;
+    }
+    Action::Delete => {
+      delete::ask(
+        &bot,
+        &dialogue,
+        msg.chat.id,
+        &target.hash,
+        &target.name,
+        lang,
+      )
+      .await?;
+    }
+    Action::Files => {
+      let settings = settings::of(&db, Some(user));
+      let (text, keyboard) =
+        files::page(&torrent, &target.hash, &target.name, 0, &settings, lang).await?;
+      bot
+        .send_message(msg.chat.id, text)
+        .parse_mode(ParseMode::Html)
+        .reply_markup(keyboard)
+        .await?;
+    }
+  }
+  Ok(())
+}
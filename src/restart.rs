@@ -0,0 +1,99 @@
+use std::{sync::Arc, time::Duration};
+use teloxide::prelude::*;
+use tokio::process::Command;
+
+use crate::{
+  audit,
+  auth::Access,
+  config::Config,
+  db::Db,
+  i18n::{self, Lang},
+  torrent::TorrentApi,
+  HandlerResult,
+};
+
+// a hook that hangs (e.g. waiting for a sudo password) is killed rather than waited on forever
+const HOOK_TIMEOUT: Duration = Duration::from_secs(60);
+// how long qBittorrent gets to come back before we give up waiting
+const COME_BACK_TIMEOUT: Duration = Duration::from_secs(90);
+const POLL_EVERY: Duration = Duration::from_secs(3);
+
+/// Shuts qBittorrent down and, with `restart_command` configured, starts it again.
+pub async fn qbrestart(
+  bot: Bot,
+  msg: Message,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  access: Access,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(user) = msg.from().filter(|user| access.is_admin(user.id)) else {
+    bot.send_message(msg.chat.id, lang.t("admin.only")).await?;
+    return Ok(());
+  };
+
+  audit::record(&db, user, "restarted qBittorrent", None);
+  if let Err(err) = torrent.shutdown().await {
+    // a wedged qBittorrent may not answer at all, the restart hook can still fix it
+    log::warn!("qBittorrent shutdown failed: {err}");
+  }
+
+  let Some(command) = config.restart_command.as_deref() else {
+    bot
+      .send_message(msg.chat.id, lang.t("restart.shutdown_only"))
+      .await?;
+    return Ok(());
+  };
+
+  let progress = bot
+    .send_message(msg.chat.id, lang.t("restart.running"))
+    .await?;
+  // the command and the wait can take minutes, which would hold up this chat's other updates
+  let command = command.to_string();
+  tokio::spawn(async move {
+    let reply = restart(&torrent, &command, lang).await;
+    if let Err(err) = bot.edit_message_text(msg.chat.id, progress.id, reply).await {
+      log::warn!("Could not report the qBittorrent restart: {err}");
+    }
+  });
+  Ok(())
+}
+
+/// Runs `command` and waits for qBittorrent to answer again, returning what to tell the admin.
+async fn restart(torrent: &TorrentApi, command: &str, lang: Lang) -> String {
+  let hook = Command::new("sh")
+    .arg("-c")
+    .arg(command)
+    .kill_on_drop(true)
+    .output();
+  let Ok(output) = tokio::time::timeout(HOOK_TIMEOUT, hook).await else {
+    log::error!("The restart command did not finish within {HOOK_TIMEOUT:?}");
+    return lang.tf("restart.hook_timeout", &[("secs", &HOOK_TIMEOUT.as_secs())]);
+  };
+  let failure = match output {
+    Ok(output) if output.status.success() => None,
+    Ok(output) => Some(format!(
+      "{}: {}",
+      output.status,
+      String::from_utf8_lossy(&output.stderr).trim()
+    )),
+    Err(err) => Some(err.to_string()),
+  };
+  if let Some(error) = failure {
+    return lang.tf("restart.failed", &[("error", &error)]);
+  }
+
+  let deadline = tokio::time::Instant::now() + COME_BACK_TIMEOUT;
+  loop {
+    tokio::time::sleep(POLL_EVERY).await;
+    // the new process has a fresh session, so log in again before asking
+    let _ = torrent.login().await;
+    if let Ok(version) = torrent.ping().await {
+      return lang.tf("restart.done", &[("version", &version)]);
+    }
+    if tokio::time::Instant::now() >= deadline {
+      return lang.t("restart.timeout").to_string();
+    }
+  }
+}
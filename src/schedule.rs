@@ -0,0 +1,267 @@
+use chrono::{DateTime, Duration as ChronoDuration, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::{sync::Arc, time::Duration};
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardButton, InlineKeyboardMarkup},
+};
+
+use crate::{
+  audit, blocked, callback_data,
+  config::Config,
+  db::{Db, ScheduledAdd},
+  duplicates,
+  format::format_timestamp,
+  guard::{self, Candidate},
+  i18n::{self, Lang},
+  parental::{self, Parental},
+  quota::Quotas,
+  settings,
+  torrent::{self, AddTorrentArg, TorrentApi},
+  within_quota, HandlerResult,
+};
+
+// times are set to the minute, so checking more often wouldn't add anything sooner
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// When `text` means: `HH:MM` is its next occurrence in the user's time zone, `30m`, `2h` or
+/// `1d` count from now.
+fn parse_when(text: &str, timezone: Tz, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+  if let Ok(time) = NaiveTime::parse_from_str(text, "%H:%M") {
+    let today = now.with_timezone(&timezone).date_naive();
+    let at = |day: chrono::NaiveDate| {
+      timezone
+        .from_local_datetime(&day.and_time(time))
+        .earliest()
+        .map(|at| at.with_timezone(&Utc))
+    };
+    return match at(today)? {
+      at if at > now => Some(at),
+      _ => at(today.succ_opt()?),
+    };
+  }
+  let split = text.find(|c: char| !c.is_ascii_digit())?;
+  let (amount, unit) = text.split_at(split);
+  let amount: i64 = amount.parse().ok().filter(|amount| *amount > 0)?;
+  let delay = match unit {
+    "m" | "min" => ChronoDuration::try_minutes(amount)?,
+    "h" => ChronoDuration::try_hours(amount)?,
+    "d" => ChronoDuration::try_days(amount)?,
+    _ => return None,
+  };
+  now.checked_add_signed(delay)
+}
+
+/// The magnet's display name, else its hash, for lists and notifications.
+fn name(link: &str) -> String {
+  parental::magnet_name(link)
+    .or_else(|| torrent::extract_info_hash(link))
+    .unwrap_or_else(|| link.to_string())
+}
+
+fn cancel_button(pending: &ScheduledAdd) -> InlineKeyboardButton {
+  callback_data::button(
+    format!("✖️ {}", name(&pending.link)),
+    format!("later:cancel:{}", pending.id),
+  )
+}
+
+fn queue_keyboard(pending: &[ScheduledAdd]) -> InlineKeyboardMarkup {
+  InlineKeyboardMarkup::new(pending.iter().map(|pending| vec![cancel_button(pending)]))
+}
+
+fn queue_text(pending: &[ScheduledAdd], timezone: Tz, lang: Lang) -> String {
+  if pending.is_empty() {
+    return lang.t("later.empty").to_string();
+  }
+  let mut text = lang.t("later.title").to_string();
+  for (i, pending) in pending.iter().enumerate() {
+    text.push_str(&format!(
+      "\n{}. {} — {}",
+      i + 1,
+      name(&pending.link),
+      format_timestamp(pending.at, timezone)
+    ));
+  }
+  text
+}
+
+/// `/addlater <HH:MM|30m|2h|1d> <magnet>` stores the magnet until then, without arguments it
+/// shows the pending adds with buttons to cancel them.
+pub async fn addlater(
+  bot: Bot,
+  msg: Message,
+  args: String,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(user) = msg.from() else {
+    return Ok(());
+  };
+  let timezone = settings::of(&db, Some(user)).tz();
+  let mut words = args.split_whitespace();
+  let (Some(when), Some(link)) = (words.next(), words.next()) else {
+    let pending = db.scheduled_adds(Some(user.id.0), i64::MAX)?;
+    let text = format!(
+      "{}\n\n{}",
+      queue_text(&pending, timezone, lang),
+      lang.t("later.usage")
+    );
+    bot
+      .send_message(msg.chat.id, text)
+      .reply_markup(queue_keyboard(&pending))
+      .await?;
+    return Ok(());
+  };
+  let Some(at) = parse_when(when, timezone, Utc::now()) else {
+    bot.send_message(msg.chat.id, lang.t("later.usage")).await?;
+    return Ok(());
+  };
+  let Some(link) =
+    torrent::hash_magnet(link).or_else(|| link.starts_with("magnet:").then(|| link.to_string()))
+  else {
+    bot.send_message(msg.chat.id, lang.t("later.usage")).await?;
+    return Ok(());
+  };
+  // checked now so nobody gets a surprise later, the quota is checked again when it's added
+  if blocked(&bot, &msg, &parental, lang, parental::magnet_name(&link)).await? {
+    return Ok(());
+  }
+  if !within_quota(&bot, &msg, &quotas, lang, duplicates::magnet_size(&link)).await? {
+    return Ok(());
+  }
+
+  let id = db.schedule_add(user.id.0, msg.chat.id.0, &link, at.timestamp())?;
+  audit::record(
+    &db,
+    user,
+    format!(
+      "scheduled an add for {}",
+      format_timestamp(at.timestamp(), Tz::UTC)
+    ),
+    torrent::extract_info_hash(&link).as_deref(),
+  );
+  let pending = ScheduledAdd {
+    id,
+    user_id: user.id.0,
+    chat_id: msg.chat.id.0,
+    link,
+    at: at.timestamp(),
+  };
+  let text = lang.tf(
+    "later.scheduled",
+    &[
+      ("name", &name(&pending.link)),
+      ("at", &format_timestamp(pending.at, timezone)),
+    ],
+  );
+  bot
+    .send_message(msg.chat.id, text)
+    .reply_markup(InlineKeyboardMarkup::new([[cancel_button(&pending)]]))
+    .await?;
+  Ok(())
+}
+
+pub async fn callback(bot: Bot, q: CallbackQuery, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  let Some(id) = q
+    .data
+    .as_deref()
+    .and_then(|data| data.strip_prefix("later:cancel:"))
+    .and_then(|id| id.parse().ok())
+  else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+
+  let cancelled = db.cancel_scheduled_add(id, q.from.id.0)?;
+  if cancelled {
+    audit::record(&db, &q.from, "cancelled a scheduled add", None);
+  }
+  bot
+    .answer_callback_query(q.id)
+    .text(lang.t(if cancelled {
+      "later.cancelled"
+    } else {
+      "later.gone"
+    }))
+    .await?;
+  // redraw as the queue, with whatever is still pending
+  if let Some(msg) = &q.message {
+    let pending = db.scheduled_adds(Some(q.from.id.0), i64::MAX)?;
+    let timezone = settings::of(&db, Some(&q.from)).tz();
+    bot
+      .edit_message_text(msg.chat.id, msg.id, queue_text(&pending, timezone, lang))
+      .reply_markup(queue_keyboard(&pending))
+      .await?;
+  }
+  Ok(())
+}
+
+async fn add(
+  torrent: &TorrentApi,
+  config: &Config,
+  quotas: &Quotas,
+  pending: &ScheduledAdd,
+  lang: Lang,
+) -> Result<(), String> {
+  let candidate = Candidate::from_link(&pending.link);
+  quotas.check(pending.user_id, candidate.size, lang)?;
+  if let Some(rejection) = guard::check(torrent, &config.guard, &candidate)
+    .await
+    .map_err(|err| err.to_string())?
+  {
+    return Err(rejection.message(lang));
+  }
+  let arg = AddTorrentArg {
+    urls: vec![pending.link.clone()],
+    ..Default::default()
+  };
+  torrent
+    .add_torrent(&arg)
+    .await
+    .map_err(|err| err.to_string())?;
+  if let Some(hash) = torrent::extract_info_hash(&pending.link) {
+    quotas.record(pending.user_id, &hash, candidate.size);
+    guard::recheck(&hash, ChatId(pending.chat_id));
+  }
+  Ok(())
+}
+
+/// Adds the scheduled magnets once they are due and tells the chat they were scheduled from.
+/// An add that fails or breaks the add policies is dropped with a message rather than retried
+/// forever.
+pub fn spawn(bot: Bot, torrent: TorrentApi, config: Arc<Config>, db: Db, quotas: Quotas) {
+  tokio::spawn(async move {
+    let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+      ticker.tick().await;
+      let due = match db.scheduled_adds(None, Utc::now().timestamp()) {
+        Ok(due) => due,
+        Err(err) => {
+          log::error!("Could not load scheduled adds: {err}");
+          continue;
+        }
+      };
+      for pending in due {
+        let lang = i18n::chat_lang(&db, ChatId(pending.user_id as i64));
+        let name = name(&pending.link);
+        let text = match add(&torrent, &config, &quotas, &pending, lang).await {
+          Ok(()) => lang.tf("later.added", &[("name", &name)]),
+          Err(err) => lang.tf("later.failed", &[("name", &name), ("error", &err)]),
+        };
+        if let Err(err) = db.clear_scheduled_add(pending.id) {
+          log::error!("Could not clear scheduled add {}: {err}", pending.id);
+        }
+        if let Err(err) = bot.send_message(ChatId(pending.chat_id), text).await {
+          log::warn!(
+            "Could not report a scheduled add to {}: {err}",
+            pending.chat_id
+          );
+        }
+      }
+    }
+  });
+}
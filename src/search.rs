@@ -0,0 +1,281 @@
+use async_trait::async_trait;
+use std::{
+  cmp::Reverse,
+  collections::HashMap,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex, MutexGuard,
+  },
+};
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardButtonKind, InlineKeyboardMarkup, ParseMode},
+};
+
+use crate::{
+  add_keyboard, audit, callback_data,
+  config::Config,
+  db::Db,
+  duplicates,
+  guard::{self, Candidate},
+  i18n,
+  parental::Parental,
+  quota::Quotas,
+  render, settings,
+  torrent::{self, AddTorrentArg, ApiResult, TorrentApi},
+  HandlerResult,
+};
+
+mod torznab;
+
+use torznab::TorznabProvider;
+
+// results shown per search, one button each
+const MAX_RESULTS: usize = 10;
+// searches whose buttons still work, older ones are dropped first
+const KEPT_SEARCHES: u64 = 50;
+
+/// One release an indexer offered.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+  pub title: String,
+  /// A magnet, or a .torrent URL qBittorrent can fetch.
+  pub link: String,
+  pub size: u64,
+  pub seeders: u64,
+  pub leechers: u64,
+  pub indexer: String,
+}
+
+/// Somewhere /search can look for torrents that aren't in qBittorrent yet.
+#[async_trait]
+pub trait SearchProvider: Send + Sync {
+  fn name(&self) -> &str;
+
+  async fn search(&self, query: &str) -> ApiResult<Vec<SearchResult>>;
+}
+
+/// Every configured provider, plus the recent results their buttons point at.
+#[derive(Clone)]
+pub struct SearchProviders {
+  providers: Arc<Vec<Box<dyn SearchProvider>>>,
+  results: Arc<Mutex<HashMap<u64, Vec<SearchResult>>>>,
+  next_id: Arc<AtomicU64>,
+}
+
+impl SearchProviders {
+  pub fn from_config(config: &Config) -> Self {
+    let http = reqwest::Client::new();
+    let providers: Vec<Box<dyn SearchProvider>> = config
+      .search
+      .iter()
+      .map(|indexer| {
+        Box::new(TorznabProvider::new(http.clone(), indexer.clone())) as Box<dyn SearchProvider>
+      })
+      .collect();
+    SearchProviders {
+      providers: Arc::new(providers),
+      results: Arc::default(),
+      next_id: Arc::default(),
+    }
+  }
+
+  /// Results of every provider, most seeded first. A provider that fails is skipped.
+  async fn search(&self, query: &str) -> Vec<SearchResult> {
+    let mut results = Vec::new();
+    for provider in self.providers.iter() {
+      match provider.search(query).await {
+        Ok(found) => results.extend(found),
+        Err(err) => log::warn!("{} search failed: {err}", provider.name()),
+      }
+    }
+    results.sort_by_key(|result| Reverse(result.seeders));
+    results.truncate(MAX_RESULTS);
+    results
+  }
+
+  fn keep(&self, results: Vec<SearchResult>) -> u64 {
+    let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+    let mut kept = self.lock();
+    kept.insert(id, results);
+    kept.retain(|kept_id, _| kept_id + KEPT_SEARCHES > id);
+    id
+  }
+
+  fn result(&self, id: u64, index: usize) -> Option<SearchResult> {
+    self.lock().get(&id)?.get(index).cloned()
+  }
+
+  /// Result `number` (as shown, from 1) of the results message `msg`, read back from its buttons.
+  pub fn replied_result(&self, msg: &Message, number: usize) -> Option<SearchResult> {
+    let data = msg
+      .reply_markup()?
+      .inline_keyboard
+      .iter()
+      .flatten()
+      .find(|button| button.text == number.to_string())
+      .and_then(|button| match &button.kind {
+        InlineKeyboardButtonKind::CallbackData(data) => callback_data::resolve(data),
+        _ => None,
+      })?;
+    let (id, index) = data.strip_prefix("search:")?.split_once(':')?;
+    self.result(id.parse().ok()?, index.parse().ok()?)
+  }
+
+  fn lock(&self) -> MutexGuard<'_, HashMap<u64, Vec<SearchResult>>> {
+    self
+      .results
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+fn keyboard(id: u64, count: usize) -> InlineKeyboardMarkup {
+  let buttons: Vec<_> = (0..count)
+    .map(|index| callback_data::button((index + 1).to_string(), format!("search:{id}:{index}")))
+    .collect();
+  InlineKeyboardMarkup::new(buttons.chunks(5).map(<[_]>::to_vec))
+}
+
+/// `/search <text>` asks the configured indexers, each result gets a button that adds it.
+pub async fn search(
+  bot: Bot,
+  msg: Message,
+  query: String,
+  providers: SearchProviders,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  if providers.providers.is_empty() {
+    bot
+      .send_message(msg.chat.id, lang.t("search.disabled"))
+      .await?;
+    return Ok(());
+  }
+  let query = query.trim();
+  if query.is_empty() {
+    bot
+      .send_message(msg.chat.id, lang.t("search.usage"))
+      .await?;
+    return Ok(());
+  }
+
+  let results: Vec<_> = providers
+    .search(query)
+    .await
+    .into_iter()
+    .filter(|result| !parental.blocks(msg.chat.id, &result.title))
+    .collect();
+  if results.is_empty() {
+    bot.send_message(msg.chat.id, lang.t("search.none")).await?;
+    return Ok(());
+  }
+
+  let units = settings::of(&db, msg.from()).units();
+  let mut text = lang.tf("search.title", &[("query", &render::bold(query))]);
+  for (index, result) in results.iter().enumerate() {
+    text.push_str(&format!(
+      "\n\n{}. {}\n🌱 {} · 🐢 {} · {} · {}",
+      index + 1,
+      render::bold(&result.title),
+      result.seeders,
+      result.leechers,
+      units.bytes(result.size),
+      render::escape(&result.indexer)
+    ));
+  }
+  let count = results.len();
+  let id = providers.keep(results);
+  bot
+    .send_message(msg.chat.id, text)
+    .parse_mode(ParseMode::Html)
+    .reply_markup(keyboard(id, count))
+    .await?;
+  Ok(())
+}
+
+/// Adds the chosen result after the same parental and quota checks as a magnet.
+#[allow(clippy::too_many_arguments)]
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  torrent: TorrentApi,
+  providers: SearchProviders,
+  config: Arc<Config>,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  let mut parts = q.data.as_deref().unwrap_or_default().split(':').skip(1);
+  let (Some(id), Some(index), Some(msg)) = (
+    parts.next().and_then(|id| id.parse().ok()),
+    parts.next().and_then(|index| index.parse().ok()),
+    q.message.as_ref(),
+  ) else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+  let Some(result) = providers.result(id, index) else {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("search.expired"))
+      .await?;
+    return Ok(());
+  };
+  if parental.blocks(msg.chat.id, &result.title) {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("parental.blocked"))
+      .await?;
+    return Ok(());
+  }
+  let size = Some(result.size).filter(|size| *size > 0);
+  if let Err(reason) = quotas.check(q.from.id.0, size, lang) {
+    bot.answer_callback_query(q.id).text(reason).await?;
+    return Ok(());
+  }
+
+  bot.answer_callback_query(q.id).await?;
+  let candidate = Candidate {
+    name: Some(result.title.clone()),
+    size,
+    ..Candidate::from_link(&result.link)
+  };
+  if let Some(rejection) = guard::check(&torrent, &config.guard, &candidate).await? {
+    return guard::reject(&bot, msg.chat.id, &rejection, Some(&result.link), lang).await;
+  }
+  let hash = torrent::extract_info_hash(&result.link);
+  let duplicate =
+    duplicates::check_duplicates(&torrent, hash.as_deref(), Some(&result.title), size).await?;
+  if let Some(duplicate) = duplicate {
+    return duplicates::warn(&bot, msg.chat.id, &duplicate, &result.link, lang).await;
+  }
+  let arg = AddTorrentArg {
+    urls: vec![result.link],
+    ..Default::default()
+  };
+  let reply = match torrent.add_torrent(&arg).await {
+    Ok(()) => {
+      if let Some(hash) = &hash {
+        // indexers only guess the size, the real one is charged once metadata arrives
+        quotas.record(q.from.id.0, hash, None);
+        guard::recheck(hash, msg.chat.id);
+      }
+      audit::record(&db, &q.from, "added a search result", hash.as_deref());
+      lang.tf("search.added", &[("name", &result.title)])
+    }
+    Err(err) => err.to_string(),
+  };
+  let send = bot.send_message(msg.chat.id, reply);
+  match hash {
+    Some(hash) => {
+      send
+        .reply_markup(add_keyboard(&hash, &config, lang))
+        .await?
+    }
+    None => send.await?,
+  };
+  Ok(())
+}
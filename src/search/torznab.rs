@@ -0,0 +1,187 @@
+use async_trait::async_trait;
+use regex::Regex;
+use std::sync::OnceLock;
+
+use super::{SearchProvider, SearchResult};
+use crate::{config::TorznabConfig, torrent::ApiResult};
+
+/// A Jackett or Prowlarr indexer (or Jackett's `all` aggregate) spoken to over Torznab.
+pub struct TorznabProvider {
+  http: reqwest::Client,
+  config: TorznabConfig,
+}
+
+impl TorznabProvider {
+  pub fn new(http: reqwest::Client, config: TorznabConfig) -> Self {
+    TorznabProvider { http, config }
+  }
+}
+
+#[async_trait]
+impl SearchProvider for TorznabProvider {
+  fn name(&self) -> &str {
+    &self.config.name
+  }
+
+  async fn search(&self, query: &str) -> ApiResult<Vec<SearchResult>> {
+    let feed = self
+      .http
+      .get(&self.config.url)
+      .query(&[
+        ("apikey", self.config.api_key.as_str()),
+        ("t", "search"),
+        ("q", query),
+      ])
+      .send()
+      .await?
+      .error_for_status()?
+      .text()
+      .await?;
+    Ok(parse(&feed, &self.config.name))
+  }
+}
+
+fn item_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?s)<item>(.*?)</item>").expect("item pattern is valid"))
+}
+
+fn element_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| {
+    Regex::new(r"(?s)<(title|link|size|jackettindexer|prowlarrindexer)\b[^>]*>(.*?)</")
+      .expect("element pattern is valid")
+  })
+}
+
+fn attr_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| {
+    Regex::new(r#"<torznab:attr\s+name="([^"]+)"\s+value="([^"]*)""#)
+      .expect("attr pattern is valid")
+  })
+}
+
+fn enclosure_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| {
+    Regex::new(r#"<enclosure\b[^>]*\burl="([^"]+)""#).expect("enclosure pattern is valid")
+  })
+}
+
+/// Text content with CDATA unwrapped and the XML entities feeds actually use decoded.
+fn text(raw: &str) -> String {
+  let raw = raw.trim();
+  if let Some(cdata) = raw
+    .strip_prefix("<![CDATA[")
+    .and_then(|raw| raw.strip_suffix("]]>"))
+  {
+    return cdata.to_string();
+  }
+  raw
+    .replace("&lt;", "<")
+    .replace("&gt;", ">")
+    .replace("&quot;", "\"")
+    .replace("&apos;", "'")
+    .replace("&#39;", "'")
+    .replace("&amp;", "&")
+}
+
+/// The `<item>`s of a Torznab RSS feed. A regex per field is enough for this fixed format and
+/// skips pulling in an XML parser.
+fn parse(feed: &str, provider: &str) -> Vec<SearchResult> {
+  item_re()
+    .captures_iter(feed)
+    .filter_map(|item| {
+      let item = &item[1];
+      let (mut title, mut link, mut indexer, mut size) = (None, None, None, 0);
+      for element in element_re().captures_iter(item) {
+        let value = text(&element[2]);
+        match &element[1] {
+          "title" => title = Some(value),
+          "link" => link = Some(value),
+          "size" => size = value.parse().unwrap_or(size),
+          _ => indexer = Some(value),
+        }
+      }
+      let (mut seeders, mut peers, mut magnet) = (0u64, 0u64, None);
+      for attr in attr_re().captures_iter(item) {
+        let value = text(&attr[2]);
+        match &attr[1] {
+          "seeders" => seeders = value.parse().unwrap_or(0),
+          "peers" => peers = value.parse().unwrap_or(0),
+          "magneturl" => magnet = Some(value),
+          "size" if size == 0 => size = value.parse().unwrap_or(0),
+          _ => {}
+        }
+      }
+      let link = magnet
+        .or(link)
+        .or_else(|| enclosure_re().captures(item).map(|url| text(&url[1])))?;
+      Some(SearchResult {
+        title: title?,
+        link,
+        size,
+        seeders,
+        leechers: peers.saturating_sub(seeders),
+        indexer: indexer.unwrap_or_else(|| provider.to_string()),
+      })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:torznab="http://torznab.com/schemas/2015/feed">
+<channel>
+  <item>
+    <title><![CDATA[Some Show S01E01 1080p]]></title>
+    <link>https://indexer.example/dl?id=1&amp;key=x</link>
+    <size>1073741824</size>
+    <jackettindexer id="demo">Demo</jackettindexer>
+    <torznab:attr name="seeders" value="12" />
+    <torznab:attr name="peers" value="15" />
+    <torznab:attr name="magneturl" value="magnet:?xt=urn:btih:abc&amp;dn=show" />
+  </item>
+  <item>
+    <title>Movie &amp; More</title>
+    <enclosure url="https://indexer.example/movie.torrent" length="0" />
+    <torznab:attr name="size" value="2048" />
+  </item>
+  <item>
+    <title>No link</title>
+  </item>
+</channel>
+</rss>"#;
+
+  #[test]
+  fn parses_items() {
+    let results = parse(FEED, "Fallback");
+    assert_eq!(results.len(), 2);
+
+    let show = &results[0];
+    assert_eq!(show.title, "Some Show S01E01 1080p");
+    assert_eq!(show.link, "magnet:?xt=urn:btih:abc&dn=show");
+    assert_eq!(show.size, 1073741824);
+    assert_eq!((show.seeders, show.leechers), (12, 3));
+    assert_eq!(show.indexer, "Demo");
+
+    let movie = &results[1];
+    assert_eq!(movie.title, "Movie & More");
+    assert_eq!(movie.link, "https://indexer.example/movie.torrent");
+    assert_eq!(movie.size, 2048);
+    assert_eq!(movie.indexer, "Fallback");
+  }
+
+  #[test]
+  fn decodes_text() {
+    assert_eq!(text(" <![CDATA[a &amp; b]]> "), "a &amp; b");
+    assert_eq!(
+      text("&lt;a&gt; &quot;b&quot; &#39;c&apos; &amp;amp;"),
+      "<a> \"b\" 'c' &amp;"
+    );
+  }
+}
@@ -0,0 +1,149 @@
+use teloxide::{prelude::*, types::InlineKeyboardMarkup};
+
+use crate::{
+  callback_data,
+  db::Db,
+  format::StateGroup,
+  i18n::{self, Lang},
+  list::page_row,
+  parental::Parental,
+  settings,
+  torrent::events::{TorrentEvents, TorrentSnapshot},
+  HandlerResult,
+};
+
+/// What picking a torrent from [`torrent_select_keyboard`] does.
+#[derive(Debug, Clone, Copy)]
+pub enum Action {
+  Files,
+  DeleteData,
+}
+
+impl Action {
+  const ALL: [Action; 2] = [Action::Files, Action::DeleteData];
+
+  fn key(self) -> &'static str {
+    match self {
+      Action::Files => "files",
+      Action::DeleteData => "deletedata",
+    }
+  }
+
+  fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|action| action.key() == key)
+  }
+
+  /// The callback data of the button that already performs this action.
+  fn data(self, hash: &str) -> String {
+    match self {
+      Action::Files => format!("fl:{hash}:0"),
+      Action::DeleteData => format!("deldata:{hash}"),
+    }
+  }
+}
+
+/// One page of the torrents visible in `chat`, sorted by name, each button running `action`.
+fn torrent_select_keyboard(
+  events: &TorrentEvents,
+  parental: &Parental,
+  chat: ChatId,
+  action: Action,
+  page: usize,
+  page_size: usize,
+  lang: Lang,
+) -> Option<InlineKeyboardMarkup> {
+  let torrents = events.torrents();
+  let current = torrents.borrow();
+  let mut visible: Vec<&TorrentSnapshot> = current
+    .values()
+    .filter(|torrent| !parental.hides(chat, torrent))
+    .collect();
+  if visible.is_empty() {
+    return None;
+  }
+  visible.sort_by_key(|torrent| torrent.name.to_lowercase());
+
+  let pages = visible.len().div_ceil(page_size);
+  let page = page.min(pages - 1);
+  let mut rows: Vec<_> = visible
+    .iter()
+    .skip(page * page_size)
+    .take(page_size)
+    .map(|torrent| {
+      vec![callback_data::button(
+        format!(
+          "{} {}",
+          StateGroup::of(&torrent.state).emoji(),
+          torrent.name
+        ),
+        action.data(&torrent.hash),
+      )]
+    })
+    .collect();
+  rows.push(page_row(page, page + 1 < pages, lang, |page| {
+    format!("select:{}:{page}", action.key())
+  }));
+  Some(InlineKeyboardMarkup::new(rows))
+}
+
+/// Asks which torrent to run `action` on, for commands sent without a hash.
+pub async fn offer(
+  bot: &Bot,
+  msg: &Message,
+  action: Action,
+  events: &TorrentEvents,
+  parental: &Parental,
+  db: &Db,
+) -> HandlerResult {
+  let lang = i18n::lang(db, msg.from());
+  let page_size = settings::of(db, msg.from()).page_size as usize;
+  let Some(keyboard) =
+    torrent_select_keyboard(events, parental, msg.chat.id, action, 0, page_size, lang)
+  else {
+    bot
+      .send_message(msg.chat.id, lang.t("select.empty"))
+      .await?;
+    return Ok(());
+  };
+  bot
+    .send_message(msg.chat.id, lang.t(&format!("select.{}", action.key())))
+    .reply_markup(keyboard)
+    .await?;
+  Ok(())
+}
+
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  events: TorrentEvents,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  bot.answer_callback_query(q.id).await?;
+  let mut parts = q.data.as_deref().unwrap_or_default().split(':').skip(1);
+  let (Some(action), Some(page), Some(msg)) = (
+    parts.next().and_then(Action::from_key),
+    parts.next().and_then(|page| page.parse().ok()),
+    q.message.as_ref(),
+  ) else {
+    return Ok(());
+  };
+
+  let lang = i18n::lang(&db, Some(&q.from));
+  let page_size = settings::of(&db, Some(&q.from)).page_size as usize;
+  let keyboard = torrent_select_keyboard(
+    &events,
+    &parental,
+    msg.chat.id,
+    action,
+    page,
+    page_size,
+    lang,
+  )
+  .unwrap_or_default();
+  bot
+    .edit_message_reply_markup(msg.chat.id, msg.id)
+    .reply_markup(keyboard)
+    .await?;
+  Ok(())
+}
@@ -0,0 +1,184 @@
+use std::{path::Path, sync::Arc};
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardButton, InputFile},
+};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+  callback_data,
+  config::Config,
+  db::Db,
+  features::{Feature, Features},
+  format::format_bytes,
+  i18n::{self, Lang},
+  parental::Parental,
+  torrent::{
+    events::{TorrentEvent, TorrentEvents},
+    ApiResult, TorrentApi,
+  },
+  watch, HandlerResult,
+};
+
+/// Bots can upload documents of at most 50 MB.
+pub const MAX_UPLOAD: u64 = 50 * 1024 * 1024;
+
+/// Uploads every file of a finished torrent that fits into `max_bytes`, returns the names of
+/// the files that were too large.
+pub async fn upload_files(
+  bot: &Bot,
+  chat_id: ChatId,
+  torrent: &TorrentApi,
+  hash: &str,
+  save_path: &str,
+  max_bytes: u64,
+) -> ApiResult<Vec<String>> {
+  let mut too_large = Vec::new();
+  for file in torrent.files(hash).await? {
+    if file.size > max_bytes.min(MAX_UPLOAD) {
+      too_large.push(format!("{} ({})", file.name, format_bytes(file.size)));
+      continue;
+    }
+    // qBittorrent reports paths relative to the save path, which has to be visible to the bot
+    let path = Path::new(save_path).join(&file.name);
+    bot.send_document(chat_id, InputFile::file(path)).await?;
+  }
+  Ok(too_large)
+}
+
+/// `/send <hash>` uploads the files of a completed torrent to the chat.
+pub async fn send(
+  bot: Bot,
+  msg: Message,
+  hash: String,
+  torrent: TorrentApi,
+  events: TorrentEvents,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let snapshot = {
+    let torrents = events.torrents();
+    let current = torrents.borrow();
+    let all: Vec<_> = current
+      .values()
+      .filter(|torrent| !parental.hides(msg.chat.id, torrent))
+      .collect();
+    watch::find(&all, hash.trim()).cloned()
+  };
+  let Some(snapshot) = snapshot else {
+    bot
+      .send_message(msg.chat.id, lang.t("send.not_found"))
+      .await?;
+    return Ok(());
+  };
+  if !snapshot.is_complete() {
+    bot
+      .send_message(msg.chat.id, lang.t("send.incomplete"))
+      .await?;
+    return Ok(());
+  }
+
+  let too_large = upload_files(
+    &bot,
+    msg.chat.id,
+    &torrent,
+    &snapshot.hash,
+    &snapshot.save_path,
+    MAX_UPLOAD,
+  )
+  .await?;
+  if !too_large.is_empty() {
+    // splitting files into parts nobody can open on a phone isn't worth it
+    bot
+      .send_message(
+        msg.chat.id,
+        format!("{}\n• {}", lang.t("send.too_large"), too_large.join("\n• ")),
+      )
+      .await?;
+  }
+  Ok(())
+}
+
+pub fn auto_send_button(hash: &str, lang: Lang) -> InlineKeyboardButton {
+  callback_data::button(lang.t("send.auto_button"), format!("autosend:{hash}"))
+}
+
+pub async fn callback(bot: Bot, q: CallbackQuery, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  let (Some(hash), Some(msg)) = (
+    q.data
+      .as_deref()
+      .and_then(|data| data.strip_prefix("autosend:")),
+    q.message.as_ref(),
+  ) else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+
+  db.set_auto_send(hash, msg.chat.id.0)?;
+  bot
+    .answer_callback_query(q.id)
+    .text(lang.t("send.auto_set"))
+    .await?;
+  Ok(())
+}
+
+/// Uploads the files of torrents marked with "Send when done" once they complete.
+pub fn spawn_auto_send(
+  bot: Bot,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  db: Db,
+  features: Features,
+  events: TorrentEvents,
+) {
+  let max_bytes = config.notify.auto_send_max_mb * 1024 * 1024;
+  let mut events = events.subscribe();
+  tokio::spawn(async move {
+    loop {
+      let completed = match events.recv().await {
+        Ok(TorrentEvent::Completed(completed)) => completed,
+        Ok(_) => continue,
+        Err(RecvError::Lagged(skipped)) => {
+          log::warn!("Auto send skipped {skipped} events");
+          continue;
+        }
+        Err(RecvError::Closed) => break,
+      };
+      let chat_id = match db.take_auto_send(&completed.hash) {
+        Ok(Some(chat_id)) => ChatId(chat_id),
+        Ok(None) => continue,
+        Err(err) => {
+          log::error!("Could not look up auto send for {}: {err}", completed.hash);
+          continue;
+        }
+      };
+      if !features.enabled(Feature::Notifications) || features.in_maintenance() {
+        continue;
+      }
+
+      let res = upload_files(
+        &bot,
+        chat_id,
+        &torrent,
+        &completed.hash,
+        &completed.save_path,
+        max_bytes,
+      )
+      .await;
+      match res {
+        Ok(too_large) if too_large.is_empty() => {}
+        Ok(too_large) => {
+          let text = format!(
+            "⚠️ Not sent from {}:\n• {}",
+            completed.name,
+            too_large.join("\n• ")
+          );
+          let _ = bot.send_message(chat_id, text).await;
+        }
+        Err(err) => log::warn!("Could not send the files of {}: {err}", completed.name),
+      }
+    }
+  });
+}
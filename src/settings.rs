@@ -0,0 +1,218 @@
+use chrono_tz::Tz;
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardMarkup, User},
+};
+
+use crate::{
+  callback_data,
+  db::{Db, UserSettings},
+  i18n::{self, Lang},
+  HandlerResult,
+};
+
+/// A setting shown in /settings. Tapping it cycles through `choices`.
+#[derive(Debug, Clone, Copy)]
+enum Setting {
+  PageSize,
+  WatchRefresh,
+  InlineResults,
+  ListStyle,
+  FilesPageSize,
+  Units,
+}
+
+impl Setting {
+  const ALL: [Setting; 6] = [
+    Setting::PageSize,
+    Setting::WatchRefresh,
+    Setting::InlineResults,
+    Setting::ListStyle,
+    Setting::FilesPageSize,
+    Setting::Units,
+  ];
+
+  fn key(self) -> &'static str {
+    match self {
+      Setting::PageSize => "page_size",
+      Setting::WatchRefresh => "watch_refresh",
+      Setting::InlineResults => "inline_results",
+      Setting::ListStyle => "list_style",
+      Setting::FilesPageSize => "files_page_size",
+      Setting::Units => "units",
+    }
+  }
+
+  fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|setting| setting.key() == key)
+  }
+
+  fn choices(self) -> &'static [u64] {
+    match self {
+      Setting::PageSize => &[5, 10, 20],
+      Setting::WatchRefresh => &[2, 5, 10, 30],
+      // Telegram accepts at most 50 inline results
+      Setting::InlineResults => &[10, 25, 50],
+      // compact, detailed
+      Setting::ListStyle => &[0, 1],
+      // the picker needs a button per file, Telegram allows 100
+      Setting::FilesPageSize => &[10, 20, 50],
+      // binary, SI, binary with bit speeds, SI with bit speeds
+      Setting::Units => &[0, 1, 2, 3],
+    }
+  }
+
+  fn get(self, settings: &UserSettings) -> u64 {
+    match self {
+      Setting::PageSize => settings.page_size as u64,
+      Setting::WatchRefresh => settings.watch_refresh_secs,
+      Setting::InlineResults => settings.inline_results as u64,
+      Setting::ListStyle => settings.detailed_list as u64,
+      Setting::FilesPageSize => settings.files_page_size as u64,
+      Setting::Units => settings.unit_style as u64,
+    }
+  }
+
+  fn set(self, settings: &mut UserSettings, value: u64) {
+    match self {
+      Setting::PageSize => settings.page_size = value as u32,
+      Setting::WatchRefresh => settings.watch_refresh_secs = value,
+      Setting::InlineResults => settings.inline_results = value as usize,
+      Setting::ListStyle => settings.detailed_list = value != 0,
+      Setting::FilesPageSize => settings.files_page_size = value as u32,
+      Setting::Units => settings.unit_style = value as u8,
+    }
+  }
+
+  fn next(self, settings: &mut UserSettings) {
+    let choices = self.choices();
+    let current = self.get(settings);
+    let next = choices
+      .iter()
+      .find(|choice| **choice > current)
+      .unwrap_or(&choices[0]);
+    self.set(settings, *next);
+  }
+
+  fn display(self, settings: &UserSettings, lang: Lang) -> String {
+    match self {
+      Setting::ListStyle if settings.detailed_list => lang.t("settings.detailed").to_string(),
+      Setting::ListStyle => lang.t("settings.compact").to_string(),
+      Setting::Units => {
+        let units = settings.units();
+        format!("{} · {}", units.bytes(1 << 30), units.speed(1 << 20))
+      }
+      _ => self.get(settings).to_string(),
+    }
+  }
+}
+
+/// The user's settings, defaults when unknown or unreadable.
+pub fn of(db: &Db, user: Option<&User>) -> UserSettings {
+  let Some(user) = user else {
+    return UserSettings::default();
+  };
+  db.settings(user.id.0).unwrap_or_else(|err| {
+    log::warn!("Could not load the settings of {}: {err}", user.id);
+    UserSettings::default()
+  })
+}
+
+fn keyboard(settings: &UserSettings, lang: Lang) -> InlineKeyboardMarkup {
+  let mut rows: Vec<_> = Setting::ALL
+    .into_iter()
+    .map(|setting| {
+      let label = lang.tf(
+        &format!("settings.{}", setting.key()),
+        &[("value", &setting.display(settings, lang))],
+      );
+      vec![callback_data::button(
+        label,
+        format!("settings:{}", setting.key()),
+      )]
+    })
+    .collect();
+  // too many zones to cycle through, the button explains how to type one
+  rows.push(vec![callback_data::button(
+    lang.tf("settings.timezone", &[("value", &settings.timezone)]),
+    "settings:timezone",
+  )]);
+  InlineKeyboardMarkup::new(rows)
+}
+
+/// `/settings timezone <zone>` takes an IANA name like `Europe/Madrid`.
+async fn set_timezone(bot: &Bot, msg: &Message, zone: &str, db: &Db, lang: Lang) -> HandlerResult {
+  let Some(user) = msg.from() else {
+    return Ok(());
+  };
+  let Ok(tz) = zone.parse::<Tz>() else {
+    bot
+      .send_message(msg.chat.id, lang.t("settings.timezone_invalid"))
+      .await?;
+    return Ok(());
+  };
+  let mut settings = db.settings(user.id.0)?;
+  settings.timezone = tz.name().to_string();
+  db.set_settings(user.id.0, &settings)?;
+  bot
+    .send_message(
+      msg.chat.id,
+      lang.tf("settings.timezone_set", &[("zone", &settings.timezone)]),
+    )
+    .await?;
+  Ok(())
+}
+
+pub async fn settings(bot: Bot, msg: Message, args: String, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let mut words = args.split_whitespace();
+  match (words.next(), words.next()) {
+    (None, _) => {}
+    (Some("timezone"), Some(zone)) => return set_timezone(&bot, &msg, zone, &db, lang).await,
+    _ => {
+      bot
+        .send_message(msg.chat.id, lang.t("settings.usage"))
+        .await?;
+      return Ok(());
+    }
+  }
+  bot
+    .send_message(msg.chat.id, lang.t("settings.prompt"))
+    .reply_markup(keyboard(&of(&db, msg.from()), lang))
+    .await?;
+  Ok(())
+}
+
+pub async fn callback(bot: Bot, q: CallbackQuery, db: Db) -> HandlerResult {
+  let key = q
+    .data
+    .as_deref()
+    .and_then(|data| data.strip_prefix("settings:"));
+  if key == Some("timezone") {
+    let lang = i18n::lang(&db, Some(&q.from));
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("settings.usage"))
+      .show_alert(true)
+      .await?;
+    return Ok(());
+  }
+  let Some(setting) = key.and_then(Setting::from_key) else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+
+  let mut settings = db.settings(q.from.id.0)?;
+  setting.next(&mut settings);
+  db.set_settings(q.from.id.0, &settings)?;
+
+  let lang = i18n::lang(&db, Some(&q.from));
+  bot.answer_callback_query(q.id).await?;
+  if let Some(msg) = q.message {
+    bot
+      .edit_message_reply_markup(msg.chat.id, msg.id)
+      .reply_markup(keyboard(&settings, lang))
+      .await?;
+  }
+  Ok(())
+}
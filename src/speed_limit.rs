@@ -0,0 +1,244 @@
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardButton, InlineKeyboardMarkup, User},
+};
+
+use crate::{
+  audit, callback_data,
+  db::Db,
+  format::{format_speed, Units},
+  i18n::{self, Lang},
+  settings,
+  torrent::{ApiResult, TorrentApi},
+  HandlerResult, MyDialogue, State,
+};
+
+// qBittorrent counts limits in KiB/s, so a "MB" here is a MiB like in its web UI
+const KB: u64 = 1024;
+const MB: u64 = 1024 * 1024;
+const PRESETS_MB: [u64; 4] = [1, 5, 10, 50];
+
+pub fn button(hash: &str, lang: Lang) -> InlineKeyboardButton {
+  callback_data::button(lang.t("limit.button"), format!("limit:{hash}"))
+}
+
+fn direction(upload: bool) -> &'static str {
+  if upload {
+    "up"
+  } else {
+    "dl"
+  }
+}
+
+fn arrow(upload: bool) -> &'static str {
+  if upload {
+    "⬆️"
+  } else {
+    "⬇️"
+  }
+}
+
+/// Two rows per direction: the presets, then Unlimited and a custom value.
+fn keyboard(hash: &str, lang: Lang) -> InlineKeyboardMarkup {
+  let mut rows = Vec::new();
+  for upload in [false, true] {
+    let (arrow, direction) = (arrow(upload), direction(upload));
+    rows.push(
+      PRESETS_MB
+        .iter()
+        .map(|mb| {
+          callback_data::button(
+            format!("{arrow} {mb} MB/s"),
+            format!("limit:{hash}:{direction}:{}", mb * MB),
+          )
+        })
+        .collect(),
+    );
+    rows.push(vec![
+      callback_data::button(
+        format!("{arrow} {}", lang.t("limit.unlimited")),
+        format!("limit:{hash}:{direction}:0"),
+      ),
+      callback_data::button(
+        format!("{arrow} {}", lang.t("limit.custom")),
+        format!("limit:{hash}:{direction}:custom"),
+      ),
+    ]);
+  }
+  InlineKeyboardMarkup::new(rows)
+}
+
+/// A speed like `2.5`, `2.5 MB/s`, `800k` or `unlimited`. Plain numbers are MB/s, `0` lifts the
+/// limit.
+fn parse_rate(text: &str) -> Option<u64> {
+  let text = text.trim().to_lowercase();
+  if matches!(text.as_str(), "unlimited" | "none" | "off") {
+    return Some(0);
+  }
+  let text = text.strip_suffix("/s").unwrap_or(&text);
+  let split = text
+    .find(|c: char| !c.is_ascii_digit() && c != '.' && c != ',')
+    .unwrap_or(text.len());
+  let (number, unit) = text.split_at(split);
+  let number: f64 = number.replace(',', ".").parse().ok()?;
+  let scale = match unit.trim() {
+    "" | "m" | "mb" | "mib" => MB,
+    "k" | "kb" | "kib" => KB,
+    _ => return None,
+  };
+  (number.is_finite() && number >= 0.0).then(|| (number * scale as f64).round() as u64)
+}
+
+fn describe(limit: u64, units: Units, lang: Lang) -> String {
+  if limit == 0 {
+    lang.t("limit.unlimited").to_string()
+  } else {
+    units.speed(limit)
+  }
+}
+
+async fn apply(
+  torrent: &TorrentApi,
+  db: &Db,
+  user: &User,
+  hash: &str,
+  upload: bool,
+  limit: u64,
+) -> ApiResult<()> {
+  if upload {
+    torrent.set_upload_limit(hash, limit).await?;
+  } else {
+    torrent.set_download_limit(hash, limit).await?;
+  }
+  let action = match (upload, limit) {
+    (true, 0) => "removed the upload limit".to_string(),
+    (false, 0) => "removed the download limit".to_string(),
+    (true, limit) => format!("limited uploads to {}", format_speed(limit)),
+    (false, limit) => format!("limited downloads to {}", format_speed(limit)),
+  };
+  audit::record(db, user, action, Some(hash));
+  Ok(())
+}
+
+fn confirmation(upload: bool, limit: u64, units: Units, lang: Lang) -> String {
+  lang.tf(
+    if upload {
+      "limit.set_up"
+    } else {
+      "limit.set_dl"
+    },
+    &[("limit", &describe(limit, units, lang))],
+  )
+}
+
+/// `limit:<hash>` opens the presets, `limit:<hash>:<dl|up>:<bytes per second|custom>` applies one
+/// or asks for a value.
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  dialogue: MyDialogue,
+  torrent: TorrentApi,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  let (Some(data), Some(msg)) = (
+    q.data
+      .as_deref()
+      .and_then(|data| data.strip_prefix("limit:")),
+    q.message.as_ref(),
+  ) else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+  let mut parts = data.split(':');
+
+  match (parts.next(), parts.next(), parts.next()) {
+    (Some(hash), None, None) => {
+      bot.answer_callback_query(q.id).await?;
+      bot
+        .send_message(msg.chat.id, lang.t("limit.prompt"))
+        .reply_markup(keyboard(hash, lang))
+        .await?;
+    }
+    (Some(hash), Some(direction @ ("dl" | "up")), Some("custom")) => {
+      let upload = direction == "up";
+      dialogue
+        .update(State::GetSpeedLimit {
+          hash: hash.to_string(),
+          upload,
+        })
+        .await?;
+      bot.answer_callback_query(q.id).await?;
+      bot
+        .edit_message_text(
+          msg.chat.id,
+          msg.id,
+          lang.t(if upload {
+            "limit.ask_up"
+          } else {
+            "limit.ask_dl"
+          }),
+        )
+        .await?;
+    }
+    (Some(hash), Some(direction @ ("dl" | "up")), Some(limit)) => {
+      let upload = direction == "up";
+      let limit: u64 = limit.parse()?;
+      match apply(&torrent, &db, &q.from, hash, upload, limit).await {
+        Ok(()) => {
+          let units = settings::of(&db, Some(&q.from)).units();
+          let text = confirmation(upload, limit, units, lang);
+          bot.answer_callback_query(q.id).text(&text).await?;
+          bot.edit_message_text(msg.chat.id, msg.id, text).await?;
+        }
+        Err(err) => {
+          bot
+            .answer_callback_query(q.id)
+            .text(err.to_string())
+            .show_alert(true)
+            .await?;
+        }
+      }
+    }
+    _ => {
+      bot.answer_callback_query(q.id).await?;
+    }
+  }
+  Ok(())
+}
+
+/// The reply to the custom value prompt, asked again until it reads as a speed.
+pub async fn custom(
+  bot: Bot,
+  dialogue: MyDialogue,
+  (hash, upload): (String, bool),
+  msg: Message,
+  torrent: TorrentApi,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(limit) = msg.text().and_then(parse_rate) else {
+    bot
+      .send_message(msg.chat.id, lang.t("limit.invalid"))
+      .await?;
+    return Ok(());
+  };
+  let Some(user) = msg.from() else {
+    dialogue.exit().await?;
+    return Ok(());
+  };
+
+  let units = settings::of(&db, Some(user)).units();
+  match apply(&torrent, &db, user, &hash, upload, limit).await {
+    Ok(()) => {
+      bot
+        .send_message(msg.chat.id, confirmation(upload, limit, units, lang))
+        .await?;
+      dialogue.exit().await?;
+    }
+    Err(err) => {
+      bot.send_message(msg.chat.id, err.to_string()).await?;
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use std::{
+  sync::{Mutex, OnceLock},
+  time::Instant,
+};
+use teloxide::prelude::*;
+
+use crate::{
+  auth::Access,
+  db::Db,
+  format::{format_duration, StateGroup},
+  i18n::{self, Lang},
+  torrent::{events::TorrentEvents, TorrentApi},
+  HandlerResult,
+};
+
+static STARTED: OnceLock<DateTime<Utc>> = OnceLock::new();
+static LAST_ERROR: Mutex<Option<(DateTime<Utc>, String)>> = Mutex::new(None);
+
+/// Uptime in /status counts from here.
+pub fn mark_started() {
+  let _ = STARTED.set(Utc::now());
+}
+
+/// Remembers `error` as the last thing that went wrong, shown to admins in /status.
+pub fn record_error(error: impl ToString) {
+  let mut last = LAST_ERROR.lock().unwrap_or_else(|err| err.into_inner());
+  *last = Some((Utc::now(), error.to_string()));
+}
+
+fn last_error(lang: Lang) -> String {
+  let last = LAST_ERROR.lock().unwrap_or_else(|err| err.into_inner());
+  match last.as_ref() {
+    Some((at, error)) => lang.tf(
+      "status.last_error",
+      &[
+        ("ago", &format_duration((Utc::now() - *at).num_seconds())),
+        ("error", error),
+      ],
+    ),
+    None => lang.t("status.no_errors").to_string(),
+  }
+}
+
+/// `/status` checks qBittorrent and reports on the bot itself, admins also see the last error.
+pub async fn status(
+  bot: Bot,
+  msg: Message,
+  torrent: TorrentApi,
+  events: TorrentEvents,
+  access: Access,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let started = Instant::now();
+  let mut lines = vec![match torrent.ping().await {
+    Ok(version) => lang.tf(
+      "status.ok",
+      &[
+        ("version", &version),
+        ("latency", &started.elapsed().as_millis()),
+      ],
+    ),
+    Err(err) => lang.tf("status.down", &[("error", &err)]),
+  }];
+
+  let (total, active) = {
+    let torrents = events.torrents();
+    let current = torrents.borrow();
+    let active = current
+      .values()
+      .filter(|torrent| {
+        matches!(
+          StateGroup::of(&torrent.state),
+          StateGroup::Downloading | StateGroup::Seeding
+        )
+      })
+      .count();
+    (current.len(), active)
+  };
+  lines.push(lang.tf("status.torrents", &[("total", &total), ("active", &active)]));
+  if let Some(started) = STARTED.get() {
+    let uptime = format_duration((Utc::now() - *started).num_seconds());
+    lines.push(lang.tf("status.uptime", &[("uptime", &uptime)]));
+  }
+  if msg.from().is_some_and(|user| access.is_admin(user.id)) {
+    lines.push(last_error(lang));
+  }
+
+  bot.send_message(msg.chat.id, lines.join("\n")).await?;
+  Ok(())
+}
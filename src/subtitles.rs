@@ -0,0 +1,227 @@
+use serde::Deserialize;
+use serde_json::json;
+use std::{path::Path, sync::Arc};
+use teloxide::prelude::*;
+
+use crate::{
+  config::{Config, OpenSubtitlesConfig},
+  db::Db,
+  i18n,
+  parental::Parental,
+  torrent::{events::TorrentEvents, ApiResult, TorrentApi, TorrentFile},
+  watch, HandlerResult,
+};
+
+const API_URL: &str = "https://api.opensubtitles.com/api/v1";
+// OpenSubtitles rejects requests without an identifying user agent
+const USER_AGENT: &str = concat!("ChatQBit v", env!("CARGO_PKG_VERSION"));
+const VIDEO_EXTENSIONS: [&str; 8] = ["mkv", "mp4", "avi", "m4v", "mov", "webm", "ts", "wmv"];
+
+fn is_video(name: &str) -> bool {
+  Path::new(name)
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .is_some_and(|ext| VIDEO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+#[derive(Deserialize)]
+struct Login {
+  token: String,
+}
+
+#[derive(Deserialize)]
+struct Search {
+  data: Vec<SearchHit>,
+}
+
+#[derive(Deserialize)]
+struct SearchHit {
+  attributes: HitAttributes,
+}
+
+#[derive(Deserialize)]
+struct HitAttributes {
+  files: Vec<HitFile>,
+}
+
+#[derive(Deserialize)]
+struct HitFile {
+  file_id: u64,
+}
+
+#[derive(Deserialize)]
+struct Download {
+  link: String,
+}
+
+/// A logged in OpenSubtitles session, downloads count against the account's daily quota.
+struct Session<'a> {
+  http: reqwest::Client,
+  config: &'a OpenSubtitlesConfig,
+  token: String,
+}
+
+impl<'a> Session<'a> {
+  async fn login(config: &'a OpenSubtitlesConfig) -> ApiResult<Session<'a>> {
+    let http = reqwest::Client::new();
+    let login: Login = http
+      .post(format!("{API_URL}/login"))
+      .header("Api-Key", &config.api_key)
+      .header("User-Agent", USER_AGENT)
+      .json(&json!({ "username": config.username, "password": config.password }))
+      .send()
+      .await?
+      .error_for_status()?
+      .json()
+      .await?;
+    Ok(Session {
+      http,
+      config,
+      token: login.token,
+    })
+  }
+
+  /// The best matching subtitle file for `query`, searched by name since the video may not be
+  /// downloaded enough to hash.
+  async fn search(&self, query: &str, language: &str) -> ApiResult<Option<u64>> {
+    let search: Search = self
+      .http
+      .get(format!("{API_URL}/subtitles"))
+      .header("Api-Key", &self.config.api_key)
+      .header("User-Agent", USER_AGENT)
+      .query(&[("query", query), ("languages", language)])
+      .send()
+      .await?
+      .error_for_status()?
+      .json()
+      .await?;
+    Ok(
+      search
+        .data
+        .into_iter()
+        .find_map(|hit| hit.attributes.files.into_iter().next())
+        .map(|file| file.file_id),
+    )
+  }
+
+  /// A temporary link to the .srt of `file_id`.
+  async fn link(&self, file_id: u64) -> ApiResult<String> {
+    let download: Download = self
+      .http
+      .post(format!("{API_URL}/download"))
+      .header("Api-Key", &self.config.api_key)
+      .header("User-Agent", USER_AGENT)
+      .bearer_auth(&self.token)
+      .json(&json!({ "file_id": file_id }))
+      .send()
+      .await?
+      .error_for_status()?
+      .json()
+      .await?;
+    Ok(download.link)
+  }
+}
+
+/// A subtitle saved next to one of the videos.
+struct Fetched {
+  video: String,
+  language: String,
+  link: String,
+}
+
+/// Downloads a subtitle per configured language for every video in `files` and saves it as
+/// `<video>.<language>.srt`, which players pick up on their own. `save_path` has to be visible
+/// to the bot, like for /send.
+async fn fetch(
+  config: &OpenSubtitlesConfig,
+  save_path: &str,
+  files: &[TorrentFile],
+) -> ApiResult<Vec<Fetched>> {
+  let session = Session::login(config).await?;
+  let mut fetched = Vec::new();
+  for file in files.iter().filter(|file| is_video(&file.name)) {
+    let video = Path::new(save_path).join(&file.name);
+    let Some(stem) = video.file_stem().and_then(|stem| stem.to_str()) else {
+      continue;
+    };
+    let query = stem.replace(['.', '_'], " ");
+    for language in &config.languages {
+      let Some(file_id) = session.search(&query, language).await? else {
+        continue;
+      };
+      let link = session.link(file_id).await?;
+      let srt = session
+        .http
+        .get(&link)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+      if let Some(dir) = video.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+      }
+      tokio::fs::write(video.with_extension(format!("{language}.srt")), srt).await?;
+      fetched.push(Fetched {
+        video: file.name.clone(),
+        language: language.clone(),
+        link,
+      });
+    }
+  }
+  Ok(fetched)
+}
+
+/// `/subtitles <hash>` fetches subtitles for a torrent's videos and links them.
+#[allow(clippy::too_many_arguments)]
+pub async fn subtitles(
+  bot: Bot,
+  msg: Message,
+  hash: String,
+  torrent: TorrentApi,
+  events: TorrentEvents,
+  config: Arc<Config>,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(opensubtitles) = &config.opensubtitles else {
+    bot
+      .send_message(msg.chat.id, lang.t("subtitles.disabled"))
+      .await?;
+    return Ok(());
+  };
+  let snapshot = {
+    let torrents = events.torrents();
+    let current = torrents.borrow();
+    let all: Vec<_> = current
+      .values()
+      .filter(|torrent| !parental.hides(msg.chat.id, torrent))
+      .collect();
+    watch::find(&all, hash.trim()).cloned()
+  };
+  let Some(snapshot) = snapshot.filter(|_| !hash.trim().is_empty()) else {
+    bot
+      .send_message(msg.chat.id, lang.t("subtitles.usage"))
+      .await?;
+    return Ok(());
+  };
+
+  let files = torrent.files(&snapshot.hash).await?;
+  let fetched = fetch(opensubtitles, &snapshot.save_path, &files).await?;
+  if fetched.is_empty() {
+    bot
+      .send_message(msg.chat.id, lang.t("subtitles.none"))
+      .await?;
+    return Ok(());
+  }
+  let mut text = lang.tf("subtitles.found", &[("name", &snapshot.name)]);
+  for subtitle in fetched {
+    text.push_str(&format!(
+      "\n• {} [{}]: {}",
+      subtitle.video, subtitle.language, subtitle.link
+    ));
+  }
+  bot.send_message(msg.chat.id, text).await?;
+  Ok(())
+}
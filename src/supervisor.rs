@@ -0,0 +1,79 @@
+use std::{
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  time::{Duration, Instant},
+};
+use teloxide::prelude::*;
+
+use crate::{alert, db::Db, format::format_eta, i18n, status, torrent::TorrentApi, HandlerResult};
+
+const CHECK_EVERY: Duration = Duration::from_secs(30);
+const FIRST_RETRY: Duration = Duration::from_secs(5);
+const MAX_RETRY: Duration = Duration::from_secs(5 * 60);
+
+/// Whether qBittorrent answered the last health check.
+#[derive(Debug, Clone)]
+pub struct Connection {
+  online: Arc<AtomicBool>,
+}
+
+impl Default for Connection {
+  fn default() -> Self {
+    Connection {
+      online: Arc::new(AtomicBool::new(true)),
+    }
+  }
+}
+
+impl Connection {
+  pub fn is_online(&self) -> bool {
+    self.online.load(Ordering::Relaxed)
+  }
+}
+
+/// Pings qBittorrent and, once it stops answering, logs in again with backoff until it does.
+/// The admin chat hears when the connection is lost and when it's back.
+pub fn spawn(api: TorrentApi, connection: Connection) {
+  tokio::spawn(async move {
+    let mut ticker = tokio::time::interval(CHECK_EVERY);
+    loop {
+      ticker.tick().await;
+      let Err(err) = api.ping().await else {
+        continue;
+      };
+      connection.online.store(false, Ordering::Relaxed);
+      let lost = Instant::now();
+      log::warn!("Lost the connection to qBittorrent: {err}");
+      status::record_error(format!("lost the connection to qBittorrent: {err}"));
+      alert::report(format!("🔌 Lost the connection to qBittorrent: {err}"));
+
+      let mut retry = FIRST_RETRY;
+      loop {
+        tokio::time::sleep(retry).await;
+        if let Err(err) = api.login().await {
+          log::debug!("qBittorrent login failed: {err}");
+        }
+        if api.ping().await.is_ok() {
+          break;
+        }
+        retry = (retry * 2).min(MAX_RETRY);
+      }
+      connection.online.store(true, Ordering::Relaxed);
+      let down_for = format_eta(lost.elapsed().as_secs() as i64);
+      log::info!("Reconnected to qBittorrent after {down_for}");
+      alert::report(format!("🔌 Reconnected to qBittorrent after {down_for}"));
+      ticker.reset();
+    }
+  });
+}
+
+/// Answers commands that need qBittorrent while the supervisor is still reconnecting.
+pub async fn offline(bot: Bot, msg: Message, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  bot
+    .send_message(msg.chat.id, lang.t("qbit.offline"))
+    .await?;
+  Ok(())
+}
@@ -0,0 +1,255 @@
+use std::sync::Arc;
+use teloxide::{prelude::*, types::InlineKeyboardMarkup};
+
+use crate::{
+  add_keyboard, audit, callback_data,
+  config::Config,
+  db::{AddTemplate, Db},
+  duplicates,
+  guard::{self, Candidate},
+  i18n::{self, Lang},
+  parental::{self, Parental},
+  quota::Quotas,
+  torrent::{self, AddTorrentArg, TorrentApi},
+  HandlerResult,
+};
+
+// what the "No template" button sends instead of a name
+const NONE: &str = "-";
+
+/// Names end up in callback data, so they stay short and free of the `:` separator.
+fn valid_name(name: &str) -> bool {
+  name.len() <= 32
+    && name
+      .chars()
+      .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    && name != NONE
+}
+
+fn apply(template: &AddTemplate, arg: &mut AddTorrentArg) {
+  arg.category = template.category.clone();
+  arg.save_path = template.save_path.clone();
+  arg.tags = template.tags.clone();
+  arg.paused = template.paused;
+  if template.sequential {
+    arg.sequential_download = true;
+    arg.first_last_piece_prio = true;
+  }
+}
+
+fn describe(template: &AddTemplate, lang: Lang) -> String {
+  let mut parts = Vec::new();
+  if let Some(category) = &template.category {
+    parts.push(lang.tf("template.category", &[("category", category)]));
+  }
+  if let Some(path) = &template.save_path {
+    parts.push(lang.tf("template.path", &[("path", path)]));
+  }
+  if let Some(tags) = &template.tags {
+    parts.push(lang.tf("template.tags", &[("tags", tags)]));
+  }
+  if template.paused {
+    parts.push(lang.t("template.paused").to_string());
+  }
+  if template.sequential {
+    parts.push(lang.t("template.sequential").to_string());
+  }
+  if parts.is_empty() {
+    parts.push(lang.t("template.defaults").to_string());
+  }
+  format!("{}: {}", template.name, parts.join(", "))
+}
+
+/// `/template <name> [category:<c>] [path:<p>] [tags:<a,b>] [paused] [sequential]` saves a
+/// template, `/template <name> delete` removes it, no arguments lists them.
+pub async fn template(bot: Bot, msg: Message, args: String, db: Db) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let Some(user) = msg.from() else {
+    return Ok(());
+  };
+  let mut words = args.split_whitespace();
+  let Some(name) = words.next() else {
+    let templates = db.templates(user.id.0)?;
+    let text = if templates.is_empty() {
+      lang.t("template.none").to_string()
+    } else {
+      templates
+        .iter()
+        .map(|template| describe(template, lang))
+        .collect::<Vec<_>>()
+        .join("\n")
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    bot
+      .send_message(msg.chat.id, lang.t("template.usage"))
+      .await?;
+    return Ok(());
+  };
+  if !valid_name(name) {
+    bot
+      .send_message(msg.chat.id, lang.t("template.usage"))
+      .await?;
+    return Ok(());
+  }
+
+  let options: Vec<&str> = words.collect();
+  if options == ["delete"] {
+    let key = if db.delete_template(user.id.0, name)? {
+      audit::record(&db, user, format!("deleted the {name} template"), None);
+      "template.deleted"
+    } else {
+      "template.unknown"
+    };
+    let text = lang.tf(key, &[("name", &name)]);
+    bot.send_message(msg.chat.id, text).await?;
+    return Ok(());
+  }
+
+  let mut template = AddTemplate {
+    name: name.to_string(),
+    ..Default::default()
+  };
+  for option in options {
+    match option.split_once(':') {
+      None if option == "paused" => template.paused = true,
+      None if option == "sequential" => template.sequential = true,
+      Some((key, value)) if !value.is_empty() && key == "category" => {
+        template.category = Some(value.to_string())
+      }
+      Some((key, value)) if !value.is_empty() && key == "path" => {
+        template.save_path = Some(value.to_string())
+      }
+      Some((key, value)) if !value.is_empty() && key == "tags" => {
+        template.tags = Some(value.to_string())
+      }
+      _ => {
+        bot
+          .send_message(msg.chat.id, lang.t("template.usage"))
+          .await?;
+        return Ok(());
+      }
+    }
+  }
+  db.set_template(user.id.0, &template)?;
+  audit::record(&db, user, format!("saved the {name} template"), None);
+  let text = lang.tf(
+    "template.saved",
+    &[("template", &describe(&template, lang))],
+  );
+  bot.send_message(msg.chat.id, text).await?;
+  Ok(())
+}
+
+/// One button per template and one to add without, `None` when the user has no templates.
+pub fn keyboard(templates: &[AddTemplate], link: &str, lang: Lang) -> Option<InlineKeyboardMarkup> {
+  if templates.is_empty() {
+    return None;
+  }
+  let mut rows: Vec<_> = templates
+    .chunks(2)
+    .map(|chunk| {
+      chunk
+        .iter()
+        .map(|template| {
+          callback_data::button(
+            format!("📐 {}", template.name),
+            format!("tpl:{}:{link}", template.name),
+          )
+        })
+        .collect()
+    })
+    .collect();
+  rows.push(vec![callback_data::button(
+    lang.t("template.without"),
+    format!("tpl:{NONE}:{link}"),
+  )]);
+  Some(InlineKeyboardMarkup::new(rows))
+}
+
+/// Adds the link with the picked template. Duplicates were checked before the keyboard was
+/// offered, the parental, quota and add policy checks run again since time has passed.
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  torrent: TorrentApi,
+  config: Arc<Config>,
+  quotas: Quotas,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  let (Some((name, link)), Some(prompt)) = (
+    q.data
+      .as_deref()
+      .and_then(|data| data.strip_prefix("tpl:"))
+      .and_then(|data| data.split_once(':')),
+    q.message.as_ref(),
+  ) else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+  let template = match name {
+    NONE => None,
+    name => match db.template(q.from.id.0, name)? {
+      Some(template) => Some(template),
+      None => {
+        let text = lang.tf("template.unknown", &[("name", &name)]);
+        bot.answer_callback_query(q.id).text(text).await?;
+        return Ok(());
+      }
+    },
+  };
+  if parental::magnet_name(link).is_some_and(|name| parental.blocks(prompt.chat.id, &name)) {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("parental.blocked"))
+      .await?;
+    return Ok(());
+  }
+  if let Err(reason) = quotas.check(q.from.id.0, duplicates::magnet_size(link), lang) {
+    bot.answer_callback_query(q.id).text(reason).await?;
+    return Ok(());
+  }
+
+  bot.answer_callback_query(q.id).await?;
+  let candidate = Candidate::from_link(link);
+  if let Some(rejection) = guard::check(&torrent, &config.guard, &candidate).await? {
+    bot
+      .edit_message_reply_markup(prompt.chat.id, prompt.id)
+      .await?;
+    return guard::reject(&bot, prompt.chat.id, &rejection, Some(link), lang).await;
+  }
+  let hash = torrent::extract_info_hash(link);
+  let mut arg = AddTorrentArg {
+    urls: vec![link.to_string()],
+    ..Default::default()
+  };
+  if let Some(template) = &template {
+    apply(template, &mut arg);
+  }
+  let reply = match torrent.add_torrent(&arg).await {
+    Ok(()) => {
+      if let Some(hash) = &hash {
+        quotas.record(q.from.id.0, hash, candidate.size);
+        guard::recheck(hash, prompt.chat.id);
+      }
+      let action = match &template {
+        Some(template) => format!("added a magnet with the {} template", template.name),
+        None => "added a magnet".to_string(),
+      };
+      audit::record(&db, &q.from, action, hash.as_deref());
+      lang.t("torrent.added").to_string()
+    }
+    Err(err) => err.to_string(),
+  };
+  let edit = bot.edit_message_text(prompt.chat.id, prompt.id, reply);
+  match hash {
+    Some(hash) => {
+      edit
+        .reply_markup(add_keyboard(&hash, &config, lang))
+        .await?
+    }
+    None => edit.await?,
+  };
+  Ok(())
+}
@@ -0,0 +1,75 @@
+use teloxide::{prelude::*, types::ParseMode};
+
+use crate::{
+  db::Db,
+  i18n,
+  parental::Parental,
+  render, settings,
+  torrent::{ListQuery, TorrentApi},
+  HandlerResult,
+};
+
+/// `/top <metric>` sort keys, as understood by `torrents/info`.
+fn sort_key(metric: &str) -> Option<&'static str> {
+  match metric {
+    "speed" => Some("dlspeed"),
+    "size" => Some("size"),
+    "ratio" => Some("ratio"),
+    "peers" => Some("num_leechs"),
+    _ => None,
+  }
+}
+
+/// `/top speed|size|ratio|peers` lists the heaviest torrents, as many as the user's page size.
+pub async fn top(
+  bot: Bot,
+  msg: Message,
+  metric: String,
+  torrent: TorrentApi,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let metric = metric.trim().to_lowercase();
+  let metric = if metric.is_empty() { "speed" } else { &metric };
+  let Some(sort) = sort_key(metric) else {
+    bot.send_message(msg.chat.id, lang.t("top.usage")).await?;
+    return Ok(());
+  };
+
+  let settings = settings::of(&db, msg.from());
+  let count = settings.page_size as usize;
+  // hidden torrents would eat into the limit, so restricted chats filter the whole list
+  let restricted = parental.is_restricted(msg.chat.id);
+  let query = ListQuery {
+    sort: Some(sort.to_string()),
+    reverse: true,
+    limit: (!restricted).then_some(count),
+    ..Default::default()
+  };
+  let torrents: Vec<_> = torrent
+    .list_with(&query)
+    .await?
+    .into_iter()
+    .filter(|torrent| !parental.hides_labelled(msg.chat.id, &torrent.category, &torrent.tags))
+    .take(count)
+    .collect();
+  if torrents.is_empty() {
+    bot.send_message(msg.chat.id, lang.t("top.empty")).await?;
+    return Ok(());
+  }
+
+  let mut text = lang.tf("top.title", &[("metric", &metric)]);
+  for (rank, torrent) in torrents.iter().enumerate() {
+    text.push_str(&format!(
+      "\n\n{}. {}",
+      rank + 1,
+      render::torrent_item(torrent, settings.detailed_list, settings.units())
+    ));
+  }
+  bot
+    .send_message(msg.chat.id, text)
+    .parse_mode(ParseMode::Html)
+    .await?;
+  Ok(())
+}
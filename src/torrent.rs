@@ -1,18 +1,830 @@
-use qbit_api_rs::{client::QbitClient, error::ClientError};
-use std::sync::Arc;
+use serde::Deserialize;
+use std::{collections::HashMap, env, fs, path::PathBuf, sync::Arc};
+use tokio::sync::RwLock;
+
+pub mod events;
+pub mod metainfo;
+
+pub type ApiResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// File priorities understood by the qBittorrent Web API.
+pub const PRIORITY_SKIP: u8 = 0;
+pub const PRIORITY_NORMAL: u8 = 1;
+pub const PRIORITY_HIGH: u8 = 6;
 
 #[derive(Debug, Clone)]
 pub struct TorrentApi {
-  pub client: Arc<QbitClient>,
+  // carries the proxy and TLS options, every Web API call goes through it
+  http: reqwest::Client,
+  host: String,
+  username: String,
+  password: String,
+  sid: Arc<RwLock<Option<String>>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TorrentFile {
+  pub index: usize,
+  pub name: String,
+  pub size: u64,
+  pub progress: f64,
+  pub priority: u8,
+}
+
+/// One entry of `torrents/trackers`, DHT, PeX and LSD included.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Tracker {
+  pub url: String,
+  /// 0 disabled, 1 not contacted yet, 2 working, 3 updating, 4 not working.
+  pub status: u8,
+  /// What the tracker answered last, e.g. "unregistered torrent".
+  #[serde(default)]
+  pub msg: String,
+}
+
+impl Tracker {
+  pub fn is_working(&self) -> bool {
+    self.status != 4
+  }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TorrentInfo {
+  pub hash: String,
+  pub name: String,
+  pub state: String,
+  pub progress: f64,
+  pub size: u64,
+  pub dlspeed: u64,
+  pub upspeed: u64,
+  pub eta: i64,
+  pub ratio: f64,
+  /// All-time bytes, the ratio is `uploaded / downloaded`.
+  #[serde(default)]
+  pub uploaded: u64,
+  #[serde(default)]
+  pub downloaded: u64,
+  #[serde(default)]
+  pub category: String,
+  #[serde(default)]
+  pub tags: String,
+  pub added_on: i64,
+  #[serde(default)]
+  pub tracker: String,
+  #[serde(default)]
+  pub magnet_uri: String,
+  #[serde(default)]
+  pub seq_dl: bool,
+  #[serde(default)]
+  pub f_l_piece_prio: bool,
+  #[serde(default)]
+  pub save_path: String,
+  #[serde(default)]
+  pub num_seeds: u64,
+  #[serde(default)]
+  pub num_leechs: u64,
+  /// Queue position starting at 1, `0` or `-1` when the torrent isn't queued.
+  #[serde(default)]
+  pub priority: i64,
+  /// Both set for hybrid torrents, empty when the torrent lacks that version or qBittorrent
+  /// predates 4.4.
+  #[serde(default)]
+  pub infohash_v1: String,
+  #[serde(default)]
+  pub infohash_v2: String,
+}
+
+impl TorrentInfo {
+  /// Whether `id` (as [`extract_info_hash`] gives it) names this torrent, also when it came
+  /// from the other half of a hybrid torrent.
+  pub fn has_hash(&self, id: &str) -> bool {
+    [&self.hash, &self.infohash_v1]
+      .into_iter()
+      .any(|hash| hash.eq_ignore_ascii_case(id))
+      || self
+        .infohash_v2
+        .get(..V1_HEX_LEN)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(id))
+  }
+}
+
+/// Server-side sorting and paging for `torrents/info`.
+#[derive(Debug, Clone, Default)]
+pub struct ListQuery {
+  /// Any [`TorrentInfo`] field name, e.g. `size` or `ratio`.
+  pub sort: Option<String>,
+  pub reverse: bool,
+  pub limit: Option<usize>,
+  pub offset: Option<usize>,
+  /// State filter, e.g. `downloading`, `completed` or `errored`.
+  pub filter: Option<String>,
+  /// Only this category, `Some("")` for torrents without one.
+  pub category: Option<String>,
+  /// Only this tag, `Some("")` for untagged torrents.
+  pub tag: Option<String>,
+}
+
+/// Options sent along with a new torrent to `torrents/add`.
+#[derive(Debug, Clone, Default)]
+pub struct AddTorrentArg {
+  pub urls: Vec<String>,
+  pub sequential_download: bool,
+  pub first_last_piece_prio: bool,
+  /// Uploaded .torrent files as (file name, contents).
+  pub files: Vec<(String, Vec<u8>)>,
+  /// Download directory, qBittorrent's default when unset.
+  pub save_path: Option<String>,
+  pub category: Option<String>,
+  /// Comma-separated.
+  pub tags: Option<String>,
+  /// Added without starting.
+  pub paused: bool,
+}
+
+/// Connection options for [`TorrentApi`] beyond the `QBIT_*` credentials.
+#[derive(Debug, Clone, Default)]
+pub struct TorrentApiBuilder {
+  /// http://, https:// or socks5:// URL, used for the qBittorrent requests only.
+  proxy: Option<String>,
+  /// PEM encoded certificate to trust, e.g. the CA behind a self-signed `QBIT_HOST`.
+  ca_cert: Option<PathBuf>,
+  /// Skips certificate verification entirely. Only for hosts you control.
+  accept_invalid_certs: bool,
+}
+
+impl TorrentApiBuilder {
+  /// Picks up `QBIT_PROXY`, `QBIT_CA_CERT` and `QBIT_INSECURE_TLS`.
+  pub fn from_env() -> Self {
+    let var = |name| env::var(name).ok().filter(|value| !value.is_empty());
+    TorrentApiBuilder {
+      proxy: var("QBIT_PROXY"),
+      ca_cert: var("QBIT_CA_CERT").map(PathBuf::from),
+      accept_invalid_certs: var("QBIT_INSECURE_TLS").is_some_and(|value| value == "true"),
+    }
+  }
+
+  pub fn build(self) -> ApiResult<TorrentApi> {
+    let mut http = reqwest::Client::builder();
+    if let Some(proxy) = &self.proxy {
+      let proxy = reqwest::Proxy::all(proxy).map_err(|err| format!("QBIT_PROXY {proxy}: {err}"))?;
+      http = http.proxy(proxy);
+    }
+    if let Some(path) = &self.ca_cert {
+      let certificate = fs::read(path)
+        .map_err(|err| err.to_string())
+        .and_then(|pem| reqwest::Certificate::from_pem(&pem).map_err(|err| err.to_string()))
+        .map_err(|err| format!("QBIT_CA_CERT {}: {err}", path.display()))?;
+      http = http.add_root_certificate(certificate);
+    }
+    if self.accept_invalid_certs {
+      log::warn!("TLS certificate verification is disabled for QBIT_HOST");
+      http = http.danger_accept_invalid_certs(true);
+    }
+
+    let host = env::var("QBIT_HOST").unwrap_or_else(|_| "http://127.0.0.1:8080".to_string());
+    Ok(TorrentApi {
+      http: http.build()?,
+      host: host.trim_end_matches('/').to_string(),
+      username: env::var("QBIT_USERNAME").unwrap_or_else(|_| "admin".to_string()),
+      password: env::var("QBIT_PASSWORD").unwrap_or_default(),
+      sid: Arc::new(RwLock::new(None)),
+    })
+  }
 }
 
 impl TorrentApi {
-  pub fn new() -> Self {
-    let client = Arc::new(QbitClient::new_from_env().unwrap());
-    TorrentApi { client }
+  /// The client configured by the `QBIT_*` variables, `Err` when they don't make sense.
+  pub fn new() -> ApiResult<Self> {
+    TorrentApiBuilder::from_env().build()
+  }
+
+  /// Starts a Web API session, its cookie is sent along with every request after this.
+  pub async fn login(&self) -> ApiResult<()> {
+    let res = self
+      .http
+      .post(format!("{}/api/v2/auth/login", self.host))
+      .header(reqwest::header::REFERER, &self.host)
+      .form(&[("username", &self.username), ("password", &self.password)])
+      .send()
+      .await?;
+
+    let sid = res
+      .headers()
+      .get_all(reqwest::header::SET_COOKIE)
+      .iter()
+      .filter_map(|value| value.to_str().ok())
+      .find_map(|cookie| cookie.strip_prefix("SID="))
+      .and_then(|cookie| cookie.split(';').next())
+      .map(ToOwned::to_owned);
+
+    match sid {
+      Some(sid) => {
+        *self.sid.write().await = Some(sid);
+        Ok(())
+      }
+      None => Err(format!("login rejected: {}", res.text().await?).into()),
+    }
+  }
+
+  async fn send(&self, builder: reqwest::RequestBuilder) -> ApiResult<reqwest::Response> {
+    let sid = self.sid.read().await.clone().unwrap_or_default();
+    let res = builder
+      .header(reqwest::header::COOKIE, format!("SID={sid}"))
+      .send()
+      .await?;
+    Ok(res.error_for_status()?)
+  }
+
+  /// Sends a request built by `build`, logging in again once if the session expired.
+  async fn request<F>(&self, build: F) -> ApiResult<reqwest::Response>
+  where
+    F: Fn(&reqwest::Client, &str) -> reqwest::RequestBuilder,
+  {
+    let api = format!("{}/api/v2", self.host);
+    match self.send(build(&self.http, &api)).await {
+      Err(err) if http_status(err.as_ref()) == Some(reqwest::StatusCode::FORBIDDEN) => {
+        self.login().await?;
+        self.send(build(&self.http, &api)).await
+      }
+      res => res,
+    }
+  }
+
+  pub async fn files(&self, hash: &str) -> ApiResult<Vec<TorrentFile>> {
+    let res = self
+      .request(|http, api| {
+        http
+          .get(format!("{api}/torrents/files"))
+          .query(&[("hash", hash)])
+      })
+      .await?;
+    Ok(res.json().await?)
+  }
+
+  pub async fn trackers(&self, hash: &str) -> ApiResult<Vec<Tracker>> {
+    let res = self
+      .request(|http, api| {
+        http
+          .get(format!("{api}/torrents/trackers"))
+          .query(&[("hash", hash)])
+      })
+      .await?;
+    Ok(res.json().await?)
+  }
+
+  pub async fn set_file_priority(
+    &self,
+    hash: &str,
+    indices: &[usize],
+    priority: u8,
+  ) -> ApiResult<()> {
+    if indices.is_empty() {
+      return Ok(());
+    }
+    let ids = indices
+      .iter()
+      .map(ToString::to_string)
+      .collect::<Vec<_>>()
+      .join("|");
+    let priority = priority.to_string();
+    self
+      .request(|http, api| {
+        http.post(format!("{api}/torrents/filePrio")).form(&[
+          ("hash", hash),
+          ("id", ids.as_str()),
+          ("priority", priority.as_str()),
+        ])
+      })
+      .await?;
+    Ok(())
+  }
+
+  /// Marks every file but the ones at `indices` as "do not download", in a single `filePrio`
+  /// call. The chosen files keep their priority, normal unless it was changed since adding.
+  pub async fn download_only_files(&self, hash: &str, indices: &[usize]) -> ApiResult<()> {
+    let skipped: Vec<usize> = self
+      .files(hash)
+      .await?
+      .into_iter()
+      .map(|file| file.index)
+      .filter(|index| !indices.contains(index))
+      .collect();
+    self.set_file_priority(hash, &skipped, PRIORITY_SKIP).await
+  }
+
+  pub async fn add_torrent(&self, arg: &AddTorrentArg) -> ApiResult<()> {
+    let res = self
+      .request(|http, api| {
+        let mut form = reqwest::multipart::Form::new()
+          .text("sequentialDownload", arg.sequential_download.to_string())
+          .text("firstLastPiecePrio", arg.first_last_piece_prio.to_string());
+        if !arg.urls.is_empty() {
+          form = form.text("urls", arg.urls.join("\n"));
+        }
+        if let Some(save_path) = &arg.save_path {
+          form = form.text("savepath", save_path.clone());
+        }
+        if let Some(category) = &arg.category {
+          form = form.text("category", category.clone());
+        }
+        if let Some(tags) = &arg.tags {
+          form = form.text("tags", tags.clone());
+        }
+        if arg.paused {
+          // qBittorrent 5 renamed the field along with pause/resume
+          form = form.text("paused", "true").text("stopped", "true");
+        }
+        for (name, contents) in &arg.files {
+          let part = reqwest::multipart::Part::bytes(contents.clone()).file_name(name.clone());
+          form = form.part("torrents", part);
+        }
+        http.post(format!("{api}/torrents/add")).multipart(form)
+      })
+      .await?;
+
+    match res.text().await?.trim() {
+      "Ok." => Ok(()),
+      reply => Err(format!("qBittorrent refused the torrent: {reply}").into()),
+    }
+  }
+
+  /// Cheap reachability and authentication check, returns the qBittorrent version.
+  pub async fn ping(&self) -> ApiResult<String> {
+    let res = self
+      .request(|http, api| http.get(format!("{api}/app/version")))
+      .await?;
+    Ok(res.text().await?)
+  }
+
+  /// Asks qBittorrent to exit. It does not come back on its own unless a supervisor restarts it.
+  pub async fn shutdown(&self) -> ApiResult<()> {
+    self
+      .request(|http, api| http.post(format!("{api}/app/shutdown")))
+      .await?;
+    Ok(())
+  }
+
+  /// Changes since response `rid`, `0` asks for everything.
+  pub async fn maindata(&self, rid: i64) -> ApiResult<events::MainData> {
+    let rid = rid.to_string();
+    let res = self
+      .request(|http, api| {
+        http
+          .get(format!("{api}/sync/maindata"))
+          .query(&[("rid", rid.as_str())])
+      })
+      .await?;
+    Ok(res.json().await?)
+  }
+
+  /// Lists every torrent known to qBittorrent.
+  pub async fn list(&self) -> ApiResult<Vec<TorrentInfo>> {
+    let res = self
+      .request(|http, api| http.get(format!("{api}/torrents/info")))
+      .await?;
+    Ok(res.json().await?)
+  }
+
+  pub async fn list_with(&self, query: &ListQuery) -> ApiResult<Vec<TorrentInfo>> {
+    let mut params = vec![("reverse", query.reverse.to_string())];
+    if let Some(sort) = &query.sort {
+      params.push(("sort", sort.clone()));
+    }
+    if let Some(limit) = query.limit {
+      params.push(("limit", limit.to_string()));
+    }
+    if let Some(offset) = query.offset {
+      params.push(("offset", offset.to_string()));
+    }
+    if let Some(filter) = &query.filter {
+      params.push(("filter", filter.clone()));
+    }
+    if let Some(category) = &query.category {
+      params.push(("category", category.clone()));
+    }
+    if let Some(tag) = &query.tag {
+      params.push(("tag", tag.clone()));
+    }
+    let res = self
+      .request(|http, api| http.get(format!("{api}/torrents/info")).query(&params))
+      .await?;
+    Ok(res.json().await?)
+  }
+
+  pub async fn info(&self, hash: &str) -> ApiResult<Option<TorrentInfo>> {
+    let res = self
+      .request(|http, api| {
+        http
+          .get(format!("{api}/torrents/info"))
+          .query(&[("hashes", hash)])
+      })
+      .await?;
+    let torrents: Vec<TorrentInfo> = res.json().await?;
+    Ok(torrents.into_iter().next())
+  }
+
+  /// The .torrent file of `hash`, needs qBittorrent 4.5 or later.
+  pub async fn export(&self, hash: &str) -> ApiResult<Vec<u8>> {
+    let res = self
+      .request(|http, api| {
+        http
+          .get(format!("{api}/torrents/export"))
+          .query(&[("hash", hash)])
+      })
+      .await?;
+    Ok(res.bytes().await?.to_vec())
+  }
+
+  /// Turns sequential download and first/last piece priority on or off, only toggling
+  /// the flags that are not already in the requested state.
+  pub async fn set_sequential_download(&self, hash: &str, enabled: bool) -> ApiResult<()> {
+    let Some(info) = self.info(hash).await? else {
+      return Err(format!("no torrent with hash {hash}").into());
+    };
+    if info.seq_dl != enabled {
+      self.toggle_sequential_download(hash).await?;
+    }
+    if info.f_l_piece_prio != enabled {
+      self.toggle_first_last_piece_prio(hash).await?;
+    }
+    Ok(())
+  }
+
+  /// Names of every category.
+  pub async fn categories(&self) -> ApiResult<Vec<String>> {
+    let res = self
+      .request(|http, api| http.get(format!("{api}/torrents/categories")))
+      .await?;
+    let categories: HashMap<String, serde_json::Value> = res.json().await?;
+    let mut names: Vec<_> = categories.into_keys().collect();
+    names.sort();
+    Ok(names)
+  }
+
+  pub async fn tags(&self) -> ApiResult<Vec<String>> {
+    let res = self
+      .request(|http, api| http.get(format!("{api}/torrents/tags")))
+      .await?;
+    Ok(res.json().await?)
+  }
+
+  /// Creating a category that already exists is not an error.
+  pub async fn create_category(&self, category: &str) -> ApiResult<()> {
+    let res = self
+      .request(|http, api| {
+        http
+          .post(format!("{api}/torrents/createCategory"))
+          .form(&[("category", category)])
+      })
+      .await;
+    match res {
+      Err(err) if http_status(err.as_ref()) == Some(reqwest::StatusCode::CONFLICT) => Ok(()),
+      res => res.map(|_| ()),
+    }
+  }
+
+  pub async fn set_category(&self, hashes: &[String], category: &str) -> ApiResult<()> {
+    let hashes = hashes.join("|");
+    self
+      .request(|http, api| {
+        http
+          .post(format!("{api}/torrents/setCategory"))
+          .form(&[("hashes", hashes.as_str()), ("category", category)])
+      })
+      .await?;
+    Ok(())
+  }
+
+  /// With automatic management qBittorrent moves the data into the category's save path.
+  pub async fn set_auto_management(&self, hashes: &[String], enable: bool) -> ApiResult<()> {
+    let hashes = hashes.join("|");
+    let enable = enable.to_string();
+    self
+      .request(|http, api| {
+        http
+          .post(format!("{api}/torrents/setAutoManagement"))
+          .form(&[("hashes", hashes.as_str()), ("enable", enable.as_str())])
+      })
+      .await?;
+    Ok(())
+  }
+
+  /// Posts `form` to `torrents/<endpoint>`.
+  async fn post(&self, endpoint: &str, form: &[(&str, &str)]) -> ApiResult<reqwest::Response> {
+    self
+      .request(|http, api| http.post(format!("{api}/torrents/{endpoint}")).form(form))
+      .await
+  }
+
+  /// qBittorrent 5 renamed a few endpoints, try the old name first and fall back to the new one.
+  async fn post_renamed(&self, old: &str, new: &str, form: &[(&str, &str)]) -> ApiResult<()> {
+    match self.post(old, form).await {
+      Err(err) if http_status(err.as_ref()) == Some(reqwest::StatusCode::NOT_FOUND) => {
+        self.post(new, form).await.map(|_| ())
+      }
+      res => res.map(|_| ()),
+    }
+  }
+
+  /// `hashes` is a `|`-separated list, or `all`.
+  pub async fn delete(&self, hashes: &str, delete_files: bool) -> ApiResult<()> {
+    let delete_files = delete_files.to_string();
+    self
+      .post(
+        "delete",
+        &[("hashes", hashes), ("deleteFiles", delete_files.as_str())],
+      )
+      .await?;
+    Ok(())
+  }
+
+  /// Moves the data of `hashes` (a `|`-separated list) to `location`.
+  pub async fn set_location(&self, hashes: &str, location: &str) -> ApiResult<()> {
+    self
+      .post("setLocation", &[("hashes", hashes), ("location", location)])
+      .await?;
+    Ok(())
+  }
+
+  /// `tags` is a comma-separated list.
+  pub async fn add_tags(&self, hashes: &str, tags: &str) -> ApiResult<()> {
+    self
+      .post("addTags", &[("hashes", hashes), ("tags", tags)])
+      .await?;
+    Ok(())
+  }
+
+  /// `hashes` is a `|`-separated list, or `all`.
+  pub async fn pause(&self, hashes: &str) -> ApiResult<()> {
+    self
+      .post_renamed("pause", "stop", &[("hashes", hashes)])
+      .await
+  }
+
+  pub async fn resume(&self, hashes: &str) -> ApiResult<()> {
+    self
+      .post_renamed("resume", "start", &[("hashes", hashes)])
+      .await
+  }
+
+  /// Asks the trackers for peers right away instead of at the next announce interval.
+  pub async fn reannounce(&self, hashes: &str) -> ApiResult<()> {
+    self.post("reannounce", &[("hashes", hashes)]).await?;
+    Ok(())
+  }
+
+  /// Force-started torrents run regardless of the queue limits.
+  pub async fn set_force_start(&self, hashes: &str, enabled: bool) -> ApiResult<()> {
+    let value = enabled.to_string();
+    self
+      .post("setForceStart", &[("hashes", hashes), ("value", &value)])
+      .await?;
+    Ok(())
+  }
+
+  /// Moves the torrent to the top (`top = true`) or bottom of the download queue.
+  pub async fn set_queue_position(&self, hash: &str, top: bool) -> ApiResult<()> {
+    let endpoint = if top { "topPrio" } else { "bottomPrio" };
+    self
+      .request(|http, api| {
+        http
+          .post(format!("{api}/torrents/{endpoint}"))
+          .form(&[("hashes", hash)])
+      })
+      .await?;
+    Ok(())
+  }
+
+  /// Moves `hash` one place up or down the queue.
+  pub async fn step_queue_position(&self, hash: &str, up: bool) -> ApiResult<()> {
+    let endpoint = if up { "increasePrio" } else { "decreasePrio" };
+    self.post(endpoint, &[("hashes", hash)]).await?;
+    Ok(())
+  }
+
+  /// Limits are in bytes per second, `0` removes the limit.
+  pub async fn set_upload_limit(&self, hash: &str, limit: u64) -> ApiResult<()> {
+    let limit = limit.to_string();
+    self
+      .request(|http, api| {
+        http
+          .post(format!("{api}/torrents/setUploadLimit"))
+          .form(&[("hashes", hash), ("limit", limit.as_str())])
+      })
+      .await?;
+    Ok(())
+  }
+
+  pub async fn set_download_limit(&self, hash: &str, limit: u64) -> ApiResult<()> {
+    let limit = limit.to_string();
+    self
+      .request(|http, api| {
+        http
+          .post(format!("{api}/torrents/setDownloadLimit"))
+          .form(&[("hashes", hash), ("limit", limit.as_str())])
+      })
+      .await?;
+    Ok(())
+  }
+
+  /// Flips sequential download on existing torrents; new ones get it through [`AddTorrentArg`].
+  pub async fn toggle_sequential_download(&self, hash: &str) -> ApiResult<()> {
+    self
+      .request(|http, api| {
+        http
+          .post(format!("{api}/torrents/toggleSequentialDownload"))
+          .form(&[("hashes", hash)])
+      })
+      .await?;
+    Ok(())
+  }
+
+  pub async fn toggle_first_last_piece_prio(&self, hash: &str) -> ApiResult<()> {
+    self
+      .request(|http, api| {
+        http
+          .post(format!("{api}/torrents/toggleFirstLastPiecePrio"))
+          .form(&[("hashes", hash)])
+      })
+      .await?;
+    Ok(())
+  }
+
+  /// Whether the alternative speed limits are in effect.
+  pub async fn alt_speed_enabled(&self) -> ApiResult<bool> {
+    let res = self
+      .request(|http, api| http.get(format!("{api}/transfer/speedLimitsMode")))
+      .await?;
+    Ok(res.text().await?.trim() == "1")
+  }
+
+  pub async fn toggle_alt_speed(&self) -> ApiResult<()> {
+    self
+      .request(|http, api| http.post(format!("{api}/transfer/toggleSpeedLimitsMode")))
+      .await?;
+    Ok(())
+  }
+}
+
+fn http_status(
+  err: &(dyn std::error::Error + Send + Sync + 'static),
+) -> Option<reqwest::StatusCode> {
+  err
+    .downcast_ref::<reqwest::Error>()
+    .and_then(reqwest::Error::status)
+}
+
+/// Decodes RFC 4648 base32 (`A-Z2-7`, any case, no padding), `None` on anything else.
+fn decode_base32(text: &str) -> Option<Vec<u8>> {
+  let mut bytes = Vec::with_capacity(text.len() * 5 / 8);
+  let (mut buffer, mut bits) = (0u32, 0);
+  for c in text.chars() {
+    let value = match c.to_ascii_uppercase() {
+      c @ 'A'..='Z' => c as u32 - 'A' as u32,
+      c @ '2'..='7' => c as u32 - '2' as u32 + 26,
+      _ => return None,
+    };
+    buffer = (buffer << 5) | value;
+    bits += 5;
+    if bits >= 8 {
+      bits -= 8;
+      bytes.push((buffer >> bits) as u8);
+      buffer &= (1 << bits) - 1;
+    }
+  }
+  Some(bytes)
+}
+
+// v1 hashes are SHA-1, and qBittorrent cuts v2 ones down to this length to name torrents
+const V1_HEX_LEN: usize = 40;
+// magnets carry v2 hashes as a multihash: 0x12 for SHA-256, 0x20 for 32 bytes, then the digest
+const SHA256_MULTIHASH: &str = "1220";
+
+/// The lowercase hex SHA-256 of a v2 torrent, given as 64 hex characters or as the multihash
+/// of a `btmh` magnet.
+fn normalize_v2_hash(hash: &str) -> Option<String> {
+  let hash = match hash.len() {
+    68 => hash.strip_prefix(SHA256_MULTIHASH)?,
+    _ => hash,
+  };
+  (hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())).then(|| hash.to_lowercase())
+}
+
+/// The id qBittorrent gives the torrent of an info hash: the lowercase hex of a v1 hash (40 hex
+/// or 32 base32 characters), or a v2 hash cut to the same length. Hashes from any source compare
+/// equal this way.
+pub fn normalize_info_hash(hash: &str) -> Option<String> {
+  match hash.len() {
+    V1_HEX_LEN if hash.chars().all(|c| c.is_ascii_hexdigit()) => Some(hash.to_lowercase()),
+    32 => decode_base32(hash).map(hex::encode),
+    _ => normalize_v2_hash(hash).map(|hash| hash[..V1_HEX_LEN].to_string()),
+  }
+}
+
+/// Whether 32 characters sent on their own are a base32 hash rather than a long word: hashes
+/// come in one case, and 20 random bytes all but always encode to some digits.
+fn looks_like_base32_hash(text: &str) -> bool {
+  let one_case = text == text.to_uppercase() || text == text.to_lowercase();
+  one_case && text.bytes().any(|byte| (b'2'..=b'7').contains(&byte))
+}
+
+/// A magnet for a bare info hash, so it can be added like any other.
+pub fn hash_magnet(text: &str) -> Option<String> {
+  let text = text.trim();
+  if let Some(hash) = normalize_v2_hash(text) {
+    return Some(format!("magnet:?xt=urn:btmh:{SHA256_MULTIHASH}{hash}"));
+  }
+  if text.len() == 32 && !looks_like_base32_hash(text) {
+    return None;
+  }
+  normalize_info_hash(text).map(|hash| format!("magnet:?xt=urn:btih:{hash}"))
+}
+
+/// The id of the torrent a magnet link points at, see [`normalize_info_hash`]. Hybrid magnets
+/// carry both versions, qBittorrent goes by the v1 hash then.
+pub fn extract_info_hash(magnet: &str) -> Option<String> {
+  let exact = |prefix: &str| {
+    magnet
+      .split(['?', '&'])
+      .find_map(|param| param.strip_prefix(prefix))
+  };
+  if let Some(hash) = exact("xt=urn:btih:") {
+    return Some(normalize_info_hash(hash).unwrap_or_else(|| hash.to_lowercase()));
+  }
+  exact("xt=urn:btmh:")
+    .and_then(normalize_v2_hash)
+    .map(|hash| hash[..V1_HEX_LEN].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const HEX: &str = "c12fe1c06bba254a9dc9f519b335aa7c1367a88a";
+  const BASE32: &str = "YEX6DQDLXISUVHOJ6UM3GNNKPQJWPKEK";
+  const V2: &str = "2d711642b726b04401627ca9fbac32f5c8530fb1903cc4db02258717921a4881";
+
+  #[test]
+  fn decodes_base32() {
+    assert_eq!(decode_base32("MZXW6").as_deref(), Some(&b"foo"[..]));
+    assert_eq!(decode_base32("mzxw6").as_deref(), Some(&b"foo"[..]));
+    assert_eq!(decode_base32("MZXW1"), None);
+    assert_eq!(decode_base32("MZXW6==="), None);
+  }
+
+  #[test]
+  fn normalizes_v2_hashes() {
+    assert_eq!(normalize_v2_hash(V2).as_deref(), Some(V2));
+    let multihash = format!("{SHA256_MULTIHASH}{}", V2.to_uppercase());
+    assert_eq!(normalize_v2_hash(&multihash).as_deref(), Some(V2));
+    assert_eq!(normalize_v2_hash(&format!("1120{V2}")), None);
+    assert_eq!(normalize_v2_hash(HEX), None);
+  }
+
+  #[test]
+  fn normalizes_info_hashes() {
+    assert_eq!(normalize_info_hash(HEX).as_deref(), Some(HEX));
+    assert_eq!(
+      normalize_info_hash(&HEX.to_uppercase()).as_deref(),
+      Some(HEX)
+    );
+    assert_eq!(normalize_info_hash(BASE32).as_deref(), Some(HEX));
+    assert_eq!(
+      normalize_info_hash(&BASE32.to_lowercase()).as_deref(),
+      Some(HEX)
+    );
+    assert_eq!(normalize_info_hash(V2).as_deref(), Some(&V2[..40]));
+    assert_eq!(normalize_info_hash(&HEX[1..]), None);
+    assert_eq!(normalize_info_hash(&HEX.replace('c', "x")), None);
+  }
+
+  #[test]
+  fn turns_bare_hashes_into_magnets() {
+    let v1 = format!("magnet:?xt=urn:btih:{HEX}");
+    assert_eq!(hash_magnet(&format!(" {HEX}\n")).as_deref(), Some(&*v1));
+    assert_eq!(hash_magnet(BASE32).as_deref(), Some(&*v1));
+    assert_eq!(
+      hash_magnet(V2),
+      Some(format!("magnet:?xt=urn:btmh:{SHA256_MULTIHASH}{V2}"))
+    );
+    assert_eq!(hash_magnet("magnet:?xt=urn:btih:abc"), None);
+  }
+
+  #[test]
+  fn leaves_words_that_only_look_like_base32() {
+    assert_eq!(hash_magnet("ABCDEFGHIJKLMNOPQRSTUVWXYZABCDEF"), None);
+    assert_eq!(hash_magnet("abcdefghijklmnopqrstuvwxyzabcdef"), None);
+    assert_eq!(hash_magnet("YEX6DQDLXISUVHOJ6UM3GNNKPQJWPKEk"), None);
   }
 
-  pub async fn login(&self) -> Result<String, ClientError> {
-    self.client.auth_login().await
+  #[test]
+  fn extracts_info_hashes_from_magnets() {
+    let magnet = format!("magnet:?xt=urn:btih:{BASE32}&dn=test");
+    assert_eq!(extract_info_hash(&magnet).as_deref(), Some(HEX));
+    let v2 = format!("magnet:?dn=test&xt=urn:btmh:{SHA256_MULTIHASH}{V2}");
+    assert_eq!(extract_info_hash(&v2).as_deref(), Some(&V2[..40]));
+    let hybrid = format!("magnet:?xt=urn:btmh:{SHA256_MULTIHASH}{V2}&xt=urn:btih:{HEX}");
+    assert_eq!(extract_info_hash(&hybrid).as_deref(), Some(HEX));
+    assert_eq!(extract_info_hash("magnet:?dn=test"), None);
   }
 }
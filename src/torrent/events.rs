@@ -0,0 +1,395 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::{broadcast, watch};
+
+use super::TorrentApi;
+
+// events are dropped for subscribers lagging this far behind
+const CHANNEL_CAPACITY: usize = 256;
+// progress percentages that raise a Milestone event
+const MILESTONES: [u8; 5] = [25, 50, 75, 90, 100];
+
+/// Merged view of a torrent from `sync/maindata`. Fields missing from the Web API default.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TorrentSnapshot {
+  pub hash: String,
+  pub name: String,
+  pub state: String,
+  pub progress: f64,
+  pub size: u64,
+  pub downloaded: u64,
+  pub dlspeed: u64,
+  pub upspeed: u64,
+  pub eta: i64,
+  pub num_seeds: u64,
+  pub num_leechs: u64,
+  pub ratio: f64,
+  pub category: String,
+  pub tags: String,
+  pub added_on: i64,
+  pub completion_on: i64,
+  pub save_path: String,
+  pub content_path: String,
+  pub last_activity: i64,
+  /// Seconds spent seeding.
+  pub seeding_time: i64,
+  /// Effective share ratio limit, negative when there is none.
+  pub max_ratio: f64,
+  /// Effective seeding time limit in minutes, negative when there is none.
+  pub max_seeding_time: i64,
+}
+
+impl TorrentSnapshot {
+  pub fn is_complete(&self) -> bool {
+    self.progress >= 1.0
+  }
+
+  pub fn is_errored(&self) -> bool {
+    matches!(self.state.as_str(), "error" | "missingFiles")
+  }
+
+  pub fn is_stalled(&self) -> bool {
+    self.state == "stalledDL"
+  }
+}
+
+/// Merged global transfer statistics from `sync/maindata`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ServerState {
+  pub dl_info_speed: u64,
+  pub up_info_speed: u64,
+  pub dl_info_data: u64,
+  pub up_info_data: u64,
+  pub alltime_dl: u64,
+  pub alltime_ul: u64,
+  pub free_space_on_disk: u64,
+  pub global_ratio: String,
+  pub connection_status: String,
+  pub use_alt_speed_limits: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum TorrentEvent {
+  Added(TorrentSnapshot),
+  Completed(TorrentSnapshot),
+  Errored(TorrentSnapshot),
+  StalledChanged {
+    torrent: TorrentSnapshot,
+    stalled: bool,
+  },
+  Removed {
+    hash: String,
+    name: String,
+  },
+  Milestone {
+    torrent: TorrentSnapshot,
+    percent: u8,
+  },
+  /// The torrent's size and files became known: as it's added for .torrent files, once peers
+  /// sent the metadata for magnets.
+  MetadataReceived(TorrentSnapshot),
+}
+
+/// The highest milestone progress went past since the last poll. A jump past several is still
+/// a single event.
+fn milestone(old: f64, new: f64) -> Option<u8> {
+  MILESTONES.iter().rev().copied().find(|percent| {
+    let at = f64::from(*percent) / 100.0;
+    old < at && new >= at
+  })
+}
+
+/// Latest state of every torrent, refreshed on each poll.
+pub type Torrents = Arc<HashMap<String, TorrentSnapshot>>;
+
+/// Polls `sync/maindata` and turns the deltas into [`TorrentEvent`]s.
+#[derive(Debug, Clone)]
+pub struct TorrentEvents {
+  sender: broadcast::Sender<TorrentEvent>,
+  torrents: watch::Receiver<Torrents>,
+  server_state: watch::Receiver<Arc<ServerState>>,
+}
+
+impl TorrentEvents {
+  pub fn spawn(api: TorrentApi, interval: Duration) -> Self {
+    let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+    let (torrents_tx, torrents) = watch::channel(Torrents::default());
+    let (server_state_tx, server_state) = watch::channel(Arc::new(ServerState::default()));
+
+    let events = sender.clone();
+    tokio::spawn(async move {
+      let mut poller = Poller::default();
+      let mut ticker = tokio::time::interval(interval);
+      loop {
+        ticker.tick().await;
+        match api.maindata(poller.rid).await {
+          Ok(data) => {
+            for event in poller.apply(data) {
+              // no subscribers is fine, events are only interesting to whoever listens
+              let _ = events.send(event);
+            }
+            torrents_tx.send_replace(Arc::new(poller.snapshots()));
+            server_state_tx.send_replace(Arc::new(poller.server_state()));
+          }
+          Err(err) => {
+            log::warn!("sync/maindata failed: {err}");
+            crate::status::record_error(format!("sync/maindata failed: {err}"));
+          }
+        }
+      }
+    });
+
+    TorrentEvents {
+      sender,
+      torrents,
+      server_state,
+    }
+  }
+
+  pub fn subscribe(&self) -> broadcast::Receiver<TorrentEvent> {
+    self.sender.subscribe()
+  }
+
+  /// Watch handle on the torrent list, for views that refresh without querying qBittorrent.
+  pub fn torrents(&self) -> watch::Receiver<Torrents> {
+    self.torrents.clone()
+  }
+
+  pub fn server_state(&self) -> watch::Receiver<Arc<ServerState>> {
+    self.server_state.clone()
+  }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MainData {
+  pub rid: i64,
+  #[serde(default)]
+  pub full_update: bool,
+  #[serde(default)]
+  pub torrents: HashMap<String, Map<String, Value>>,
+  #[serde(default)]
+  pub torrents_removed: Vec<String>,
+  #[serde(default)]
+  pub server_state: Map<String, Value>,
+}
+
+#[derive(Default)]
+struct Poller {
+  rid: i64,
+  raw: HashMap<String, Map<String, Value>>,
+  snapshots: HashMap<String, TorrentSnapshot>,
+  raw_server_state: Map<String, Value>,
+  server_state: ServerState,
+}
+
+impl Poller {
+  fn apply(&mut self, data: MainData) -> Vec<TorrentEvent> {
+    // the first response is the full list, which is state rather than news
+    let initial = self.rid == 0;
+    self.rid = data.rid;
+    if data.full_update {
+      self.raw.clear();
+    }
+    self.raw_server_state.extend(data.server_state);
+    match serde_json::from_value(Value::Object(self.raw_server_state.clone())) {
+      Ok(state) => self.server_state = state,
+      Err(err) => log::warn!("Could not parse the server state from sync/maindata: {err}"),
+    }
+
+    let mut events = Vec::new();
+    for hash in data.torrents_removed {
+      self.raw.remove(&hash);
+      if let Some(old) = self.snapshots.remove(&hash) {
+        events.push(TorrentEvent::Removed {
+          hash,
+          name: old.name,
+        });
+      }
+    }
+
+    for (hash, fields) in data.torrents {
+      let raw = self.raw.entry(hash.clone()).or_default();
+      raw.extend(fields);
+
+      let parsed = serde_json::from_value::<TorrentSnapshot>(Value::Object(raw.clone()));
+      let mut snapshot = match parsed {
+        Ok(snapshot) => snapshot,
+        Err(err) => {
+          // keeping the last good snapshot beats reporting the torrent as something else
+          log::warn!("Could not parse torrent {hash} from sync/maindata: {err}");
+          continue;
+        }
+      };
+      snapshot.hash = hash.clone();
+
+      match self.snapshots.insert(hash, snapshot.clone()) {
+        None if !initial => {
+          events.push(TorrentEvent::Added(snapshot.clone()));
+          if snapshot.size > 0 {
+            events.push(TorrentEvent::MetadataReceived(snapshot));
+          }
+        }
+        None => {}
+        Some(old) => {
+          if old.size == 0 && snapshot.size > 0 {
+            events.push(TorrentEvent::MetadataReceived(snapshot.clone()));
+          }
+          if let Some(percent) = milestone(old.progress, snapshot.progress) {
+            events.push(TorrentEvent::Milestone {
+              torrent: snapshot.clone(),
+              percent,
+            });
+          }
+          if !old.is_complete() && snapshot.is_complete() {
+            events.push(TorrentEvent::Completed(snapshot.clone()));
+          }
+          if !old.is_errored() && snapshot.is_errored() {
+            events.push(TorrentEvent::Errored(snapshot.clone()));
+          }
+          if old.is_stalled() != snapshot.is_stalled() {
+            let stalled = snapshot.is_stalled();
+            events.push(TorrentEvent::StalledChanged {
+              torrent: snapshot,
+              stalled,
+            });
+          }
+        }
+      }
+    }
+
+    if data.full_update && !initial {
+      // a full update replaces the list, anything not in it is gone
+      let gone: Vec<String> = self
+        .snapshots
+        .keys()
+        .filter(|hash| !self.raw.contains_key(*hash))
+        .cloned()
+        .collect();
+      for hash in gone {
+        if let Some(old) = self.snapshots.remove(&hash) {
+          events.push(TorrentEvent::Removed {
+            hash,
+            name: old.name,
+          });
+        }
+      }
+    }
+    events
+  }
+
+  fn snapshots(&self) -> HashMap<String, TorrentSnapshot> {
+    self.snapshots.clone()
+  }
+
+  fn server_state(&self) -> ServerState {
+    self.server_state.clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn main_data(value: Value) -> MainData {
+    serde_json::from_value(value).expect("valid maindata")
+  }
+
+  fn names(events: &[TorrentEvent]) -> Vec<String> {
+    events
+      .iter()
+      .map(|event| match event {
+        TorrentEvent::Added(_) => "added".to_string(),
+        TorrentEvent::Completed(_) => "completed".to_string(),
+        TorrentEvent::Errored(_) => "errored".to_string(),
+        TorrentEvent::StalledChanged { stalled, .. } => format!("stalled {stalled}"),
+        TorrentEvent::Removed { hash, .. } => format!("removed {hash}"),
+        TorrentEvent::Milestone { percent, .. } => format!("milestone {percent}"),
+        TorrentEvent::MetadataReceived(_) => "metadata".to_string(),
+      })
+      .collect()
+  }
+
+  /// A poller past its first poll, knowing a torrent `a` that is half done.
+  fn poller() -> Poller {
+    let mut poller = Poller::default();
+    let events = poller.apply(main_data(json!({
+      "rid": 1,
+      "full_update": true,
+      "torrents": { "a": { "name": "A", "size": 100, "progress": 0.5, "state": "downloading" } },
+    })));
+    assert!(events.is_empty(), "the first poll is state, not news");
+    poller
+  }
+
+  #[test]
+  fn reports_milestones() {
+    assert_eq!(milestone(0.1, 0.3), Some(25));
+    assert_eq!(milestone(0.1, 0.95), Some(90));
+    assert_eq!(milestone(0.25, 0.3), None);
+    assert_eq!(milestone(0.99, 1.0), Some(100));
+  }
+
+  #[test]
+  fn merges_deltas_into_events() {
+    let mut poller = poller();
+    let events = poller.apply(main_data(json!({
+      "rid": 2,
+      "torrents": { "a": { "progress": 1.0, "state": "uploading" } },
+    })));
+    assert_eq!(names(&events), ["milestone 100", "completed"]);
+    let a = &poller.snapshots()["a"];
+    assert_eq!((a.name.as_str(), a.size, a.hash.as_str()), ("A", 100, "a"));
+
+    let events = poller.apply(main_data(json!({ "rid": 3, "torrents_removed": ["a"] })));
+    assert_eq!(names(&events), ["removed a"]);
+    assert!(poller.snapshots().is_empty());
+  }
+
+  #[test]
+  fn reports_metadata_of_magnets_once_known() {
+    let mut poller = poller();
+    let events = poller.apply(main_data(json!({
+      "rid": 2,
+      "torrents": { "m": { "name": "m", "size": 0, "state": "metaDL" } },
+    })));
+    assert_eq!(names(&events), ["added"]);
+
+    let events = poller.apply(main_data(json!({
+      "rid": 3,
+      "torrents": { "m": { "name": "Movie", "size": 2048, "state": "stalledDL" } },
+    })));
+    assert_eq!(names(&events), ["metadata", "stalled true"]);
+
+    let events = poller.apply(main_data(json!({
+      "rid": 4,
+      "torrents": { "f": { "name": "File", "size": 10 } },
+    })));
+    assert_eq!(names(&events), ["added", "metadata"]);
+  }
+
+  #[test]
+  fn keeps_the_last_snapshot_when_a_delta_does_not_parse() {
+    let mut poller = poller();
+    let events = poller.apply(main_data(json!({
+      "rid": 2,
+      "torrents": { "a": { "progress": "soon" } },
+    })));
+    assert!(events.is_empty());
+    assert_eq!(poller.snapshots()["a"].progress, 0.5);
+  }
+
+  #[test]
+  fn full_updates_drop_missing_torrents() {
+    let mut poller = poller();
+    let events = poller.apply(main_data(json!({
+      "rid": 2,
+      "full_update": true,
+      "torrents": { "b": { "name": "B", "state": "error" } },
+    })));
+    assert_eq!(names(&events), ["added", "removed a"]);
+  }
+}
@@ -0,0 +1,268 @@
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use super::V1_HEX_LEN;
+
+// deeper nesting than this isn't a real .torrent, and would only eat the stack
+const MAX_DEPTH: usize = 64;
+
+/// What a .torrent file tells about its content, read without handing it to qBittorrent.
+#[derive(Debug, Clone, Default)]
+pub struct Metainfo {
+  /// The id qBittorrent will give the torrent, see [`super::normalize_info_hash`].
+  pub hash: String,
+  pub name: String,
+  pub size: u64,
+  /// Paths inside the torrent, `/` separated. Padding files are left out.
+  pub files: Vec<String>,
+  pub trackers: Vec<String>,
+}
+
+#[derive(Debug)]
+enum Bencode<'a> {
+  Int(i64),
+  Bytes(&'a [u8]),
+  List(Vec<Bencode<'a>>),
+  Dict(Vec<(&'a [u8], Bencode<'a>)>),
+}
+
+impl<'a> Bencode<'a> {
+  fn get(&self, key: &str) -> Option<&Bencode<'a>> {
+    match self {
+      Bencode::Dict(entries) => entries
+        .iter()
+        .find(|(name, _)| *name == key.as_bytes())
+        .map(|(_, value)| value),
+      _ => None,
+    }
+  }
+
+  fn int(&self) -> Option<i64> {
+    match self {
+      Bencode::Int(value) => Some(*value),
+      _ => None,
+    }
+  }
+
+  fn text(&self) -> Option<String> {
+    match self {
+      Bencode::Bytes(bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+      _ => None,
+    }
+  }
+
+  fn list(&self) -> &[Bencode<'a>] {
+    match self {
+      Bencode::List(items) => items,
+      _ => &[],
+    }
+  }
+}
+
+/// Reads one value starting at `pos`, returning it with the position right after it.
+fn value(bytes: &[u8], pos: usize, depth: usize) -> Option<(Bencode<'_>, usize)> {
+  if depth > MAX_DEPTH {
+    return None;
+  }
+  match *bytes.get(pos)? {
+    b'i' => {
+      let end = pos + bytes[pos..].iter().position(|b| *b == b'e')?;
+      let number = std::str::from_utf8(&bytes[pos + 1..end])
+        .ok()?
+        .parse()
+        .ok()?;
+      Some((Bencode::Int(number), end + 1))
+    }
+    b'l' => {
+      let mut items = Vec::new();
+      let mut pos = pos + 1;
+      while *bytes.get(pos)? != b'e' {
+        let (item, next) = value(bytes, pos, depth + 1)?;
+        items.push(item);
+        pos = next;
+      }
+      Some((Bencode::List(items), pos + 1))
+    }
+    b'd' => {
+      let mut entries = Vec::new();
+      let mut pos = pos + 1;
+      while *bytes.get(pos)? != b'e' {
+        let (Bencode::Bytes(key), next) = value(bytes, pos, depth + 1)? else {
+          return None;
+        };
+        let (item, next) = value(bytes, next, depth + 1)?;
+        entries.push((key, item));
+        pos = next;
+      }
+      Some((Bencode::Dict(entries), pos + 1))
+    }
+    b'0'..=b'9' => {
+      let colon = pos + bytes[pos..].iter().position(|b| *b == b':')?;
+      let len: usize = std::str::from_utf8(&bytes[pos..colon]).ok()?.parse().ok()?;
+      let end = (colon + 1).checked_add(len)?;
+      Some((Bencode::Bytes(bytes.get(colon + 1..end)?), end))
+    }
+    _ => None,
+  }
+}
+
+/// The raw bytes of the top-level `info` dictionary, which is what the info hash covers.
+fn info_bytes(bytes: &[u8]) -> Option<&[u8]> {
+  if bytes.first() != Some(&b'd') {
+    return None;
+  }
+  let mut pos = 1;
+  while *bytes.get(pos)? != b'e' {
+    let (Bencode::Bytes(key), start) = value(bytes, pos, 1)? else {
+      return None;
+    };
+    let (_, end) = value(bytes, start, 1)?;
+    if key == b"info" {
+      return Some(&bytes[start..end]);
+    }
+    pos = end;
+  }
+  None
+}
+
+/// Files of a v2 `file tree`: directories map names to subtrees, a file is `{"": {length}}`.
+fn walk_tree(tree: &Bencode, path: &str, files: &mut Vec<(String, u64)>) {
+  let Bencode::Dict(entries) = tree else {
+    return;
+  };
+  for (name, subtree) in entries {
+    let name = String::from_utf8_lossy(name);
+    if name.is_empty() {
+      let length = subtree.get("length").and_then(Bencode::int).unwrap_or(0);
+      files.push((path.to_string(), length.max(0) as u64));
+    } else if path.is_empty() {
+      walk_tree(subtree, &name, files);
+    } else {
+      walk_tree(subtree, &format!("{path}/{name}"), files);
+    }
+  }
+}
+
+/// The name, size, files, trackers and info hash of a .torrent file, `None` when it isn't one.
+pub fn parse(bytes: &[u8]) -> Option<Metainfo> {
+  let (root, _) = value(bytes, 0, 0)?;
+  let raw_info = info_bytes(bytes)?;
+  let info = root.get("info")?;
+  let name = info.get("name").and_then(Bencode::text).unwrap_or_default();
+
+  let mut files = Vec::new();
+  if let Some(length) = info.get("length").and_then(Bencode::int) {
+    files.push((name.clone(), length.max(0) as u64));
+  } else if let Some(list) = info.get("files") {
+    for file in list.list() {
+      let padding = file
+        .get("attr")
+        .and_then(Bencode::text)
+        .is_some_and(|attr| attr.contains('p'));
+      if padding {
+        continue;
+      }
+      let path: Vec<String> = file
+        .get("path")
+        .map(Bencode::list)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(Bencode::text)
+        .collect();
+      let length = file.get("length").and_then(Bencode::int).unwrap_or(0);
+      files.push((path.join("/"), length.max(0) as u64));
+    }
+  } else if let Some(tree) = info.get("file tree") {
+    walk_tree(tree, "", &mut files);
+  }
+
+  // hybrid torrents have v1 pieces too, and qBittorrent goes by their v1 hash then
+  let hash = if info.get("pieces").is_some() {
+    hex::encode(Sha1::digest(raw_info))
+  } else {
+    hex::encode(Sha256::digest(raw_info))[..V1_HEX_LEN].to_string()
+  };
+
+  let mut trackers: Vec<String> = root
+    .get("announce")
+    .and_then(Bencode::text)
+    .into_iter()
+    .collect();
+  for tier in root
+    .get("announce-list")
+    .map(Bencode::list)
+    .unwrap_or_default()
+  {
+    for tracker in tier.list().iter().filter_map(Bencode::text) {
+      if !trackers.contains(&tracker) {
+        trackers.push(tracker);
+      }
+    }
+  }
+
+  Some(Metainfo {
+    hash,
+    name,
+    size: files.iter().map(|(_, length)| length).sum(),
+    files: files.into_iter().map(|(path, _)| path).collect(),
+    trackers,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // hashes computed with Python's hashlib over the info dictionaries
+  const SINGLE: &[u8] = b"d8:announce31:http://tracker.example/announce4:infod6:lengthi1024e4:name8:test.iso12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+  const SINGLE_HASH: &str = "5f13493968157ba1c01999cb8c21f23de8a99c50";
+  const MULTI: &[u8] = b"d13:announce-listll9:udp://a/ael9:udp://b/a9:udp://a/aee4:infod5:filesld6:lengthi100e4:pathl3:sub5:a.mkveed4:attr1:p6:lengthi28e4:pathl4:.pad2:28eed6:lengthi50e4:pathl5:b.txteee4:name3:dir12:piece lengthi16384e6:pieces20:bbbbbbbbbbbbbbbbbbbbee";
+  const MULTI_HASH: &str = "449a4b3bfb1b7dbd260d08af65fb85eaeb63ce7a";
+  const V2: &[u8] = b"d4:infod9:file treed4:showd6:e1.mkvd0:d6:lengthi300eeeee12:meta versioni2e4:name4:show12:piece lengthi16384eee";
+  const V2_HASH: &str = "9cfcdb5e82a13bdb8a06f672f44ec2f93ab78c02";
+
+  #[test]
+  fn reads_a_single_file_torrent() {
+    let metainfo = parse(SINGLE).expect("valid torrent");
+    assert_eq!(metainfo.hash, SINGLE_HASH);
+    assert_eq!(metainfo.name, "test.iso");
+    assert_eq!(metainfo.size, 1024);
+    assert_eq!(metainfo.files, ["test.iso"]);
+    assert_eq!(metainfo.trackers, ["http://tracker.example/announce"]);
+  }
+
+  #[test]
+  fn reads_a_multi_file_torrent_without_padding() {
+    let metainfo = parse(MULTI).expect("valid torrent");
+    assert_eq!(metainfo.hash, MULTI_HASH);
+    assert_eq!(metainfo.name, "dir");
+    assert_eq!(metainfo.size, 150);
+    assert_eq!(metainfo.files, ["sub/a.mkv", "b.txt"]);
+    assert_eq!(metainfo.trackers, ["udp://a/a", "udp://b/a"]);
+  }
+
+  #[test]
+  fn reads_a_v2_file_tree() {
+    let metainfo = parse(V2).expect("valid torrent");
+    assert_eq!(metainfo.hash, V2_HASH);
+    assert_eq!(metainfo.size, 300);
+    assert_eq!(metainfo.files, ["show/e1.mkv"]);
+  }
+
+  #[test]
+  fn rejects_what_isnt_a_torrent() {
+    assert!(parse(b"").is_none());
+    assert!(parse(b"<html></html>").is_none());
+    assert!(parse(b"d4:name3:abce").is_none());
+    assert!(parse(&SINGLE[..SINGLE.len() - 1]).is_none());
+    assert!(parse(b"d4:infod4:name99:abcee").is_none());
+  }
+
+  #[test]
+  fn stops_at_deep_nesting() {
+    let mut nested = b"d4:info".to_vec();
+    nested.extend(b"l".repeat(MAX_DEPTH * 2));
+    nested.extend(b"e".repeat(MAX_DEPTH * 2 + 1));
+    assert!(parse(&nested).is_none());
+  }
+}
@@ -0,0 +1,145 @@
+use std::{sync::Arc, time::Duration};
+use teloxide::{prelude::*, types::InlineKeyboardMarkup, ApiError, RequestError};
+
+use crate::{
+  callback_data,
+  config::Config,
+  db::Db,
+  format::Units,
+  live::LiveViews,
+  settings,
+  torrent::events::{ServerState, TorrentEvents},
+  HandlerResult,
+};
+
+fn render(state: &ServerState, units: Units) -> String {
+  format!(
+    "📶 Transfer info\n\n⬇️ {} ⬆️ {}\nSession: {} down, {} up\nAll time: {} down, {} up\nGlobal ratio: {}\nFree disk space: {}\nAlternative speed limits: {}\nConnection: {}",
+    units.speed(state.dl_info_speed),
+    units.speed(state.up_info_speed),
+    units.bytes(state.dl_info_data),
+    units.bytes(state.up_info_data),
+    units.bytes(state.alltime_dl),
+    units.bytes(state.alltime_ul),
+    state.global_ratio,
+    units.bytes(state.free_space_on_disk),
+    if state.use_alt_speed_limits { "on" } else { "off" },
+    state.connection_status,
+  )
+}
+
+fn idle_keyboard() -> InlineKeyboardMarkup {
+  InlineKeyboardMarkup::new(vec![vec![callback_data::button(
+    "🔄 Live",
+    "transfer:live",
+  )]])
+}
+
+fn live_keyboard() -> InlineKeyboardMarkup {
+  InlineKeyboardMarkup::new(vec![vec![
+    callback_data::button("⏸ Pause", "transfer:pause"),
+    callback_data::button("⏹ Stop", "transfer:stop"),
+  ]])
+}
+
+pub async fn transfer(bot: Bot, msg: Message, events: TorrentEvents, db: Db) -> HandlerResult {
+  let state = events.server_state().borrow().clone();
+  let units = settings::of(&db, msg.from()).units();
+  bot
+    .send_message(msg.chat.id, render(&state, units))
+    .reply_markup(idle_keyboard())
+    .await?;
+  Ok(())
+}
+
+/// Keeps editing the dashboard until it is paused, stopped or `dashboard.duration_mins` ran out.
+fn spawn_live(
+  bot: Bot,
+  msg: Message,
+  events: TorrentEvents,
+  views: LiveViews,
+  config: Arc<Config>,
+  units: Units,
+) {
+  let (chat_id, message_id) = (msg.chat.id, msg.id);
+  let mut stopped = views.register(chat_id, message_id);
+  let refresh = Duration::from_secs(config.dashboard.refresh_secs.max(1));
+  let deadline =
+    tokio::time::Instant::now() + Duration::from_secs(config.dashboard.duration_mins * 60);
+  let server_state = events.server_state();
+
+  tokio::spawn(async move {
+    let mut text = msg.text().unwrap_or_default().to_string();
+    loop {
+      let state = server_state.borrow().clone();
+      let next = render(&state, units);
+      if next != text {
+        text = next;
+        let res = bot
+          .edit_message_text(chat_id, message_id, &text)
+          .reply_markup(live_keyboard())
+          .await;
+        match res {
+          Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => {}
+          Err(err) => {
+            log::warn!("Stopped the live dashboard: {err}");
+            break;
+          }
+        }
+      }
+
+      tokio::select! {
+        // whoever stopped us already updated the buttons
+        _ = stopped.changed() => return,
+        _ = tokio::time::sleep_until(deadline) => break,
+        _ = tokio::time::sleep(refresh) => {}
+      }
+    }
+
+    views.finish(chat_id, message_id);
+    let _ = bot
+      .edit_message_reply_markup(chat_id, message_id)
+      .reply_markup(idle_keyboard())
+      .await;
+  });
+}
+
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  events: TorrentEvents,
+  views: LiveViews,
+  config: Arc<Config>,
+  db: Db,
+) -> HandlerResult {
+  // the dashboard goes live in the units of whoever tapped
+  let units = settings::of(&db, Some(&q.from)).units();
+  let Some(msg) = q.message else {
+    bot.answer_callback_query(q.id).await?;
+    return Ok(());
+  };
+
+  match q.data.as_deref() {
+    Some("transfer:live") => {
+      bot
+        .edit_message_reply_markup(msg.chat.id, msg.id)
+        .reply_markup(live_keyboard())
+        .await?;
+      spawn_live(bot.clone(), msg, events, views, config, units);
+    }
+    Some("transfer:pause") => {
+      views.stop(msg.chat.id, msg.id);
+      bot
+        .edit_message_reply_markup(msg.chat.id, msg.id)
+        .reply_markup(idle_keyboard())
+        .await?;
+    }
+    Some("transfer:stop") => {
+      views.stop(msg.chat.id, msg.id);
+      bot.edit_message_reply_markup(msg.chat.id, msg.id).await?;
+    }
+    _ => {}
+  }
+  bot.answer_callback_query(q.id).await?;
+  Ok(())
+}
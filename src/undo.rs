@@ -0,0 +1,169 @@
+use std::{
+  collections::HashMap,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+  },
+  time::Duration,
+};
+use teloxide::{
+  prelude::*,
+  types::{InlineKeyboardMarkup, MessageId},
+};
+
+use crate::{
+  audit, callback_data,
+  db::Db,
+  i18n::{self, Lang},
+  torrent::{AddTorrentArg, TorrentApi},
+  HandlerResult,
+};
+
+/// How long the Undo button stays under a deletion.
+pub const UNDO_WINDOW: Duration = Duration::from_secs(60);
+
+/// Enough of a deleted torrent to add it back.
+pub struct Deleted {
+  name: String,
+  torrent_file: Option<Vec<u8>>,
+  magnet: String,
+  save_path: String,
+}
+
+impl Deleted {
+  fn arg(self) -> AddTorrentArg {
+    let mut arg = AddTorrentArg {
+      save_path: (!self.save_path.is_empty()).then_some(self.save_path),
+      ..Default::default()
+    };
+    match self.torrent_file {
+      Some(contents) => arg.files = vec![(format!("{}.torrent", self.name), contents)],
+      None => arg.urls = vec![self.magnet],
+    }
+    arg
+  }
+}
+
+/// Exports the torrents about to be deleted. Without the export API (qBittorrent before 4.5)
+/// only the magnet is kept, which re-adds fine but has to fetch the metadata again.
+pub async fn prepare(torrent: &TorrentApi, hashes: &[String]) -> Vec<Deleted> {
+  let mut deleted = Vec::new();
+  for hash in hashes {
+    let info = match torrent.info(hash).await {
+      Ok(Some(info)) => info,
+      Ok(None) => continue,
+      Err(err) => {
+        log::warn!("Could not look up {hash} before deleting it: {err}");
+        continue;
+      }
+    };
+    deleted.push(Deleted {
+      name: info.name,
+      torrent_file: torrent.export(hash).await.ok(),
+      magnet: info.magnet_uri,
+      save_path: info.save_path,
+    });
+  }
+  deleted
+}
+
+/// Recently deleted torrents, each batch behind the Undo button of one message.
+#[derive(Clone, Default)]
+pub struct Undos {
+  pending: Arc<Mutex<HashMap<u64, Vec<Deleted>>>>,
+  next_id: Arc<AtomicU64>,
+}
+
+impl Undos {
+  /// Keeps `deleted` until [`Undos::expire`] runs out, `None` when there is nothing to undo.
+  pub fn keep(&self, deleted: Vec<Deleted>) -> Option<u64> {
+    if deleted.is_empty() {
+      return None;
+    }
+    let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+    self.lock().insert(id, deleted);
+    Some(id)
+  }
+
+  /// Drops the batch after [`UNDO_WINDOW`] and takes the button off `message`.
+  pub fn expire(&self, bot: &Bot, chat: ChatId, message: MessageId, id: u64) {
+    let (bot, undos) = (bot.clone(), self.clone());
+    tokio::spawn(async move {
+      tokio::time::sleep(UNDO_WINDOW).await;
+      if undos.take(id).is_some() {
+        let _ = bot.edit_message_reply_markup(chat, message).await;
+      }
+    });
+  }
+
+  fn take(&self, id: u64) -> Option<Vec<Deleted>> {
+    self.lock().remove(&id)
+  }
+
+  fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u64, Vec<Deleted>>> {
+    self
+      .pending
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+pub fn keyboard(id: u64, lang: Lang) -> InlineKeyboardMarkup {
+  InlineKeyboardMarkup::new([[callback_data::button(
+    lang.t("undo.button"),
+    format!("undo:{id}"),
+  )]])
+}
+
+pub async fn callback(
+  bot: Bot,
+  q: CallbackQuery,
+  undos: Undos,
+  torrent: TorrentApi,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, Some(&q.from));
+  let deleted = q
+    .data
+    .as_deref()
+    .and_then(|data| data.strip_prefix("undo:"))
+    .and_then(|id| id.parse().ok())
+    .and_then(|id| undos.take(id));
+  let (Some(deleted), Some(msg)) = (deleted, q.message.as_ref()) else {
+    bot
+      .answer_callback_query(q.id)
+      .text(lang.t("undo.expired"))
+      .await?;
+    return Ok(());
+  };
+
+  let mut restored = Vec::new();
+  let mut failed = Vec::new();
+  for deleted in deleted {
+    let name = deleted.name.clone();
+    match torrent.add_torrent(&deleted.arg()).await {
+      Ok(()) => restored.push(name),
+      Err(err) => failed.push(format!("{name}: {err}")),
+    }
+  }
+  if !restored.is_empty() {
+    let action = format!("restored {} deleted torrent(s)", restored.len());
+    audit::record(&db, &q.from, action, None);
+  }
+
+  let mut lines = Vec::new();
+  if !restored.is_empty() {
+    lines.push(lang.tf("undo.restored", &[("names", &restored.join(", "))]));
+  }
+  if !failed.is_empty() {
+    lines.push(format!(
+      "{}\n• {}",
+      lang.t("undo.failed"),
+      failed.join("\n• ")
+    ));
+  }
+  let text = lines.join("\n\n");
+  bot.answer_callback_query(q.id).await?;
+  bot.edit_message_text(msg.chat.id, msg.id, text).await?;
+  Ok(())
+}
@@ -0,0 +1,113 @@
+use regex::Regex;
+use std::{path::Path, process::Stdio, sync::OnceLock};
+use teloxide::{prelude::*, types::MessageId};
+use tokio::{
+  io::{AsyncBufReadExt, BufReader},
+  process::Command,
+};
+
+use crate::torrent::{events::TorrentSnapshot, ApiResult};
+
+// rclone prints a one-line summary this often, each one updates the progress message
+const STATS_INTERVAL: &str = "15s";
+
+fn percent_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(\d{1,3})%").expect("percent pattern is valid"))
+}
+
+/// Edits the progress message when there is a chat to report to.
+struct Progress<'a> {
+  bot: &'a Bot,
+  chat: Option<(ChatId, MessageId)>,
+}
+
+impl Progress<'_> {
+  async fn start(bot: &Bot, chat: Option<ChatId>, text: String) -> Progress<'_> {
+    let chat = match chat {
+      Some(chat) => match bot.send_message(chat, text).await {
+        Ok(msg) => Some((chat, msg.id)),
+        Err(err) => {
+          log::warn!("Could not report an upload in {chat}: {err}");
+          None
+        }
+      },
+      None => None,
+    };
+    Progress { bot, chat }
+  }
+
+  async fn update(&self, text: String) {
+    if let Some((chat, id)) = self.chat {
+      // a failed edit only costs one progress step
+      let _ = self.bot.edit_message_text(chat, id, text).await;
+    }
+  }
+}
+
+/// Copies a finished torrent to `remote` (anything rclone knows, S3 and Google Drive included)
+/// with `rclone copy`, reporting progress in `chat`. The content path has to be visible to the
+/// bot, and rclone configured for the user it runs as.
+pub async fn rclone(
+  bot: &Bot,
+  chat: Option<ChatId>,
+  torrent: &TorrentSnapshot,
+  remote: &str,
+) -> ApiResult<()> {
+  let source = Path::new(&torrent.content_path);
+  // a multi-file torrent keeps its folder on the remote, a single file goes in directly
+  let destination = if tokio::fs::metadata(source).await?.is_dir() {
+    format!("{}/{}", remote.trim_end_matches('/'), torrent.name)
+  } else {
+    remote.to_string()
+  };
+
+  let name = &torrent.name;
+  let progress = Progress::start(bot, chat, format!("⬆️ Uploading {name} to {remote}…")).await;
+  let mut child = Command::new("rclone")
+    .arg("copy")
+    .arg(source)
+    .arg(&destination)
+    .args([
+      "--stats",
+      STATS_INTERVAL,
+      "--stats-one-line",
+      "--stats-log-level",
+      "NOTICE",
+    ])
+    .stdout(Stdio::null())
+    .stderr(Stdio::piped())
+    .kill_on_drop(true)
+    .spawn()?;
+
+  let mut last_line = String::new();
+  if let Some(stderr) = child.stderr.take() {
+    let mut lines = BufReader::new(stderr).lines();
+    let mut reported = None;
+    while let Some(line) = lines.next_line().await? {
+      let percent = percent_re()
+        .captures(&line)
+        .and_then(|caps| caps[1].parse::<u8>().ok())
+        .filter(|percent| Some(*percent) != reported);
+      if let Some(percent) = percent {
+        reported = Some(percent);
+        progress
+          .update(format!("⬆️ Uploading {name} to {remote}: {percent}%"))
+          .await;
+      }
+      last_line = line;
+    }
+  }
+
+  let status = child.wait().await?;
+  if !status.success() {
+    progress
+      .update(format!("❌ Uploading {name} to {remote} failed"))
+      .await;
+    return Err(format!("rclone exited with {status}: {last_line}").into());
+  }
+  progress
+    .update(format!("☁️ {name} is uploaded to {destination}"))
+    .await;
+  Ok(())
+}
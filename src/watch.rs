@@ -0,0 +1,173 @@
+use std::time::{Duration, Instant};
+use teloxide::{prelude::*, types::InlineKeyboardMarkup, ApiError, RequestError};
+
+use crate::{
+  db::Db,
+  format::{format_eta, format_trackers, Units},
+  i18n::{self, Lang},
+  live::{stop_button, LiveViews},
+  milestones,
+  parental::Parental,
+  settings, speed_limit,
+  torrent::{
+    events::{TorrentEvents, TorrentSnapshot},
+    TorrentApi,
+  },
+  HandlerResult,
+};
+
+// nobody stares at a progress bar for longer, and it keeps forgotten views from piling up
+const MAX_WATCH: Duration = Duration::from_secs(60 * 60);
+// trackers answer every few minutes at most, asking on each refresh would only add requests
+const TRACKER_REFRESH: Duration = Duration::from_secs(60);
+
+fn render(torrent: &TorrentSnapshot, trackers: Option<&str>, units: Units, lang: Lang) -> String {
+  let mut text = lang.tf(
+    "watch.status",
+    &[
+      ("name", &torrent.name),
+      ("state", &torrent.state),
+      ("progress", &format!("{:.1}", torrent.progress * 100.0)),
+      ("dlspeed", &units.speed(torrent.dlspeed)),
+      ("upspeed", &units.speed(torrent.upspeed)),
+      ("eta", &format_eta(torrent.eta)),
+      ("seeds", &torrent.num_seeds),
+      ("leechers", &torrent.num_leechs),
+    ],
+  );
+  if let Some(trackers) = trackers {
+    text.push_str(&format!("\n\n{trackers}"));
+  }
+  text
+}
+
+/// Failing trackers and their messages, so "unregistered torrent" shows up without the web UI.
+async fn tracker_status(api: &TorrentApi, hash: &str, lang: Lang) -> Option<String> {
+  match api.trackers(hash).await {
+    Ok(trackers) => format_trackers(&trackers, lang),
+    Err(err) => {
+      log::warn!("Could not fetch the trackers of {hash}: {err}");
+      None
+    }
+  }
+}
+
+fn keyboard(hash: &str, lang: Lang) -> InlineKeyboardMarkup {
+  let mut rows = stop_button().inline_keyboard;
+  rows.push(vec![
+    milestones::button(hash, lang),
+    speed_limit::button(hash, lang),
+  ]);
+  InlineKeyboardMarkup::new(rows)
+}
+
+/// Finds a torrent by full hash or an unambiguous hash prefix.
+pub fn find<'a>(torrents: &'a [&'a TorrentSnapshot], hash: &str) -> Option<&'a TorrentSnapshot> {
+  let hash = hash.to_lowercase();
+  let mut matches = torrents
+    .iter()
+    .filter(|torrent| torrent.hash.starts_with(&hash));
+  match (matches.next(), matches.next()) {
+    (Some(torrent), None) => Some(torrent),
+    _ => None,
+  }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn watch(
+  bot: Bot,
+  msg: Message,
+  hash: String,
+  api: TorrentApi,
+  events: TorrentEvents,
+  views: LiveViews,
+  parental: Parental,
+  db: Db,
+) -> HandlerResult {
+  let lang = i18n::lang(&db, msg.from());
+  let settings = settings::of(&db, msg.from());
+  let refresh = Duration::from_secs(settings.watch_refresh_secs.max(1));
+  let units = settings.units();
+  let mut torrents = events.torrents();
+  let Some(hash) = ({
+    let current = torrents.borrow();
+    let all: Vec<_> = current
+      .values()
+      .filter(|torrent| !parental.hides(msg.chat.id, torrent))
+      .collect();
+    find(&all, hash.trim()).map(|torrent| torrent.hash.clone())
+  }) else {
+    bot
+      .send_message(msg.chat.id, lang.t("watch.not_found"))
+      .await?;
+    return Ok(());
+  };
+
+  let mut trackers = tracker_status(&api, &hash, lang).await;
+  let mut trackers_at = Instant::now();
+  let mut text = render(&torrents.borrow()[&hash], trackers.as_deref(), units, lang);
+  let sent = bot
+    .send_message(msg.chat.id, &text)
+    .reply_markup(keyboard(&hash, lang))
+    .await?;
+  let (chat_id, message_id) = (sent.chat.id, sent.id);
+  let mut stopped = views.register(chat_id, message_id);
+
+  tokio::spawn(async move {
+    let deadline = tokio::time::Instant::now() + MAX_WATCH;
+    loop {
+      tokio::select! {
+        _ = stopped.changed() => break,
+        _ = tokio::time::sleep_until(deadline) => break,
+        _ = tokio::time::sleep(refresh) => {}
+      }
+      // the poller keeps the list fresh, just pick up whatever it has now
+      let Some(torrent) = torrents.borrow_and_update().get(&hash).cloned() else {
+        let _ = bot
+          .edit_message_text(chat_id, message_id, lang.t("watch.removed"))
+          .await;
+        break;
+      };
+
+      if trackers_at.elapsed() >= TRACKER_REFRESH {
+        trackers = tracker_status(&api, &hash, lang).await;
+        trackers_at = Instant::now();
+      }
+      let next = render(&torrent, trackers.as_deref(), units, lang);
+      if next != text {
+        text = next;
+        let res = bot
+          .edit_message_text(chat_id, message_id, &text)
+          .reply_markup(keyboard(&hash, lang))
+          .await;
+        match res {
+          Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => {}
+          Err(err) => {
+            log::warn!("Stopped watching {hash}: {err}");
+            break;
+          }
+        }
+      }
+      if torrent.is_complete() {
+        break;
+      }
+    }
+
+    views.finish(chat_id, message_id);
+    // drop the Stop button, the message keeps its last state
+    let _ = bot.edit_message_text(chat_id, message_id, &text).await;
+  });
+  Ok(())
+}
+
+pub async fn callback(bot: Bot, q: CallbackQuery, views: LiveViews, db: Db) -> HandlerResult {
+  if let Some(msg) = &q.message {
+    views.stop(msg.chat.id, msg.id);
+  }
+  let lang = i18n::lang(&db, Some(&q.from));
+  bot
+    .answer_callback_query(q.id)
+    .text(lang.t("watch.stopped"))
+    .await?;
+  Ok(())
+}
@@ -0,0 +1,151 @@
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+  sync::Arc,
+  time::Duration,
+};
+use teloxide::prelude::*;
+
+use crate::{
+  config::{Config, GuardConfig, WatchFolderConfig},
+  db::Db,
+  features::Features,
+  guard::{self, Candidate, Rejection},
+  i18n::{self, Lang},
+  torrent::{metainfo, AddTorrentArg, TorrentApi},
+};
+
+/// .torrent files in `dir` with their current size.
+async fn scan(dir: &Path) -> std::io::Result<HashMap<PathBuf, u64>> {
+  let mut found = HashMap::new();
+  let mut entries = tokio::fs::read_dir(dir).await?;
+  while let Some(entry) = entries.next_entry().await? {
+    let path = entry.path();
+    let is_torrent = path
+      .extension()
+      .is_some_and(|ext| ext.eq_ignore_ascii_case("torrent"));
+    let metadata = entry.metadata().await?;
+    if is_torrent && metadata.is_file() {
+      found.insert(path, metadata.len());
+    }
+  }
+  Ok(found)
+}
+
+/// Renames `path` to `<name>.<suffix>` so it isn't picked up again.
+async fn retire(path: &Path, suffix: &str) {
+  let mut retired = path.as_os_str().to_owned();
+  retired.push(format!(".{suffix}"));
+  if let Err(err) = tokio::fs::rename(path, &retired).await {
+    log::error!("Could not rename {}: {err}", path.display());
+  }
+}
+
+/// Why a file could not be added, told to each chat in its own language.
+enum Failure {
+  Rejected(Rejection),
+  Error(String),
+}
+
+impl Failure {
+  fn message(&self, lang: Lang) -> String {
+    match self {
+      Failure::Rejected(rejection) => rejection.message(lang),
+      Failure::Error(err) => err.clone(),
+    }
+  }
+}
+
+async fn add(torrent: &TorrentApi, guard: &GuardConfig, path: &Path) -> Result<(), Failure> {
+  let contents = tokio::fs::read(path)
+    .await
+    .map_err(|err| Failure::Error(err.to_string()))?;
+  let candidate = metainfo::parse(&contents)
+    .map(|metainfo| Candidate::from_metainfo(&metainfo))
+    .unwrap_or_default();
+  let rejection = guard::check(torrent, guard, &candidate)
+    .await
+    .map_err(|err| Failure::Error(err.to_string()))?;
+  if let Some(rejection) = rejection {
+    return Err(Failure::Rejected(rejection));
+  }
+  let arg = AddTorrentArg {
+    files: vec![(file_name(path), contents)],
+    ..Default::default()
+  };
+  torrent
+    .add_torrent(&arg)
+    .await
+    .map_err(|err| Failure::Error(err.to_string()))
+}
+
+fn file_name(path: &Path) -> String {
+  path
+    .file_name()
+    .map(|name| name.to_string_lossy().into_owned())
+    .unwrap_or_default()
+}
+
+/// Adds .torrent files dropped into `watch_folder.path`, e.g. by Syncthing. A file is picked up
+/// once its size stayed the same for one interval, then renamed to `.added` or `.failed`; the
+/// add policy turning it down counts as failing.
+pub fn spawn(bot: Bot, torrent: TorrentApi, config: Arc<Config>, db: Db, features: Features) {
+  let Some(folder) = config.watch_folder.clone() else {
+    return;
+  };
+  tokio::spawn(async move {
+    let WatchFolderConfig {
+      path,
+      interval_secs,
+      chat_id,
+    } = folder;
+    let chats: Vec<ChatId> = match chat_id {
+      Some(chat_id) => vec![ChatId(chat_id)],
+      None => config.notify.chat_ids.iter().copied().map(ChatId).collect(),
+    };
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+    let mut pending = HashMap::new();
+    loop {
+      ticker.tick().await;
+      if features.in_maintenance() {
+        continue;
+      }
+      let found = match scan(&path).await {
+        Ok(found) => found,
+        Err(err) => {
+          log::warn!("Could not scan {}: {err}", path.display());
+          continue;
+        }
+      };
+
+      // anything still growing is left for the next round
+      let ready: Vec<PathBuf> = found
+        .iter()
+        .filter(|(file, size)| pending.get(*file) == Some(*size))
+        .map(|(file, _)| file.clone())
+        .collect();
+      pending = found;
+
+      for file in ready {
+        pending.remove(&file);
+        let name = file_name(&file);
+        let result = add(&torrent, &config.guard, &file).await;
+        let text = |lang: Lang| match &result {
+          Ok(()) => lang.tf("watch_folder.added", &[("name", &name)]),
+          Err(failure) => lang.tf(
+            "watch_folder.failed",
+            &[("name", &name), ("error", &failure.message(lang))],
+          ),
+        };
+        retire(&file, if result.is_ok() { "added" } else { "failed" }).await;
+        log::info!("{}", text(Lang::En));
+        for chat in &chats {
+          let text = text(i18n::chat_lang(&db, *chat));
+          if let Err(err) = bot.send_message(*chat, text).await {
+            log::warn!("Could not announce in {chat}: {err}");
+          }
+        }
+      }
+    }
+  });
+}
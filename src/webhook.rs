@@ -0,0 +1,170 @@
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+  features::{Feature, Features},
+  torrent::events::{TorrentEvent, TorrentEvents},
+};
+
+/// An outbound webhook fired on torrent lifecycle events.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Webhook {
+  pub url: String,
+  /// Signs the body as `X-ChatQBit-Signature: sha256=<hex hmac>` when set.
+  #[serde(default)]
+  pub secret: Option<String>,
+  /// Event names to send (`added`, `metadata`, `completed`, `errored`, `stalled`, `removed`,
+  /// `milestone`), all when empty.
+  #[serde(default)]
+  pub events: Vec<String>,
+  #[serde(default)]
+  pub format: WebhookFormat,
+}
+
+/// Body shape, so chat services can take the events without a relay in between.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+  /// The full event as JSON.
+  #[default]
+  Json,
+  /// `{"text": ...}` for Slack incoming webhooks (and Mattermost, Rocket.Chat).
+  Slack,
+  /// `{"content": ...}` for Discord webhooks.
+  Discord,
+  /// A plain text body for an ntfy topic URL.
+  Ntfy,
+}
+
+impl Webhook {
+  fn wants(&self, event: &str) -> bool {
+    self.events.is_empty() || self.events.iter().any(|wanted| wanted == event)
+  }
+}
+
+fn payload(event: &TorrentEvent) -> (&'static str, Value) {
+  let (name, torrent) = match event {
+    TorrentEvent::Added(torrent) => ("added", json!(torrent)),
+    TorrentEvent::MetadataReceived(torrent) => ("metadata", json!(torrent)),
+    TorrentEvent::Completed(torrent) => ("completed", json!(torrent)),
+    TorrentEvent::Errored(torrent) => ("errored", json!(torrent)),
+    TorrentEvent::StalledChanged { torrent, stalled } => {
+      let mut torrent = json!(torrent);
+      torrent["stalled"] = json!(stalled);
+      ("stalled", torrent)
+    }
+    TorrentEvent::Removed { hash, name } => ("removed", json!({ "hash": hash, "name": name })),
+    TorrentEvent::Milestone { torrent, percent } => {
+      let mut torrent = json!(torrent);
+      torrent["milestone"] = json!(percent);
+      ("milestone", torrent)
+    }
+  };
+  let body = json!({
+    "event": name,
+    "timestamp": chrono::Utc::now().timestamp(),
+    "torrent": torrent,
+  });
+  (name, body)
+}
+
+/// One line for the formats that show text instead of the JSON.
+fn summary(event: &TorrentEvent) -> String {
+  match event {
+    TorrentEvent::Added(torrent) => format!("➕ {} was added", torrent.name),
+    TorrentEvent::MetadataReceived(torrent) => {
+      format!("📄 {} has its metadata", torrent.name)
+    }
+    TorrentEvent::Completed(torrent) => format!("✅ {} has finished downloading", torrent.name),
+    TorrentEvent::Errored(torrent) => format!("❌ {} has errored", torrent.name),
+    TorrentEvent::StalledChanged {
+      torrent,
+      stalled: true,
+    } => format!("⚠️ {} has stalled", torrent.name),
+    TorrentEvent::StalledChanged { torrent, .. } => format!("▶️ {} is moving again", torrent.name),
+    TorrentEvent::Removed { name, .. } => format!("🗑 {name} was removed"),
+    TorrentEvent::Milestone { torrent, percent } => {
+      format!("📶 {} is {percent}% done", torrent.name)
+    }
+  }
+}
+
+fn encode(format: WebhookFormat, event: &TorrentEvent, json: &str) -> (&'static str, String) {
+  match format {
+    WebhookFormat::Json => ("application/json", json.to_string()),
+    WebhookFormat::Slack => (
+      "application/json",
+      json!({ "text": summary(event) }).to_string(),
+    ),
+    WebhookFormat::Discord => (
+      "application/json",
+      json!({ "content": summary(event) }).to_string(),
+    ),
+    WebhookFormat::Ntfy => ("text/plain; charset=utf-8", summary(event)),
+  }
+}
+
+/// A receiver that hangs gives up after this long instead of holding up the next event.
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn sign(secret: &str, body: &[u8]) -> String {
+  let mut mac =
+    Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+  mac.update(body);
+  format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+pub fn spawn(webhooks: Vec<Webhook>, features: Features, events: TorrentEvents) {
+  if webhooks.is_empty() {
+    return;
+  }
+  let mut events = events.subscribe();
+  let http = match reqwest::Client::builder().timeout(SEND_TIMEOUT).build() {
+    Ok(http) => http,
+    Err(err) => {
+      log::error!("Webhooks are off, no HTTP client: {err}");
+      return;
+    }
+  };
+
+  tokio::spawn(async move {
+    loop {
+      let event = match events.recv().await {
+        Ok(event) => event,
+        Err(RecvError::Lagged(skipped)) => {
+          log::warn!("Webhooks skipped {skipped} events");
+          continue;
+        }
+        Err(RecvError::Closed) => break,
+      };
+      if !features.enabled(Feature::Webhooks) || features.in_maintenance() {
+        continue;
+      }
+
+      let (name, json) = payload(&event);
+      let json = json.to_string();
+      for webhook in webhooks.iter().filter(|webhook| webhook.wants(name)) {
+        let (content_type, body) = encode(webhook.format, &event, &json);
+        let mut request = http
+          .post(&webhook.url)
+          .header(reqwest::header::CONTENT_TYPE, content_type)
+          .header("X-ChatQBit-Event", name);
+        if let Some(secret) = &webhook.secret {
+          request = request.header("X-ChatQBit-Signature", sign(secret, body.as_bytes()));
+        }
+        let request = request.body(body);
+        // each on its own, a slow receiver doesn't delay the others
+        let url = webhook.url.clone();
+        tokio::spawn(async move {
+          if let Err(err) = request.send().await.and_then(|res| res.error_for_status()) {
+            log::warn!("Webhook {url} failed: {err}");
+          }
+        });
+      }
+    }
+  });
+}